@@ -1,6 +1,6 @@
 use std::path::Path;
 use std::fs::File;
-use std::io::{BufReader, BufRead};
+use std::io::{self, BufReader, BufRead};
 use std::collections::{HashSet, HashMap};
 use regex::Regex;
 
@@ -19,15 +19,19 @@ pub struct Dicts {
     pub suffixes: HashMap<String, Vec<Entry>>,
 }
 
-fn load_dictionary(path: &str) -> HashMap<String, Vec<Entry>> {
-    let path = Path::new(path);
-
-    let mut file = match File::open(&path) {
-        Err(error) => panic!("Could not open {}: {}", path.display(), error),
-        Ok(file) => file,
-    };
+impl Dicts {
+    /// Loads `dictPrefixes`, `dictStems` and `dictSuffixes` from a directory.
+    pub fn load(directory: &Path) -> io::Result<Dicts> {
+        Ok(Dicts {
+            prefixes: load_dictionary(&directory.join("dictPrefixes"))?,
+            stems: load_dictionary(&directory.join("dictStems"))?,
+            suffixes: load_dictionary(&directory.join("dictSuffixes"))?,
+        })
+    }
+}
 
-    let reader = BufReader::new(file);
+fn load_dictionary(path: &Path) -> io::Result<HashMap<String, Vec<Entry>>> {
+    let reader = BufReader::new(File::open(path)?);
 
     let mut entries: HashMap<String, Vec<Entry>> = HashMap::new();
 
@@ -38,8 +42,6 @@ fn load_dictionary(path: &str) -> HashMap<String, Vec<Entry>> {
 
     for line in reader.lines() {
         if let Ok(line) = line {
-            println!("{}", line);
-
             if is_lemma_definition(&line) {
                 let lemma = String::from(&line.as_str()[3..]);
 
@@ -101,7 +103,7 @@ fn load_dictionary(path: &str) -> HashMap<String, Vec<Entry>> {
                     lemma: currentLemmaId.to_owned(),
                 };
 
-                match entries.get(&key) {
+                match entries.get_mut(&key) {
                     Some(existing) => {
                         existing.push(entry);
                     },
@@ -113,7 +115,7 @@ fn load_dictionary(path: &str) -> HashMap<String, Vec<Entry>> {
         }
     }
 
-    entries
+    Ok(entries)
 }
 
 fn is_lemma_definition(line: &String) -> bool {
@@ -129,17 +131,6 @@ fn clean_gloss(gloss: String) -> String {
     gloss
 }
 
-fn remove_kashida(text: &String) {
-    str::replace(text, '\u{0640}', "");
-}
-
-fn remove_fatHatAn(text: &String) -> String {
-    text.chars().filter(|c| match c {
-        '\u{064B}'..='\u{0652}' => false,
-        _ => true
-    }).collect()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,20 +1,55 @@
-use std::collections::{HashSet, HashMap};
-use crate::ara::aramorph::dict::{Entry, Dicts};
+use std::io;
+use std::path::Path;
+use crate::ara::aramorph::buckwalter::to_buckwalter;
+use crate::ara::aramorph::dict::Dicts;
 use crate::ara::aramorph::table::Tables;
+use crate::normalize::Normalization;
 
-mod buckwalter;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod buckwalter;
 
 mod dict;
 mod table;
 
-struct Solution {
-    vocalization: String,
-    lemma: String,
-    pos: String,
-    gloss: String,
+/// One analysis of a word, with its tags in Buckwalter notation.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Solution {
+    pub vocalization: String,
+    pub lemma: String,
+    pub pos: String,
+    pub gloss: String,
+}
+
+/// The Buckwalter analyzer with its dictionaries and compatibility tables.
+pub struct Aramorph {
+    dicts: Dicts,
+    tables: Tables,
+}
+
+impl Aramorph {
+    /// Loads the dictionaries and tables from a directory laid out like the
+    /// `data` directory of this module.
+    pub fn load(directory: &Path) -> io::Result<Aramorph> {
+        Ok(Aramorph {
+            dicts: Dicts::load(directory)?,
+            tables: Tables::load(directory)?,
+        })
+    }
+
+    /// Analyzes a word in Arabic script. The word is normalised with
+    /// `Normalization::arabic` before it is transliterated and looked up.
+    pub fn analyze(&self, word: &str) -> Vec<Solution> {
+        let word = Normalization::arabic().apply(word).text;
+
+        analyze(to_buckwalter(&word), &self.dicts, &self.tables)
+    }
 }
 
-pub fn analyze(word: String, dict: Dicts, table: Tables) -> Vec<Solution> {
+/// Analyzes a word in Buckwalter transliteration without diacritics.
+pub fn analyze(word: String, dict: &Dicts, table: &Tables) -> Vec<Solution> {
     let mut solutions: Vec<Solution> = Vec::new();
 
     for segmentation in segment_word(word) {
@@ -23,7 +58,7 @@ pub fn analyze(word: String, dict: Dicts, table: Tables) -> Vec<Solution> {
                 for stem in dict.stems.get(&segmentation.stem).unwrap() {
                     if table.AB.contains(format!("{} {}", prefix.category, stem.category).as_str()) {
                         for suffix in dict.suffixes.get(&segmentation.suffix).unwrap() {
-                            if table.AC.contains(format!("{} {}", prefix.category, suffix.category).as_str()) && table.BC.contains(format!("{}, {}", stem.category, suffix.category).as_str()) {
+                            if table.AC.contains(format!("{} {}", prefix.category, suffix.category).as_str()) && table.BC.contains(format!("{} {}", stem.category, suffix.category).as_str()) {
                                 solutions.push(Solution {
                                     vocalization: format!("{}{}{}", prefix.vocabulary, stem.vocabulary, suffix.vocabulary),
                                     pos: format!("{}{}{}", prefix.pos, stem.pos, suffix.pos),
@@ -51,25 +86,20 @@ struct SegmentedWord {
 }
 
 fn segment_word(word: String) -> Vec<SegmentedWord> {
-    let mut prefix_length = 0;
-    let mut suffix_length = 0;
+    let letters: Vec<char> = word.chars().collect();
+    let slice = |range: std::ops::Range<usize>| letters[range].iter().collect::<String>();
 
     let mut segmentations: Vec<SegmentedWord> = Vec::new();
 
-    while prefix_length <= 4 {
-        let prefix = &word[..prefix_length];
-        let mut stem_length = prefix.len() - word.len();
-
-        suffix_length = 0;
+    for prefix_length in 0..=4.min(letters.len()) {
+        let mut stem_length = letters.len() - prefix_length;
+        let mut suffix_length = 0;
 
         while stem_length >= 1 && suffix_length <= 6 {
-            let stem = &word[prefix_length..(prefix_length + stem_length)];
-            let suffix = &word[(prefix_length + stem_length)..];
-
             segmentations.push(SegmentedWord {
-                prefix: String::from(prefix),
-                stem: String::from(stem),
-                suffix: String::from(suffix),
+                prefix: slice(0..prefix_length),
+                stem: slice(prefix_length..prefix_length + stem_length),
+                suffix: slice(prefix_length + stem_length..letters.len()),
             });
 
             stem_length -= 1;
@@ -79,3 +109,34 @@ fn segment_word(word: String) -> Vec<SegmentedWord> {
 
     segmentations
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aramorph() -> Aramorph {
+        Aramorph::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src/ara/aramorph/data")).unwrap()
+    }
+
+    #[test]
+    fn it_segments_words() {
+        let segmentations = segment_word(String::from("wktb"));
+
+        assert_eq!(segmentations.len(), 10);
+        assert_eq!(segmentations.iter().any(|s| s.prefix == "w" && s.stem == "ktb" && s.suffix.is_empty()), true);
+        assert_eq!(segmentations.iter().all(|s| !s.stem.is_empty()), true);
+    }
+
+    #[test]
+    fn it_analyzes_arabic_words() {
+        let solutions = aramorph().analyze("كتب");
+
+        assert_eq!(solutions.is_empty(), false);
+        assert_eq!(solutions.iter().any(|solution| solution.lemma.starts_with("katab")), true);
+    }
+
+    #[test]
+    fn it_finds_no_solutions_for_unknown_words() {
+        assert_eq!(aramorph().analyze("1234").is_empty(), true);
+    }
+}
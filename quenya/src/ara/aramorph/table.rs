@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 use std::path::Path;
 use std::fs::File;
-use std::io::{BufReader, BufRead};
+use std::io::{self, BufReader, BufRead};
 
 pub struct Tables {
     pub AB: HashSet<String>,
@@ -9,17 +9,21 @@ pub struct Tables {
     pub BC: HashSet<String>,
 }
 
-pub fn load_table(path: &String) -> HashSet<String> {
-    let mut table = HashSet::new();
-
-    let path = Path::new(path);
+impl Tables {
+    /// Loads `tableAB`, `tableAC` and `tableBC` from a directory.
+    pub fn load(directory: &Path) -> io::Result<Tables> {
+        Ok(Tables {
+            AB: load_table(&directory.join("tableAB"))?,
+            AC: load_table(&directory.join("tableAC"))?,
+            BC: load_table(&directory.join("tableBC"))?,
+        })
+    }
+}
 
-    let mut file = match File::open(&path) {
-        Err(error) => panic!("Could not open {}: {}", path.display(), error),
-        Ok(file) => file,
-    };
+pub fn load_table(path: &Path) -> io::Result<HashSet<String>> {
+    let mut table = HashSet::new();
 
-    let reader = BufReader::new(file);
+    let reader = BufReader::new(File::open(path)?);
 
     for line in reader.lines() {
         if let Ok(line) = line {
@@ -27,5 +31,5 @@ pub fn load_table(path: &String) -> HashSet<String> {
         }
     }
 
-    table
+    Ok(table)
 }
@@ -1 +1,199 @@
-mod aramorph;
+use std::path::Path;
+use std::io;
+use wn::pos::Part;
+use crate::ara::aramorph::{Aramorph, Solution};
+use crate::ara::aramorph::buckwalter::to_buckwalter;
+use crate::features::Features;
+use crate::hunspell::Dictionary;
+use crate::language::{Analysis, Capability, Language, analyze_with_lexicon, inflect_with_lexicon};
+use crate::stem::Algorithm;
+use crate::lexicon::Lexicon;
+
+pub mod aramorph;
+
+pub struct Arabic {
+    aramorph: Option<Aramorph>,
+    lexicon: Option<Lexicon>,
+    dictionary: Option<Dictionary>,
+}
+
+impl Arabic {
+    pub fn new(aramorph: Option<Aramorph>, lexicon: Option<Lexicon>) -> Arabic {
+        Arabic { aramorph, lexicon, dictionary: None }
+    }
+
+    pub fn with_dictionary(mut self, dictionary: Dictionary) -> Arabic {
+        self.dictionary = Some(dictionary);
+        self
+    }
+
+    /// Loads the Buckwalter analyzer from the `aramorph` subdirectory of
+    /// `data_dir`, if there is one.
+    pub fn load(data_dir: &Path, lexicon: Option<Lexicon>) -> io::Result<Arabic> {
+        let directory = data_dir.join("aramorph");
+
+        let aramorph = if directory.is_dir() {
+            Some(Aramorph::load(&directory)?)
+        } else {
+            None
+        };
+
+        Ok(Arabic::new(aramorph, lexicon))
+    }
+}
+
+impl Language for Arabic {
+    fn code(&self) -> &str {
+        "ara"
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        match (self.lexicon.is_some(), self.aramorph.is_some(), self.dictionary.is_some()) {
+            (true, _, true) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Inflect, Capability::Transliterate, Capability::SpellCheck, Capability::Stem],
+            (true, _, false) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Inflect, Capability::Transliterate, Capability::Stem],
+            (false, _, true) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Transliterate, Capability::SpellCheck, Capability::Stem],
+            (false, true, false) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Transliterate, Capability::Stem],
+            (false, false, false) => &[Capability::Tokenize, Capability::Transliterate, Capability::Stem],
+        }
+    }
+
+    fn lexicon(&self) -> Option<&Lexicon> {
+        self.lexicon.as_ref()
+    }
+
+    fn dictionary(&self) -> Option<&Dictionary> {
+        self.dictionary.as_ref()
+    }
+
+    fn analyze_word(&self, word: &str) -> Vec<Analysis> {
+        let mut analyses: Vec<Analysis> = Vec::new();
+
+        if let Some(aramorph) = &self.aramorph {
+            analyses.extend(aramorph.analyze(word).iter().map(solution_to_analysis));
+        }
+
+        if let Some(lexicon) = &self.lexicon {
+            analyses.extend(analyze_with_lexicon(lexicon, word));
+        }
+
+        if let Some(dictionary) = &self.dictionary {
+            for analysis in dictionary.analyze(word) {
+                if !analyses.contains(&analysis) {
+                    analyses.push(analysis);
+                }
+            }
+        }
+
+        analyses
+    }
+
+    /// Inflects through the lexicon, as the Buckwalter analyzer only analyses.
+    fn inflect(&self, lemma: &str, pos: Part, features: &Features) -> Vec<String> {
+        match &self.lexicon {
+            Some(lexicon) => inflect_with_lexicon(lexicon, lemma, pos, features),
+            None => Vec::new(),
+        }
+    }
+
+    /// Transliterates into the Buckwalter scheme.
+    fn transliterate(&self, text: &str) -> Option<String> {
+        Some(to_buckwalter(&String::from(text)))
+    }
+
+    fn stem(&self, word: &str) -> Option<String> {
+        Some(Algorithm::Arabic.stem(word))
+    }
+}
+
+/// Derives the part of speech and features from the Buckwalter tags of a
+/// solution, such as `katab/VERB_PERFECT+a/PVSUFF_SUBJ:3MS`.
+fn solution_to_analysis(solution: &Solution) -> Analysis {
+    let mut pos = None;
+    let mut features = Features::new();
+
+    for tag in solution.pos.split('+').filter_map(|part| part.rsplit('/').next()) {
+        match tag {
+            "VERB_PERFECT" | "PV" => {
+                pos = Some(Part::Verb);
+                features.insert("Aspect", "Perf");
+            }
+            "VERB_IMPERFECT" | "IV" => {
+                pos = Some(Part::Verb);
+                features.insert("Aspect", "Imp");
+            }
+            "VERB_IMPERATIVE" | "CV" => {
+                pos = Some(Part::Verb);
+                features.insert("Mood", "Imp");
+            }
+            "NOUN" | "NOUN_PROP" => pos = Some(Part::Noun),
+            "ADJ" => pos = Some(Part::Adjective),
+            "ADV" => pos = Some(Part::Adverb),
+            "DET" => features.insert("Definite", "Def"),
+            _ => {
+                if let Some(subject) = tag.strip_prefix("PVSUFF_SUBJ:").or_else(|| tag.strip_prefix("IVSUFF_SUBJ:")) {
+                    add_subject_features(subject, &mut features);
+                }
+            }
+        }
+    }
+
+    let mut analysis = Analysis::new(&solution.lemma, pos, features);
+    analysis.tag = Some(solution.pos.clone());
+    analysis.gloss = Some(solution.gloss.clone());
+
+    analysis
+}
+
+/// Reads subject markers such as `3MS` (third person masculine singular).
+fn add_subject_features(subject: &str, features: &mut Features) {
+    for marker in subject.chars() {
+        match marker {
+            '1' => features.insert("Person", "1"),
+            '2' => features.insert("Person", "2"),
+            '3' => features.insert("Person", "3"),
+            'M' => features.insert("Gender", "Masc"),
+            'F' => features.insert("Gender", "Fem"),
+            'S' => features.insert("Number", "Sing"),
+            'D' => features.insert("Number", "Dual"),
+            'P' => features.insert("Number", "Plur"),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_buckwalter_tags() {
+        let solution = Solution {
+            vocalization: String::from("kataba"),
+            lemma: String::from("katab-u_1"),
+            pos: String::from("katab/VERB_PERFECT+a/PVSUFF_SUBJ:3MS"),
+            gloss: String::from("write"),
+        };
+
+        let analysis = solution_to_analysis(&solution);
+
+        assert_eq!(analysis.pos, Some(Part::Verb));
+        assert_eq!(analysis.features.to_string(), "Aspect=Perf|Gender=Masc|Number=Sing|Person=3");
+    }
+
+    #[test]
+    fn it_analyzes_with_aramorph() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/ara/aramorph/data");
+        let arabic = Arabic::new(Some(Aramorph::load(&directory).unwrap()), None);
+
+        assert_eq!(arabic.supports(Capability::Analyze), true);
+        assert_eq!(arabic.lemmatize("كتب").contains(&String::from("katab-u_1")), true);
+    }
+
+    #[test]
+    fn it_transliterates_without_data() {
+        let arabic = Arabic::new(None, None);
+
+        assert_eq!(arabic.supports(Capability::Analyze), false);
+        assert_eq!(arabic.transliterate("فيسبوك"), Some(String::from("fysbwk")));
+    }
+}
@@ -1,10 +1,69 @@
-use std::collections::HashMap;
+use quenya::conllu::read_annotated_text;
+use quenya::evaluate::{evaluate_language, evaluate_pipeline};
+use quenya::omni::{OmniError, Omniglot};
+use quenya::pipeline::Pipeline;
+use std::env;
+use std::fs::File;
+use std::process;
 
-mod lexicon;
-mod segment;
-mod jpn;
+const USAGE: &str = "usage: quenya [evaluate <gold.conllu> [language]]";
 
 fn main() {
-    let lexemes = HashMap::new();
-    let x = lexicon::Lexicon { lexemes };
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        None => list_languages(),
+        Some("evaluate") => evaluate(&args[1..]),
+        Some(_) => fail(USAGE),
+    }
+}
+
+fn list_languages() {
+    let omni = match Omniglot::from_env() {
+        Ok(omni) => omni,
+        Err(error) => fail(&error.to_string()),
+    };
+
+    for code in omni.languages() {
+        println!("{}", code);
+    }
+}
+
+/// Annotates the text of a gold CoNLL-U file and prints how the annotation
+/// compares, through the standard pipeline or one language module.
+fn evaluate(args: &[String]) {
+    let (path, language) = match args {
+        [path] => (path, None),
+        [path, language] => (path, Some(language.as_str())),
+        _ => fail(USAGE),
+    };
+
+    // Without configured languages, the built-in ones are evaluated.
+    let omni = match Omniglot::from_env() {
+        Ok(omni) => omni,
+        Err(OmniError::NotConfigured) => Omniglot::builtin(),
+        Err(error) => fail(&error.to_string()),
+    };
+
+    let gold = match File::open(path).map_err(|error| error.to_string()).and_then(|file| read_annotated_text(file).map_err(|error| error.to_string())) {
+        Ok(gold) => gold,
+        Err(error) => fail(&format!("could not read {}: {}", path, error)),
+    };
+
+    let evaluation = match language {
+        Some(code) if !omni.has_language(code) => fail(&OmniError::UnknownLanguage(String::from(code)).to_string()),
+        Some(code) => evaluate_language(&omni, code, &gold),
+        None => evaluate_pipeline(&omni, &Pipeline::standard(), &gold),
+    };
+
+    print!("{}", evaluation);
+
+    for (code, error) in omni.load_errors() {
+        eprintln!("{} was skipped: {}", code, error);
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
 }
@@ -0,0 +1,565 @@
+// Reader and writer for the CoNLL-U format used by Universal Dependencies
+// treebanks. See https://universaldependencies.org/format.html
+
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use crate::features::Features;
+use crate::import::ImportError;
+use crate::segmentation::SegmentKind;
+use crate::text::{AnnotatedText, MULTIWORD_LAYER, SENTENCE_LAYER, Span, Token};
+
+/// The ID column: a word, a multiword token range such as `1-2`, or an empty
+/// node such as `5.1`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WordId {
+    Single(usize),
+    Range(usize, usize),
+    Empty(usize, usize),
+}
+
+/// A single line of a CoNLL-U sentence. Columns containing `_` are `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Word {
+    pub id: WordId,
+    pub form: String,
+    pub lemma: Option<String>,
+    pub upos: Option<String>,
+    pub xpos: Option<String>,
+    pub feats: Features,
+    pub head: Option<usize>,
+    pub deprel: Option<String>,
+    pub deps: Option<String>,
+    pub misc: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sentence {
+    pub comments: Vec<String>,
+    pub words: Vec<Word>,
+}
+
+impl Sentence {
+    /// The syntactic words of the sentence, without multiword token ranges and
+    /// empty nodes.
+    pub fn syntactic_words(&self) -> impl Iterator<Item = &Word> {
+        self.words.iter().filter(|word| matches!(word.id, WordId::Single(_)))
+    }
+}
+
+impl fmt::Display for WordId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordId::Single(id) => write!(f, "{}", id),
+            WordId::Range(first, last) => write!(f, "{}-{}", first, last),
+            WordId::Empty(id, index) => write!(f, "{}.{}", id, index),
+        }
+    }
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let column = |value: &Option<String>| value.clone().unwrap_or_else(|| String::from("_"));
+
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.id,
+            self.form,
+            column(&self.lemma),
+            column(&self.upos),
+            column(&self.xpos),
+            self.feats,
+            column(&self.head.map(|head| head.to_string())),
+            column(&self.deprel),
+            column(&self.deps),
+            column(&self.misc),
+        )
+    }
+}
+
+impl fmt::Display for Sentence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for comment in &self.comments {
+            writeln!(f, "# {}", comment)?;
+        }
+
+        for word in &self.words {
+            writeln!(f, "{}", word)?;
+        }
+
+        writeln!(f)
+    }
+}
+
+pub fn read_conllu<R: Read>(reader: R) -> Result<Vec<Sentence>, ImportError> {
+    let mut sentences: Vec<Sentence> = Vec::new();
+    let mut sentence = Sentence::default();
+
+    for (number, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            if !sentence.words.is_empty() || !sentence.comments.is_empty() {
+                sentences.push(sentence);
+                sentence = Sentence::default();
+            }
+        } else if let Some(comment) = line.strip_prefix('#') {
+            sentence.comments.push(String::from(comment.trim()));
+        } else {
+            sentence.words.push(parse_word(&line).map_err(|message| ImportError::Malformed {
+                line: number + 1,
+                message,
+            })?);
+        }
+    }
+
+    if !sentence.words.is_empty() || !sentence.comments.is_empty() {
+        sentences.push(sentence);
+    }
+
+    Ok(sentences)
+}
+
+fn parse_word(line: &str) -> Result<Word, String> {
+    let columns: Vec<&str> = line.split('\t').collect();
+
+    if columns.len() != 10 {
+        return Err(format!("expected 10 columns, found {}", columns.len()));
+    }
+
+    let feats = match Features::parse(columns[5]) {
+        Some(feats) => feats,
+        None => return Err(format!("invalid features '{}'", columns[5])),
+    };
+
+    let head = match optional(columns[6]) {
+        Some(head) => Some(head.parse::<usize>().map_err(|_| format!("invalid head '{}'", head))?),
+        None => None,
+    };
+
+    Ok(Word {
+        id: parse_id(columns[0])?,
+        form: String::from(columns[1]),
+        lemma: optional(columns[2]),
+        upos: optional(columns[3]),
+        xpos: optional(columns[4]),
+        feats,
+        head,
+        deprel: optional(columns[7]),
+        deps: optional(columns[8]),
+        misc: optional(columns[9]),
+    })
+}
+
+fn parse_id(id: &str) -> Result<WordId, String> {
+    let number = |text: &str| text.parse::<usize>().map_err(|_| format!("invalid id '{}'", id));
+
+    if let Some(dash) = id.find('-') {
+        Ok(WordId::Range(number(&id[..dash])?, number(&id[dash + 1..])?))
+    } else if let Some(dot) = id.find('.') {
+        Ok(WordId::Empty(number(&id[..dot])?, number(&id[dot + 1..])?))
+    } else {
+        Ok(WordId::Single(number(id)?))
+    }
+}
+
+fn optional(column: &str) -> Option<String> {
+    if column == "_" {
+        None
+    } else {
+        Some(String::from(column))
+    }
+}
+
+/// Converts an annotated text into CoNLL-U sentences, one per span of the
+/// sentence layer, or a single sentence if there is no such layer. The
+/// comments kept in the label of a sentence span are written before its
+/// `text`, with a numbered `sent_id` if they have none. The whitespace after
+/// every token is kept in MISC as `SpaceAfter=No` or `SpacesAfter`, and its
+/// character offsets as `TokenRange=start:end`.
+pub fn to_sentences(text: &AnnotatedText) -> Vec<Sentence> {
+    let whole = [Span::new(0, text.text.len(), "")];
+
+    let spans = match text.layer(SENTENCE_LAYER) {
+        Some(layer) => layer.spans(),
+        None => &whole,
+    };
+
+    let mut sentences = Vec::new();
+    let mut offsets = CharOffsets::default();
+
+    for (index, span) in spans.iter().enumerate() {
+        let mut sentence = Sentence::default();
+        sentence.comments.extend(span.label.lines().map(String::from));
+
+        if !sentence.comments.iter().any(|comment| comment.starts_with("sent_id ")) {
+            sentence.comments.push(format!("sent_id = {}", index + 1));
+        }
+
+        sentence.comments.push(format!("text = {}", text.span_text(span)));
+
+        let first = text.tokens.partition_point(|token| token.start < span.start);
+        let tokens = text.tokens_of(span);
+        let mut id = 0;
+        let mut empty = 0;
+        let mut position = 0;
+
+        while position < tokens.len() {
+            let token = &tokens[position];
+
+            if token.start == token.end {
+                empty += 1;
+                sentence.words.push(to_word(text, token, WordId::Empty(id, empty), None));
+                position += 1;
+                continue;
+            }
+
+            empty = 0;
+
+            let length = tokens[position..].iter()
+                .take_while(|word| word.start == token.start && word.end == token.end)
+                .count();
+
+            let next = text.tokens[first + position + length..].iter()
+                .find(|next| next.start < next.end)
+                .map_or(text.text.len(), |next| next.start);
+
+            let spacing = spacing_after(text, token, next, &mut offsets);
+
+            if length == 1 {
+                id += 1;
+                sentence.words.push(to_word(text, token, WordId::Single(id), Some(spacing)));
+            } else {
+                sentence.words.push(Word {
+                    id: WordId::Range(id + 1, id + length),
+                    form: String::from(text.token_text(token)),
+                    lemma: None,
+                    upos: None,
+                    xpos: None,
+                    feats: Features::new(),
+                    head: None,
+                    deprel: None,
+                    deps: None,
+                    misc: Some(spacing),
+                });
+
+                for word in &tokens[position..position + length] {
+                    id += 1;
+                    sentence.words.push(to_word(text, word, WordId::Single(id), None));
+                }
+            }
+
+            position += length;
+        }
+
+        sentences.push(sentence);
+    }
+
+    sentences
+}
+
+/// Writes an annotated text in CoNLL-U.
+pub fn write_conllu(text: &AnnotatedText) -> String {
+    to_sentences(text).iter().map(|sentence| sentence.to_string()).collect()
+}
+
+fn to_word(text: &AnnotatedText, token: &Token, id: WordId, spacing: Option<String>) -> Word {
+    let mut misc: Vec<String> = token.misc.iter().cloned().collect();
+    misc.extend(spacing.filter(|spacing| !spacing.is_empty()));
+
+    Word {
+        id,
+        form: String::from(text.word_form(token)),
+        lemma: token.lemma.clone(),
+        upos: token.upos.clone(),
+        xpos: token.xpos.clone(),
+        feats: token.features.clone(),
+        head: token.head,
+        deprel: token.deprel.clone(),
+        deps: token.deps.clone(),
+        misc: if misc.is_empty() { None } else { Some(misc.join("|")) },
+    }
+}
+
+/// Converts byte offsets into character offsets for offsets that only ever
+/// increase, counting every character once.
+#[derive(Default)]
+struct CharOffsets {
+    byte: usize,
+    chars: usize,
+}
+
+impl CharOffsets {
+    fn at(&mut self, text: &str, byte: usize) -> usize {
+        self.chars += text[self.byte..byte].chars().count();
+        self.byte = byte;
+
+        self.chars
+    }
+}
+
+/// The MISC entries for the whitespace between a surface token and the next
+/// one, which starts at `next`, and for the token's character offsets.
+fn spacing_after(text: &AnnotatedText, token: &Token, next: usize, offsets: &mut CharOffsets) -> String {
+    let gap = &text.text[token.end..next];
+
+    let start = offsets.at(&text.text, token.start);
+    let end = offsets.at(&text.text, token.end);
+    let range = format!("TokenRange={}:{}", start, end);
+
+    match gap {
+        "" => format!("SpaceAfter=No|{}", range),
+        " " => range,
+        gap => format!("SpacesAfter={}|{}", escape_spaces(gap), range),
+    }
+}
+
+fn escape_spaces(spaces: &str) -> String {
+    let mut escaped = String::new();
+
+    for c in spaces.chars() {
+        match c {
+            ' ' => escaped.push_str("\\s"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '|' => escaped.push_str("\\p"),
+            '\\' => escaped.push_str("\\\\"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape_spaces(escaped: &str) -> String {
+    let mut spaces = String::new();
+    let mut chars = escaped.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            spaces.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => spaces.push(' '),
+            Some('t') => spaces.push('\t'),
+            Some('n') => spaces.push('\n'),
+            Some('r') => spaces.push('\r'),
+            Some('p') => spaces.push('|'),
+            Some(other) => spaces.push(other),
+            None => spaces.push('\\'),
+        }
+    }
+
+    spaces
+}
+
+/// Builds an annotated text from CoNLL-U sentences. The text is rebuilt from
+/// the surface forms and `SpaceAfter`/`SpacesAfter`, every sentence becomes a
+/// span of the sentence layer, labelled with its comments other than `text`,
+/// and every multiword token a span of the multiword layer.
+pub fn from_sentences(sentences: &[Sentence]) -> AnnotatedText {
+    let mut text = AnnotatedText::new("");
+    let mut pending = String::new();
+
+    for sentence in sentences {
+        let mut sentence_start: Option<usize> = None;
+        let mut range: Option<(usize, usize, usize)> = None;
+        let mut last_end = text.text.len() + pending.len();
+
+        for word in &sentence.words {
+            let (misc, spacing) = split_misc(word.misc.as_deref());
+
+            let (start, end) = match word.id {
+                WordId::Range(_, last) => {
+                    let (start, end) = push_form(&mut text, &mut pending, &word.form, spacing);
+
+                    text.add_span(MULTIWORD_LAYER, Span::new(start, end, &word.form));
+                    range = Some((last, start, end));
+                    sentence_start.get_or_insert(start);
+                    last_end = end;
+
+                    continue;
+                }
+                WordId::Single(id) => match range {
+                    Some((last, start, end)) if id <= last => {
+                        if id == last {
+                            range = None;
+                        }
+
+                        (start, end)
+                    }
+                    _ => push_form(&mut text, &mut pending, &word.form, spacing),
+                },
+                WordId::Empty(_, _) => (last_end, last_end),
+            };
+
+            sentence_start.get_or_insert(start);
+            last_end = end;
+
+            let mut token = Token::new(start, end, kind_for_upos(word.upos.as_deref()));
+
+            if start == end || text.text[start..end] != word.form {
+                token.form = Some(word.form.clone());
+            }
+
+            token.lemma = word.lemma.clone();
+            token.upos = word.upos.clone();
+            token.xpos = word.xpos.clone();
+            token.features = word.feats.clone();
+            token.head = word.head;
+            token.deprel = word.deprel.clone();
+            token.deps = word.deps.clone();
+            token.misc = misc;
+
+            text.tokens.push(token);
+        }
+
+        if let Some(start) = sentence_start {
+            let comments: Vec<&str> = sentence.comments.iter()
+                .map(String::as_str)
+                .filter(|comment| !comment.starts_with("text "))
+                .collect();
+
+            text.add_span(SENTENCE_LAYER, Span::new(start, last_end, &comments.join("\n")));
+        }
+    }
+
+    text
+}
+
+/// Reads a CoNLL-U file into an annotated text.
+pub fn read_annotated_text<R: Read>(reader: R) -> Result<AnnotatedText, ImportError> {
+    Ok(from_sentences(&read_conllu(reader)?))
+}
+
+/// Appends a surface form after the whitespace that preceded it, and keeps the
+/// whitespace that follows it until the next form.
+fn push_form(text: &mut AnnotatedText, pending: &mut String, form: &str, spacing: String) -> (usize, usize) {
+    text.text.push_str(pending);
+
+    let start = text.text.len();
+    text.text.push_str(form);
+
+    *pending = spacing;
+
+    (start, text.text.len())
+}
+
+/// Separates the whitespace entries of a MISC column from the others, which
+/// are returned as they are. Offsets are left out, as they follow from the
+/// rebuilt text.
+fn split_misc(misc: Option<&str>) -> (Option<String>, String) {
+    let mut others: Vec<&str> = Vec::new();
+    let mut spacing = String::from(" ");
+
+    for entry in misc.unwrap_or("").split('|').filter(|entry| !entry.is_empty()) {
+        if entry == "SpaceAfter=No" {
+            spacing = String::new();
+        } else if let Some(spaces) = entry.strip_prefix("SpacesAfter=") {
+            spacing = unescape_spaces(spaces);
+        } else if !entry.starts_with("TokenRange=") {
+            others.push(entry);
+        }
+    }
+
+    let others = if others.is_empty() { None } else { Some(others.join("|")) };
+
+    (others, spacing)
+}
+
+fn kind_for_upos(upos: Option<&str>) -> SegmentKind {
+    match upos {
+        Some("PUNCT") | Some("SYM") => SegmentKind::Punctuation,
+        Some("NUM") => SegmentKind::Number,
+        _ => SegmentKind::Word,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENTENCE: &str = "# sent_id = 1\n# text = Hij zoekt het.\n1\tHij\thij\tPRON\t_\tCase=Nom|Person=3\t2\tnsubj\t_\t_\n2\tzoekt\tzoeken\tVERB\t_\tNumber=Sing|Person=3|Tense=Pres\t0\troot\t_\t_\n3\thet\thet\tPRON\t_\t_\t2\tobj\t_\tSpaceAfter=No\n4\t.\t.\tPUNCT\t_\t_\t2\tpunct\t_\t_\n";
+
+    #[test]
+    fn it_reads_a_sentence() {
+        let sentences = read_conllu(SENTENCE.as_bytes()).unwrap();
+
+        assert_eq!(sentences.len(), 1);
+        assert_eq!(sentences[0].comments, vec!["sent_id = 1", "text = Hij zoekt het."]);
+        assert_eq!(sentences[0].words.len(), 4);
+        assert_eq!(sentences[0].words[1].lemma, Some(String::from("zoeken")));
+        assert_eq!(sentences[0].words[1].feats.get("Tense"), Some("Pres"));
+        assert_eq!(sentences[0].words[1].head, Some(0));
+        assert_eq!(sentences[0].words[2].xpos, None);
+    }
+
+    #[test]
+    fn it_reads_multiword_tokens_and_empty_nodes() {
+        assert_eq!(parse_id("1-2"), Ok(WordId::Range(1, 2)));
+        assert_eq!(parse_id("5.1"), Ok(WordId::Empty(5, 1)));
+        assert!(parse_id("x").is_err());
+    }
+
+    const CLITICS: &str = "# newpar\n# sent_id = ar-1\n# text = وللكتاب\n1-3\tوللكتاب\t_\t_\t_\t_\t_\t_\t_\tSpaceAfter=No\n1\tو\tو\tCCONJ\t_\t_\t3\tcc\t_\t_\n2\tل\tل\tADP\t_\t_\t3\tcase\t_\t_\n3\tالكتاب\tكتاب\tNOUN\t_\tDefinite=Def\t0\troot\t_\t_\n3.1\tهو\tهو\tPRON\t_\t_\t_\t_\t3:nsubj\t_\n";
+
+    #[test]
+    fn it_reads_annotated_text() {
+        let text = read_annotated_text(format!("{}\n{}", SENTENCE, CLITICS).as_bytes()).unwrap();
+
+        assert_eq!(text.text, "Hij zoekt het. وللكتاب");
+        assert_eq!(text.layer(SENTENCE_LAYER).unwrap().spans().len(), 2);
+        assert_eq!(text.tokens.len(), 8);
+        assert_eq!(text.token_text(&text.tokens[1]), "zoekt");
+        assert_eq!(text.tokens[1].lemma.as_deref(), Some("zoeken"));
+        assert_eq!(text.token_text(&text.tokens[3]), ".");
+        assert_eq!(text.tokens[3].kind, SegmentKind::Punctuation);
+        assert_eq!(text.token_text(&text.tokens[4]), "وللكتاب");
+        assert_eq!(text.word_form(&text.tokens[4]), "و");
+        assert_eq!(text.tokens[7].start, text.tokens[7].end);
+        assert_eq!(text.tokens[7].deps.as_deref(), Some("3:nsubj"));
+        assert!(text.spans_covering(&text.tokens[6]).iter().any(|(layer, _)| *layer == MULTIWORD_LAYER));
+    }
+
+    #[test]
+    fn it_writes_annotated_text() {
+        let text = read_annotated_text(format!("{}\n{}", SENTENCE, CLITICS).as_bytes()).unwrap();
+
+        let written = write_conllu(&text);
+        let lines: Vec<&str> = written.lines().collect();
+
+        assert_eq!(lines[0], "# sent_id = 1");
+        assert_eq!(lines[1], "# text = Hij zoekt het.");
+        assert_eq!(lines[4], "3\thet\thet\tPRON\t_\t_\t2\tobj\t_\tSpaceAfter=No|TokenRange=10:13");
+        assert_eq!(lines[5], "4\t.\t.\tPUNCT\t_\t_\t2\tpunct\t_\tTokenRange=13:14");
+        assert_eq!(lines[7], "# newpar");
+        assert_eq!(lines[8], "# sent_id = ar-1");
+        assert_eq!(lines[10], "1-3\tوللكتاب\t_\t_\t_\t_\t_\t_\t_\tSpaceAfter=No|TokenRange=15:22");
+        assert_eq!(lines[14], "3.1\tهو\tهو\tPRON\t_\t_\t_\t_\t3:nsubj\t_");
+
+        assert_eq!(read_annotated_text(written.as_bytes()).unwrap(), text);
+    }
+
+    #[test]
+    fn it_keeps_unusual_whitespace() {
+        let mut text = AnnotatedText::new("a\tb");
+        text.tokens.push(Token::new(0, 1, SegmentKind::Word));
+        text.tokens.push(Token::new(2, 3, SegmentKind::Word));
+
+        let written = write_conllu(&text);
+
+        assert!(written.contains("SpacesAfter=\\t"));
+        assert_eq!(read_annotated_text(written.as_bytes()).unwrap().text, "a\tb");
+    }
+
+    #[test]
+    fn it_reports_lines_with_missing_columns() {
+        match read_conllu("1\tHij\thij\n".as_bytes()) {
+            Err(ImportError::Malformed { line, .. }) => assert_eq!(line, 1),
+            _ => panic!("expected a malformed line error"),
+        }
+    }
+}
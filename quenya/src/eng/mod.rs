@@ -1 +1,216 @@
+use wn::pos::Part;
+use crate::eng::verbs::{is_past_simple, is_present_participle, is_present_simple, make_past_simple, make_present_participle, make_present_simple};
+use crate::features::Features;
+use crate::hunspell::Dictionary;
+use crate::language::{Analysis, Capability, Language, analyze_with_lexicon, forms_of};
+use crate::stem::Algorithm;
+use crate::lexicon::{Lexeme, Lexicon};
+
 pub mod verbs;
+
+/// Recognises a verb form and returns its stem.
+type Rule = fn(&str) -> Result<String, ()>;
+
+/// The shortest stem the rules accept when there is no lexicon to check it.
+const MIN_STEM_LENGTH: usize = 3;
+
+/// Common words that end like verb forms but are not.
+const NOT_VERB_FORMS: &[&str] = &[
+    "always", "anything", "during", "evening", "everything", "hundred", "morning", "news", "nothing", "perhaps", "series", "something", "species",
+];
+
+/// Without a lexicon, a stem must be long enough and have a vowel.
+fn is_plausible_stem(form: &str, stem: &str) -> bool {
+    stem.chars().count() >= MIN_STEM_LENGTH && stem.chars().any(|c| "aeiouy".contains(c)) && !NOT_VERB_FORMS.contains(&form.to_lowercase().as_str())
+}
+
+/// English with rule-based verb morphology. With a lexicon, irregular forms
+/// are taken from the lexicon and rule-based stems must be known verbs;
+/// without one, implausible stems are rejected. A
+/// Hunspell dictionary checks spelling and adds the analyses it describes.
+pub struct English {
+    lexicon: Option<Lexicon>,
+    dictionary: Option<Dictionary>,
+}
+
+impl English {
+    pub fn new(lexicon: Option<Lexicon>) -> English {
+        English { lexicon, dictionary: None }
+    }
+
+    pub fn with_dictionary(mut self, dictionary: Dictionary) -> English {
+        self.dictionary = Some(dictionary);
+        self
+    }
+
+    /// Analyses a form with the lexicon and the rules for regular verbs.
+    fn analyze_by_rule(&self, form: &str) -> Vec<Analysis> {
+        let mut analyses = match &self.lexicon {
+            Some(lexicon) => analyze_with_lexicon(lexicon, form),
+            None => Vec::new(),
+        };
+
+        let rules: [(Rule, &str); 3] = [
+            (is_present_participle, "Tense=Pres|VerbForm=Part"),
+            (is_past_simple, "Tense=Past|VerbForm=Fin"),
+            (is_present_simple, "Number=Sing|Person=3|Tense=Pres|VerbForm=Fin"),
+        ];
+
+        for (rule, features) in rules.iter() {
+            let stem = match rule(form) {
+                Ok(stem) if !stem.is_empty() => stem,
+                _ => continue,
+            };
+
+            let known = match &self.lexicon {
+                Some(lexicon) => lexicon.find_lexeme_by_lemma(&stem, Part::Verb).is_some(),
+                None => is_plausible_stem(form, &stem),
+            };
+
+            if !known {
+                continue;
+            }
+
+            let analysis = Analysis::new(&stem, Some(Part::Verb), Features::parse(features).unwrap_or_default());
+
+            if !analyses.contains(&analysis) {
+                analyses.push(analysis);
+            }
+        }
+
+        analyses
+    }
+}
+
+impl Language for English {
+    fn code(&self) -> &str {
+        "eng"
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        match self.dictionary {
+            Some(_) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Inflect, Capability::SpellCheck, Capability::Stem],
+            None => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Inflect, Capability::Stem],
+        }
+    }
+
+    fn lexicon(&self) -> Option<&Lexicon> {
+        self.lexicon.as_ref()
+    }
+
+    fn dictionary(&self) -> Option<&Dictionary> {
+        self.dictionary.as_ref()
+    }
+
+    fn analyze_word(&self, word: &str) -> Vec<Analysis> {
+        let mut analyses = self.analyze_by_rule(word);
+
+        if let Some(dictionary) = &self.dictionary {
+            for analysis in dictionary.analyze(word) {
+                if !analyses.contains(&analysis) {
+                    analyses.push(analysis);
+                }
+            }
+        }
+
+        analyses
+    }
+
+    /// Inflects the lexeme in the lexicon, or a verb the lexicon lacks.
+    fn inflect(&self, lemma: &str, pos: Part, features: &Features) -> Vec<String> {
+        let lexeme = self.lexicon.as_ref().and_then(|lexicon| lexicon.find_lexeme_by_lemma(lemma, pos));
+
+        match lexeme {
+            Some(lexeme) => self.inflect_lexeme(lexeme, features),
+            None => self.inflect_lexeme(&Lexeme::new(lemma, pos), features),
+        }
+    }
+
+    /// The irregular forms the lexeme lists, or else the regular verb forms.
+    fn inflect_lexeme(&self, lexeme: &Lexeme, features: &Features) -> Vec<String> {
+        let forms = forms_of(lexeme, features);
+
+        if !forms.is_empty() || lexeme.pos != Part::Verb {
+            return forms;
+        }
+
+        let lemma = lexeme.lemma.as_str();
+
+        let form = match (features.get("Tense"), features.get("VerbForm"), features.get("Person")) {
+            (Some("Past"), _, _) => make_past_simple(lemma),
+            (Some("Pres"), Some("Part"), _) => make_present_participle(lemma),
+            (Some("Pres"), _, Some("3")) => make_present_simple(lemma),
+            (None, Some("Inf"), _) => String::from(lemma),
+            _ => return Vec::new(),
+        };
+
+        vec![form]
+    }
+
+    fn stem(&self, word: &str) -> Option<String> {
+        Some(Algorithm::English.stem(word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morphology::round_trip;
+
+    #[test]
+    fn it_analyzes_regular_verbs() {
+        let english = English::new(None);
+
+        let analyses = english.analyze_word("works");
+
+        assert_eq!(analyses.len(), 1);
+        assert_eq!(analyses[0].lemma, "work");
+        assert_eq!(analyses[0].features.get("Person"), Some("3"));
+    }
+
+    #[test]
+    fn it_does_not_make_up_stems_without_a_lexicon() {
+        let english = English::new(None);
+
+        for word in &["sing", "thing", "was", "has", "need", "red", "news"] {
+            assert!(english.analyze_word(word).is_empty(), "{}", word);
+        }
+
+        assert_eq!(english.lemmatize("dying"), vec!["die"]);
+        assert_eq!(english.lemmatize("fixes"), vec!["fix"]);
+    }
+
+    #[test]
+    fn it_checks_stems_against_the_lexicon() {
+        let mut lexicon = Lexicon::new();
+        lexicon.add_form("go", Part::Verb, "went", Features::new().with("Tense", "Past"));
+
+        let english = English::new(Some(lexicon));
+
+        assert_eq!(english.lemmatize("went"), vec!["go"]);
+        assert!(english.lemmatize("working").is_empty());
+        assert_eq!(english.inflect("go", Part::Verb, &Features::new().with("Tense", "Past")), vec!["went"]);
+    }
+
+    #[test]
+    fn it_inflects_regular_verbs() {
+        let english = English::new(None);
+
+        assert_eq!(english.inflect("walk", Part::Verb, &Features::parse("Tense=Past").unwrap()), vec!["walked"]);
+        assert_eq!(english.inflect("walk", Part::Verb, &Features::parse("Tense=Pres|VerbForm=Part").unwrap()), vec!["walking"]);
+    }
+
+    #[test]
+    fn it_agrees_with_itself_on_regular_verbs() {
+        let english = English::new(None);
+
+        let features: Vec<Features> = ["Tense=Past", "Tense=Pres|VerbForm=Part", "Number=Sing|Person=3|Tense=Pres"]
+            .iter()
+            .map(|features| Features::parse(features).unwrap())
+            .collect();
+
+        for lemma in &["walk", "close", "carry", "stop", "watch", "die", "realize", "freeze", "organize", "ache", "refer", "admit"] {
+            assert_eq!(round_trip(&english, &Lexeme::new(lemma, Part::Verb), &features), vec![]);
+        }
+    }
+}
@@ -9,39 +9,189 @@
 // 2. Does it have the pattern of a verb form and does the base match with a lemma?
 
 
-fn is_present_participle(word: &str) -> Result<&str, ()> {
-    if word.ends_with("ing") {
-        return Ok(&word[..word.len() - 3]);
+// The `is_*` functions undo the spelling changes the `make_*` functions make,
+// so that every regular form can be taken back to its lemma:
+//
+// - a final silent e is dropped before -ing and -ed (close, closing, closed);
+// - a final y after a consonant becomes i (carry, carries, carried);
+// - the final consonant of a short stressed syllable is doubled (stop, stopped),
+//   which in longer words only happens in those listed as stressed on their
+//   final syllable (refer, referred; admit, admitted; but debit, debited).
+
+const DOUBLED: &str = "bgmnprt";
+
+/// Verbs of more than one syllable that are stressed on their final syllable.
+const STRESSED_FINAL: &[&str] = &[
+    "abet", "abhor", "acquit", "admit", "allot", "begin", "commit", "compel", "concur", "confer", "control", "defer", "deter", "emit", "equip", "expel",
+    "forbid", "forget", "incur", "infer", "occur", "omit", "patrol", "permit", "prefer", "propel", "rebel", "recur", "refer", "regret", "repel",
+    "submit", "transfer", "transmit", "upset",
+];
+
+fn is_vowel(c: char) -> bool {
+    "aeiou".contains(c)
+}
+
+/// Whether a word ends in a short stressed syllable whose consonant doubles:
+/// one syllable ending in a single vowel and a consonant, as in `stop` and
+/// `plan`, or a listed verb stressed on its last syllable, as in `refer`.
+fn doubles_final_consonant(word: &str) -> bool {
+    is_short_syllable(word) || STRESSED_FINAL.contains(&word)
+}
+
+/// Whether a word is one syllable ending in a single vowel and a consonant
+/// that doubles.
+fn is_short_syllable(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    let vowels = chars.iter().filter(|c| is_vowel(**c)).count();
+
+    match chars.as_slice() {
+        [.., before, vowel, last] => vowels == 1 && !is_vowel(*before) && is_vowel(*vowel) && DOUBLED.contains(*last),
+        _ => false,
+    }
+}
+
+/// Whether a stem lost a final e before a suffix, as in `clos` of `closed`.
+fn needs_final_e(stem: &str) -> bool {
+    let chars: Vec<char> = stem.chars().collect();
+
+    match chars.as_slice() {
+        [.., 'v'] | [.., 'u'] => true,
+        [.., 'n', 'c'] | [.., 'r', 'c'] | [.., 'd', 'g'] | [.., 'r', 'g'] => true,
+        [vowel, 'c', 'h'] => is_vowel(*vowel),
+        [.., before, last] if "szcg".contains(*last) => is_vowel(*before),
+        [.., before, 'l'] => "bcdfgkptz".contains(*before),
+        _ => false,
+    }
+}
+
+/// Restores the lemma of a stem left by removing -ing or -ed.
+fn restore_stem(stem: &str) -> String {
+    let chars: Vec<char> = stem.chars().collect();
+
+    if let [.., previous, last] = chars.as_slice() {
+        let undoubled = &stem[..stem.len() - last.len_utf8()];
+
+        if previous == last && doubles_final_consonant(undoubled) {
+            return String::from(undoubled);
+        }
+    }
+
+    if needs_final_e(stem) {
+        return [stem, "e"].concat();
+    }
+
+    String::from(stem)
+}
+
+pub(crate) fn is_present_participle(word: &str) -> Result<String, ()> {
+    let stem = match word.strip_suffix("ing") {
+        Some(stem) if !stem.is_empty() => stem,
+        _ => return Err(()),
+    };
+
+    // dying, lying and tying.
+    if stem.len() <= 2 && stem.ends_with('y') {
+        return Ok([&stem[..stem.len() - 1], "ie"].concat());
     }
 
-    Err(())
+    Ok(restore_stem(stem))
 }
 
-fn is_past_simple(word: &str) -> Result<&str, ()> {
-    if word.ends_with("ed") {
-        return Ok(&word[..word.len() - 2]);
+pub(crate) fn is_past_simple(word: &str) -> Result<String, ()> {
+    if let Some(stem) = word.strip_suffix("ied") {
+        // died, lied and tied.
+        if stem.len() == 1 {
+            return Ok([stem, "ie"].concat());
+        }
+
+        if stem.len() > 1 {
+            return Ok([stem, "y"].concat());
+        }
     }
 
-    Err(())
+    match word.strip_suffix("ed") {
+        Some(stem) if !stem.is_empty() => Ok(restore_stem(stem)),
+        _ => Err(()),
+    }
 }
 
-fn is_present_simple(word: &str) -> Result<&str, ()> {
-    if word.ends_with("s") {
-        return Ok(&word[..word.len() - 1]);
+pub(crate) fn is_present_simple(word: &str) -> Result<String, ()> {
+    if let Some(stem) = word.strip_suffix("ies") {
+        if stem.len() > 1 {
+            return Ok([stem, "y"].concat());
+        }
+    }
+
+    // An e is added after ch, sh, ss, x, zz and o, as in watches, buzzes and
+    // goes, but not in aches, realizes or freezes, where it belongs to the
+    // lemma.
+    if let Some(stem) = word.strip_suffix("es") {
+        let added = ["ch", "sh", "ss", "x", "zz", "o"].iter().any(|ending| stem.ends_with(ending));
+
+        if added && !(stem.len() == 3 && stem.starts_with(is_vowel)) {
+            return Ok(String::from(stem));
+        }
+    }
+
+    if word.ends_with("ss") || word.ends_with("us") || word.ends_with("is") {
+        return Err(());
     }
 
-    Err(())
+    match word.strip_suffix('s') {
+        Some(stem) if !stem.is_empty() => Ok(String::from(stem)),
+        _ => Err(()),
+    }
 }
 
-fn make_present_simple(word: &str) -> String {
+/// Whether a word ends in a y after a consonant, as in `carry`.
+fn ends_in_consonant_y(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+
+    matches!(chars.as_slice(), [.., before, 'y'] if !is_vowel(*before))
+}
+
+pub(crate) fn make_present_simple(word: &str) -> String {
+    if ends_in_consonant_y(word) {
+        return [&word[..word.len() - 1], "ies"].concat();
+    }
+
+    if ["s", "x", "z", "ch", "sh", "o"].iter().any(|ending| word.ends_with(ending)) {
+        return [word, "es"].concat();
+    }
+
     [word, "s"].concat()
 }
 
-fn make_past_simple(word: &str) -> String {
+pub(crate) fn make_past_simple(word: &str) -> String {
+    if word.ends_with('e') {
+        return [word, "d"].concat();
+    }
+
+    if ends_in_consonant_y(word) {
+        return [&word[..word.len() - 1], "ied"].concat();
+    }
+
+    if doubles_final_consonant(word) {
+        return [word, &word[word.len() - 1..], "ed"].concat();
+    }
+
     [word, "ed"].concat()
 }
 
-fn make_present_participle(word: &str) -> String {
+pub(crate) fn make_present_participle(word: &str) -> String {
+    if let Some(stem) = word.strip_suffix("ie") {
+        return [stem, "ying"].concat();
+    }
+
+    // be keeps its e, as in being.
+    if word.ends_with('e') && word != "be" && !["ee", "ye", "oe"].iter().any(|ending| word.ends_with(ending)) {
+        return [&word[..word.len() - 1], "ing"].concat();
+    }
+
+    if doubles_final_consonant(word) {
+        return [word, &word[word.len() - 1..], "ing"].concat();
+    }
+
     [word, "ing"].concat()
 }
 
@@ -53,7 +203,7 @@ mod tests {
     fn it_allows_working() {
         let result = is_present_participle("working");
 
-        assert_eq!(result, Ok("work"));
+        assert_eq!(result, Ok(String::from("work")));
     }
 
     #[test]
@@ -67,21 +217,53 @@ mod tests {
     fn it_should_allow_past_simple_of_work() {
         let result = is_past_simple("worked");
 
-        assert_eq!(result, Ok("work"));
+        assert_eq!(result, Ok(String::from("work")));
     }
 
     #[test]
     fn it_should_allow_past_simple_of_close() {
         let result = is_past_simple("closed");
 
-        assert_eq!(result, Ok("close"));
+        assert_eq!(result, Ok(String::from("close")));
     }
 
     #[test]
     fn it_should_allow_present_simple_of_work() {
         let result = is_present_simple("works");
 
-        assert_eq!(result, Ok("work"));
+        assert_eq!(result, Ok(String::from("work")));
+    }
+
+    #[test]
+    fn it_keeps_the_e_of_lemmas_in_the_present_simple() {
+        assert_eq!(is_present_simple("realizes"), Ok(String::from("realize")));
+        assert_eq!(is_present_simple("aches"), Ok(String::from("ache")));
+        assert_eq!(is_present_simple("buzzes"), Ok(String::from("buzz")));
+        assert_eq!(is_present_simple("watches"), Ok(String::from("watch")));
+    }
+
+    #[test]
+    fn it_doubles_consonants_of_stressed_final_syllables() {
+        assert_eq!(make_past_simple("refer"), "referred");
+        assert_eq!(make_present_participle("regret"), "regretting");
+        assert_eq!(make_past_simple("render"), "rendered");
+        assert_eq!(make_past_simple("open"), "opened");
+        assert_eq!(is_past_simple("referred"), Ok(String::from("refer")));
+        assert_eq!(make_past_simple("control"), "controlled");
+    }
+
+    #[test]
+    fn it_does_not_double_consonants_of_unstressed_final_syllables() {
+        assert_eq!(make_past_simple("debit"), "debited");
+        assert_eq!(make_present_participle("debit"), "debiting");
+        assert_eq!(make_present_participle("visit"), "visiting");
+        assert_eq!(is_past_simple("debited"), Ok(String::from("debit")));
+    }
+
+    #[test]
+    fn it_keeps_the_e_of_be() {
+        assert_eq!(make_present_participle("be"), "being");
+        assert_eq!(is_present_participle("being"), Ok(String::from("be")));
     }
 }
 
@@ -0,0 +1,414 @@
+// Evaluation of annotation against gold-standard CoNLL-U. The gold text is
+// rebuilt from the treebank, annotated again and the two are compared: tokens
+// by their offsets, and the words of matching tokens by lemma, part of speech
+// and features. Gold words the system did not find as tokens count as wrong
+// on every measure, so that better tokenisation shows in every score.
+//
+// Words are scored apart from tokens, as a token may match while its words do
+// not: a system that leaves a gold multiword token such as German `zum` or an
+// Arabic word with clitics unsplit finds the token but none of its words.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use crate::omni::Omniglot;
+use crate::pipeline::{Analyzer, Disambiguator, Pipeline, Stage, Tokenizer};
+use crate::segmentation::SegmentKind;
+use crate::text::{AnnotatedText, Token};
+
+/// How many error types the report lists.
+const REPORTED_ERRORS: usize = 10;
+
+/// A count of correct answers out of a total.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Score {
+    pub correct: usize,
+    pub total: usize,
+}
+
+impl Score {
+    fn add(&mut self, correct: bool) {
+        self.total += 1;
+
+        if correct {
+            self.correct += 1;
+        }
+    }
+
+    /// The share of correct answers, or 0 without any.
+    pub fn accuracy(&self) -> f64 {
+        ratio(self.correct, self.total)
+    }
+}
+
+/// A kind of mistake, such as a noun tagged as a verb.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorType {
+    /// A gold word whose token the system did not find.
+    Tokenization,
+    /// A gold word of a token the system found but split into other words.
+    WordSegmentation,
+    /// A wrong lemma for a word of the gold part of speech.
+    Lemma(String),
+    /// A wrong part of speech. A missing value is `_`.
+    Upos { gold: String, system: String },
+    /// A wrong or missing value of a feature.
+    Feature { name: String, gold: String, system: String },
+}
+
+impl fmt::Display for ErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorType::Tokenization => write!(f, "tokenization"),
+            ErrorType::WordSegmentation => write!(f, "word segmentation"),
+            ErrorType::Lemma(upos) => write!(f, "lemma of {}", upos),
+            ErrorType::Upos { gold, system } => write!(f, "UPOS {} as {}", gold, system),
+            ErrorType::Feature { name, gold, system } => write!(f, "{}={} as {}", name, gold, system),
+        }
+    }
+}
+
+/// The scores of an annotation against a gold standard.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Evaluation {
+    /// The number of distinct token spans in the gold standard.
+    pub gold_tokens: usize,
+    /// The number of distinct token spans the system found.
+    pub system_tokens: usize,
+    /// The number of token spans both have.
+    pub matched_tokens: usize,
+    /// The number of words of the gold standard, counting every word of a
+    /// multiword token.
+    pub gold_words: usize,
+    pub system_words: usize,
+    /// The number of words both have: words of a matched token that the
+    /// system split into the same forms.
+    pub matched_words: usize,
+    pub lemmas: Score,
+    pub upos: Score,
+    /// Scores per feature name, over the words where the gold standard or the
+    /// system gives the feature.
+    pub features: BTreeMap<String, Score>,
+    /// The system's words that the analyzer found analyses for.
+    pub analyzed: Score,
+    /// The analyzed words that had more than one analysis.
+    pub ambiguous: Score,
+    pub errors: HashMap<ErrorType, usize>,
+}
+
+impl Evaluation {
+    pub fn token_precision(&self) -> f64 {
+        ratio(self.matched_tokens, self.system_tokens)
+    }
+
+    pub fn token_recall(&self) -> f64 {
+        ratio(self.matched_tokens, self.gold_tokens)
+    }
+
+    pub fn token_f1(&self) -> f64 {
+        f1(self.token_precision(), self.token_recall())
+    }
+
+    pub fn word_precision(&self) -> f64 {
+        ratio(self.matched_words, self.system_words)
+    }
+
+    pub fn word_recall(&self) -> f64 {
+        ratio(self.matched_words, self.gold_words)
+    }
+
+    pub fn word_f1(&self) -> f64 {
+        f1(self.word_precision(), self.word_recall())
+    }
+
+    /// The share of words the analyzer found analyses for.
+    pub fn coverage(&self) -> f64 {
+        self.analyzed.accuracy()
+    }
+
+    /// The share of analyzed words with more than one analysis.
+    pub fn ambiguity(&self) -> f64 {
+        self.ambiguous.accuracy()
+    }
+
+    /// The most frequent error types, most frequent first.
+    pub fn worst_errors(&self, count: usize) -> Vec<(&ErrorType, usize)> {
+        let mut errors: Vec<(&ErrorType, usize)> = self.errors.iter().map(|(error, count)| (error, *count)).collect();
+
+        errors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        errors.truncate(count);
+
+        errors
+    }
+
+    fn add_error(&mut self, error: ErrorType) {
+        *self.errors.entry(error).or_insert(0) += 1;
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tokens\tP {:.2}\tR {:.2}\tF1 {:.2}", percent(self.token_precision()), percent(self.token_recall()), percent(self.token_f1()))?;
+        writeln!(f, "words\tP {:.2}\tR {:.2}\tF1 {:.2}", percent(self.word_precision()), percent(self.word_recall()), percent(self.word_f1()))?;
+        writeln!(f, "lemma\t{:.2}\t({}/{})", percent(self.lemmas.accuracy()), self.lemmas.correct, self.lemmas.total)?;
+        writeln!(f, "UPOS\t{:.2}\t({}/{})", percent(self.upos.accuracy()), self.upos.correct, self.upos.total)?;
+
+        for (name, score) in &self.features {
+            writeln!(f, "{}\t{:.2}\t({}/{})", name, percent(score.accuracy()), score.correct, score.total)?;
+        }
+
+        writeln!(f, "coverage\t{:.2}", percent(self.coverage()))?;
+        writeln!(f, "ambiguity\t{:.2}", percent(self.ambiguity()))?;
+
+        for (error, count) in self.worst_errors(REPORTED_ERRORS) {
+            writeln!(f, "{}\t{}", count, error)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares a system annotation of the gold standard's text with the gold
+/// standard. Tokens match when they span the same text, and the words of a
+/// multiword token are paired in order if the system split it into as many
+/// words.
+pub fn evaluate(gold: &AnnotatedText, system: &AnnotatedText) -> Evaluation {
+    let mut evaluation = Evaluation::default();
+
+    let gold_spans = words_by_span(&gold.tokens);
+    let system_spans = words_by_span(&system.tokens);
+
+    evaluation.gold_tokens = gold_spans.len();
+    evaluation.system_tokens = system_spans.len();
+    evaluation.matched_tokens = gold_spans.keys().filter(|span| system_spans.contains_key(span)).count();
+
+    evaluation.gold_words = gold_spans.values().map(Vec::len).sum();
+    evaluation.system_words = system_spans.values().map(Vec::len).sum();
+
+    for (span, gold_words) in &gold_spans {
+        let system_words = match system_spans.get(span) {
+            Some(system_words) if system_words.len() == gold_words.len() => system_words.as_slice(),
+            Some(_) => {
+                for gold_word in gold_words {
+                    compare(&mut evaluation, gold_word, None, ErrorType::WordSegmentation);
+                }
+
+                continue;
+            },
+            None => &[],
+        };
+
+        for (index, gold_word) in gold_words.iter().enumerate() {
+            let system_word = system_words.get(index).copied();
+
+            if system_word.is_some_and(|system_word| system.word_form(system_word) == gold.word_form(gold_word)) {
+                evaluation.matched_words += 1;
+            }
+
+            compare(&mut evaluation, gold_word, system_word, ErrorType::Tokenization);
+        }
+    }
+
+    for token in system.tokens.iter().filter(|token| token.kind == SegmentKind::Word) {
+        let analyzed = token.lemma.is_some();
+
+        evaluation.analyzed.add(analyzed);
+
+        if analyzed {
+            evaluation.ambiguous.add(!token.alternatives.is_empty());
+        }
+    }
+
+    evaluation
+}
+
+/// Annotates the text of the gold standard with a pipeline and evaluates the
+/// result.
+pub fn evaluate_pipeline(omni: &Omniglot, pipeline: &Pipeline, gold: &AnnotatedText) -> Evaluation {
+    evaluate(gold, &pipeline.run(omni, &gold.text))
+}
+
+/// Evaluates one language module, taking every word of the gold standard's
+/// text to be in that language.
+pub fn evaluate_language(omni: &Omniglot, code: &str, gold: &AnnotatedText) -> Evaluation {
+    let pipeline = Pipeline::new()
+        .with(Box::new(Tokenizer))
+        .with(Box::new(AssignLanguage(String::from(code))))
+        .with(Box::new(Analyzer))
+        .with(Box::new(Disambiguator));
+
+    evaluate_pipeline(omni, &pipeline, gold)
+}
+
+/// Sets the language of every word token.
+struct AssignLanguage(String);
+
+impl Stage for AssignLanguage {
+    fn name(&self) -> &str {
+        "language"
+    }
+
+    fn process(&self, _omni: &Omniglot, text: &mut AnnotatedText) {
+        for token in text.tokens.iter_mut().filter(|token| token.kind == SegmentKind::Word) {
+            token.language = Some(self.0.clone());
+        }
+    }
+}
+
+/// The tokens with text grouped by their span. Empty nodes are left out.
+fn words_by_span(tokens: &[Token]) -> BTreeMap<(usize, usize), Vec<&Token>> {
+    let mut spans: BTreeMap<(usize, usize), Vec<&Token>> = BTreeMap::new();
+
+    for token in tokens.iter().filter(|token| token.start < token.end) {
+        spans.entry((token.start, token.end)).or_default().push(token);
+    }
+
+    spans
+}
+
+/// Scores a gold word against the system's word, counting `missing` as the
+/// error if the system has none.
+fn compare(evaluation: &mut Evaluation, gold: &Token, system: Option<&Token>, missing: ErrorType) {
+    let value = |value: Option<&str>| String::from(value.unwrap_or("_"));
+
+    let gold_upos = value(gold.upos.as_deref());
+    let system_upos = value(system.and_then(|system| system.upos.as_deref()));
+
+    if system.is_none() {
+        evaluation.add_error(missing);
+    }
+
+    if let Some(lemma) = &gold.lemma {
+        let correct = system.and_then(|system| system.lemma.as_ref()).is_some_and(|system| system == lemma);
+
+        evaluation.lemmas.add(correct);
+
+        if !correct && system.is_some() {
+            evaluation.add_error(ErrorType::Lemma(gold_upos.clone()));
+        }
+    }
+
+    if gold.upos.is_some() {
+        let correct = gold_upos == system_upos;
+
+        evaluation.upos.add(correct);
+
+        if !correct && system.is_some() {
+            evaluation.add_error(ErrorType::Upos {
+                gold: gold_upos,
+                system: system_upos,
+            });
+        }
+    }
+
+    let mut names: Vec<&str> = gold.features.iter().map(|(name, _)| name).collect();
+
+    for (name, _) in system.iter().flat_map(|system| system.features.iter()) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    for name in names {
+        let gold_value = value(gold.features.get(name));
+        let system_value = value(system.and_then(|system| system.features.get(name)));
+        let correct = gold_value == system_value;
+
+        evaluation.features.entry(String::from(name)).or_default().add(correct);
+
+        if !correct && system.is_some() {
+            evaluation.add_error(ErrorType::Feature {
+                name: String::from(name),
+                gold: gold_value,
+                system: system_value,
+            });
+        }
+    }
+}
+
+fn f1(precision: f64, recall: f64) -> f64 {
+    if precision + recall == 0.0 {
+        return 0.0;
+    }
+
+    2.0 * precision * recall / (precision + recall)
+}
+
+fn ratio(part: usize, total: usize) -> f64 {
+    match total {
+        0 => 0.0,
+        _ => part as f64 / total as f64,
+    }
+}
+
+fn percent(ratio: f64) -> f64 {
+    ratio * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conllu::read_annotated_text;
+
+    const GOLD: &str = "# text = She works.
+1\tShe\tshe\tPRON\t_\tCase=Nom|Number=Sing\t2\tnsubj\t_\t_
+2\tworks\twork\tVERB\t_\tNumber=Sing|Person=3|Tense=Pres\t0\troot\t_\tSpaceAfter=No
+3\t.\t.\tPUNCT\t_\t_\t2\tpunct\t_\t_
+
+";
+
+    #[test]
+    fn it_scores_tokens_and_words() {
+        let gold = read_annotated_text(GOLD.as_bytes()).unwrap();
+        let mut system = AnnotatedText::new(&gold.text);
+
+        // "She" is missed as "Sh" and "e", "works" is found and "." is right.
+        for (start, end) in &[(0, 2), (2, 3), (4, 9), (9, 10)] {
+            system.tokens.push(Token::new(*start, *end, SegmentKind::Word));
+        }
+
+        system.tokens[2].lemma = Some(String::from("work"));
+        system.tokens[2].upos = Some(String::from("NOUN"));
+        system.tokens[2].features.insert("Number", "Sing");
+        system.tokens[3].lemma = Some(String::from("."));
+        system.tokens[3].upos = Some(String::from("PUNCT"));
+        system.tokens[3].kind = SegmentKind::Punctuation;
+
+        let evaluation = evaluate(&gold, &system);
+
+        assert_eq!((evaluation.token_precision(), evaluation.token_recall()), (0.5, 2.0 / 3.0));
+        assert_eq!(evaluation.lemmas, Score { correct: 2, total: 3 });
+        assert_eq!(evaluation.upos, Score { correct: 1, total: 3 });
+        assert_eq!(evaluation.features["Number"], Score { correct: 1, total: 2 });
+        assert_eq!(evaluation.features["Person"], Score { correct: 0, total: 1 });
+        assert_eq!(evaluation.analyzed, Score { correct: 1, total: 3 });
+        assert_eq!(evaluation.errors[&ErrorType::Tokenization], 1);
+        assert_eq!(evaluation.worst_errors(1)[0].0, &ErrorType::Tokenization);
+        assert_eq!(evaluation.errors[&ErrorType::Upos { gold: String::from("VERB"), system: String::from("NOUN") }], 1);
+    }
+
+    #[test]
+    fn it_evaluates_a_language_module() {
+        let gold = read_annotated_text(GOLD.as_bytes()).unwrap();
+        let evaluation = evaluate_language(&Omniglot::builtin(), "eng", &gold);
+
+        assert_eq!(evaluation.token_f1(), 1.0);
+        assert!(evaluation.lemmas.correct >= 2);
+        assert!(evaluation.to_string().starts_with("tokens\tP 100.00\tR 100.00\tF1 100.00\nwords\tP 100.00\tR 100.00\tF1 100.00\n"));
+    }
+
+    #[test]
+    fn it_scores_words_of_multiword_tokens_apart_from_tokens() {
+        let gold = read_annotated_text("# text = zum Haus\n1-2\tzum\t_\t_\t_\t_\t_\t_\t_\t_\n1\tzu\tzu\tADP\t_\t_\t3\tcase\t_\t_\n2\tdem\tder\tDET\t_\t_\t3\tdet\t_\t_\n3\tHaus\tHaus\tNOUN\t_\t_\t0\troot\t_\t_\n\n".as_bytes()).unwrap();
+        let mut system = AnnotatedText::new(&gold.text);
+
+        system.tokens.push(Token::new(0, 3, SegmentKind::Word));
+        system.tokens.push(Token::new(4, 8, SegmentKind::Word));
+
+        let evaluation = evaluate(&gold, &system);
+
+        assert_eq!(evaluation.token_f1(), 1.0);
+        assert_eq!((evaluation.gold_words, evaluation.system_words, evaluation.matched_words), (3, 2, 1));
+        assert_eq!(evaluation.errors[&ErrorType::WordSegmentation], 2);
+        assert!(!evaluation.errors.contains_key(&ErrorType::Tokenization));
+    }
+}
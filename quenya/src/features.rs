@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A bundle of grammatical features, written in the Universal Dependencies
+/// style as `Number=Sing|Person=3`. Features are kept sorted by name. In JSON
+/// they are an object such as `{"Number": "Sing", "Person": "3"}`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Features {
+    values: BTreeMap<String, String>,
+}
+
+impl Features {
+    pub fn new() -> Features {
+        Features {
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Parses a `Name=Value|Name=Value` string. Both an empty string and `_`
+    /// are read as an empty bundle. Returns `None` for malformed input.
+    pub fn parse(text: &str) -> Option<Features> {
+        let mut features = Features::new();
+
+        if text.is_empty() || text == "_" {
+            return Some(features);
+        }
+
+        for pair in text.split('|') {
+            let mut parts = pair.splitn(2, '=');
+
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) if !name.is_empty() && !value.is_empty() => {
+                    features.insert(name, value);
+                }
+                _ => return None,
+            }
+        }
+
+        Some(features)
+    }
+
+    /// Returns the bundle with `name` set to `value`.
+    pub fn with(mut self, name: &str, value: &str) -> Features {
+        self.insert(name, value);
+
+        self
+    }
+
+    pub fn insert(&mut self, name: &str, value: &str) {
+        self.values.insert(String::from(name), String::from(value));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|value| value.as_str())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.values.remove(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Whether every feature in `other` has the same value in this bundle.
+    pub fn contains(&self, other: &Features) -> bool {
+        other.iter().all(|(name, value)| self.get(name) == Some(value))
+    }
+}
+
+impl fmt::Display for Features {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "_");
+        }
+
+        let pairs: Vec<String> = self.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+
+        write!(f, "{}", pairs.join("|"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_universal_dependencies_features() {
+        let features = Features::parse("Person=3|Number=Sing").unwrap();
+
+        assert_eq!(features.get("Number"), Some("Sing"));
+        assert_eq!(features.get("Person"), Some("3"));
+        assert_eq!(features.to_string(), "Number=Sing|Person=3");
+    }
+
+    #[test]
+    fn it_parses_underscore_as_empty() {
+        assert_eq!(Features::parse("_").unwrap().is_empty(), true);
+    }
+
+    #[test]
+    fn it_rejects_malformed_features() {
+        assert_eq!(Features::parse("Number").is_none(), true);
+    }
+
+    #[test]
+    fn it_checks_whether_features_are_contained() {
+        let features = Features::new().with("Number", "Sing").with("Person", "3");
+
+        assert_eq!(features.contains(&Features::new().with("Number", "Sing")), true);
+        assert_eq!(features.contains(&Features::new().with("Number", "Plur")), false);
+    }
+}
@@ -0,0 +1,282 @@
+// Lexicons in the syntax of lexc:
+//
+//     Multichar_Symbols +Noun +Number=Plur ^
+//
+//     LEXICON Root
+//     Nouns ;
+//
+//     LEXICON Nouns
+//     cat Number ;
+//     mouse+Noun+Number=Plur:mice # ;
+//
+//     LEXICON Number
+//     +Noun:0 # ;
+//     +Noun+Number=Plur:^s # ;
+//
+// An entry is an optional `upper:lower` string, or a string that is the same
+// on both sides, followed by the lexicon it continues with; `#` ends the
+// word. The lexicon named `Root`, or else the first one, is where words
+// start. Strings are split into the declared multi-character symbols and
+// single characters, and the sides are aligned symbol by symbol.
+
+use std::collections::HashMap;
+use crate::fst::{FstError, Transducer, EPSILON, Word, split_pair, unescape, words};
+
+struct Entry {
+    upper: Vec<String>,
+    lower: Vec<String>,
+    continuation: String,
+    line: usize,
+}
+
+struct Lexicon {
+    name: String,
+    entries: Vec<Entry>,
+}
+
+/// Compiles a lexc file into a transducer from its upper to its lower side.
+pub fn compile(source: &str) -> Result<Transducer, FstError> {
+    let lexicons = parse(source)?;
+
+    if lexicons.is_empty() {
+        return Err(FstError::new(1, String::from("no LEXICON")));
+    }
+
+    let root = lexicons.iter().position(|lexicon| lexicon.name == "Root").unwrap_or(0);
+
+    let mut transducer = Transducer::new();
+    let mut states: HashMap<&str, usize> = HashMap::new();
+
+    for (index, lexicon) in lexicons.iter().enumerate() {
+        let state = if index == root { 0 } else { transducer.add_state() };
+
+        states.insert(&lexicon.name, state);
+    }
+
+    let end = transducer.add_state();
+    transducer.set_final(end, true);
+
+    let mut prefixes: HashMap<(usize, usize, usize), usize> = HashMap::new();
+
+    for lexicon in &lexicons {
+        let from = states[lexicon.name.as_str()];
+
+        for entry in &lexicon.entries {
+            let target = match entry.continuation.as_str() {
+                "#" => end,
+                name => *states.get(name).ok_or_else(|| FstError::new(entry.line, format!("unknown lexicon '{}'", name)))?,
+            };
+
+            add_path(&mut transducer, &mut prefixes, from, &entry.upper, &entry.lower, target);
+        }
+    }
+
+    Ok(transducer)
+}
+
+/// Adds the arcs of an entry. Entries of a lexicon share the states of their
+/// common prefix, which `prefixes` finds by the state and the symbol pair of
+/// the arc leading to them.
+fn add_path(transducer: &mut Transducer, prefixes: &mut HashMap<(usize, usize, usize), usize>, from: usize, upper: &[String], lower: &[String], target: usize) {
+    let length = upper.len().max(lower.len());
+    let mut state = from;
+
+    if length == 0 {
+        transducer.add_arc(from, EPSILON, EPSILON, target);
+        return;
+    }
+
+    for position in 0..length {
+        let input = upper.get(position).map_or(EPSILON, |symbol| transducer.symbol(symbol));
+        let output = lower.get(position).map_or(EPSILON, |symbol| transducer.symbol(symbol));
+
+        if position + 1 == length {
+            transducer.add_arc(state, input, output, target);
+            break;
+        }
+
+        state = match prefixes.get(&(state, input, output)) {
+            Some(&next) => next,
+            None => {
+                let next = transducer.add_state();
+
+                transducer.add_arc(state, input, output, next);
+                prefixes.insert((state, input, output), next);
+
+                next
+            }
+        };
+    }
+}
+
+fn parse(source: &str) -> Result<Vec<Lexicon>, FstError> {
+    let words = words(source);
+
+    let mut multichar: Vec<String> = Vec::new();
+    let mut lexicons: Vec<Lexicon> = Vec::new();
+    let mut entry: Vec<&Word> = Vec::new();
+    let mut position = 0;
+
+    while position < words.len() {
+        let word = &words[position];
+
+        position += 1;
+
+        match word.raw.as_str() {
+            "Multichar_Symbols" if entry.is_empty() => {
+                while position < words.len() && words[position].raw != "LEXICON" {
+                    multichar.push(unescape(&words[position].raw));
+                    position += 1;
+                }
+
+                // Longer symbols are tried first when strings are split.
+                multichar.sort_by_key(|symbol| std::cmp::Reverse(symbol.len()));
+            }
+            "LEXICON" if entry.is_empty() => {
+                let name = words.get(position).ok_or_else(|| FstError::new(word.line, String::from("expected a lexicon name")))?;
+
+                if lexicons.iter().any(|lexicon| lexicon.name == name.raw) {
+                    return Err(FstError::new(name.line, format!("lexicon '{}' is defined twice", name.raw)));
+                }
+
+                lexicons.push(Lexicon {
+                    name: name.raw.clone(),
+                    entries: Vec::new(),
+                });
+
+                position += 1;
+            }
+            ";" => {
+                let lexicon = lexicons.last_mut().ok_or_else(|| FstError::new(word.line, String::from("entry before the first LEXICON")))?;
+
+                lexicon.entries.push(parse_entry(&entry, word.line, &multichar)?);
+                entry.clear();
+            }
+            _ => entry.push(word),
+        }
+    }
+
+    if let Some(word) = entry.first() {
+        return Err(FstError::new(word.line, String::from("expected ';' after the entry")));
+    }
+
+    Ok(lexicons)
+}
+
+fn parse_entry(words: &[&Word], line: usize, multichar: &[String]) -> Result<Entry, FstError> {
+    let (string, continuation) = match words {
+        [continuation] => (None, continuation),
+        [string, continuation] => (Some(string), continuation),
+        _ => return Err(FstError::new(line, String::from("expected an entry as 'upper:lower Continuation ;'"))),
+    };
+
+    let (upper, lower) = match string {
+        Some(string) if string.raw.starts_with('<') => {
+            return Err(FstError::new(string.line, String::from("regular expression entries are not supported")));
+        }
+        Some(string) => match split_pair(&string.raw) {
+            (upper, Some(lower)) => (split_symbols(upper, multichar), split_symbols(lower, multichar)),
+            (both, None) => (split_symbols(both, multichar), split_symbols(both, multichar)),
+        },
+        None => (Vec::new(), Vec::new()),
+    };
+
+    Ok(Entry {
+        upper,
+        lower,
+        continuation: continuation.raw.clone(),
+        line: continuation.line,
+    })
+}
+
+/// Splits a string into multi-character symbols and single characters. An
+/// unescaped `0` is the empty string.
+pub(crate) fn split_symbols(raw: &str, multichar: &[String]) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    let mut rest = raw;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '%' {
+            let escaped: String = rest.chars().skip(1).take(1).collect();
+
+            rest = &rest[c.len_utf8() + escaped.len()..];
+
+            if !escaped.is_empty() {
+                symbols.push(escaped);
+            }
+
+            continue;
+        }
+
+        if let Some(symbol) = multichar.iter().find(|symbol| rest.starts_with(symbol.as_str())) {
+            symbols.push(symbol.clone());
+            rest = &rest[symbol.len()..];
+
+            continue;
+        }
+
+        if c != '0' {
+            symbols.push(c.to_string());
+        }
+
+        rest = &rest[c.len_utf8()..];
+    }
+
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOUNS: &str = "
+Multichar_Symbols +Noun +Number=Plur ^
+
+LEXICON Root
+Nouns ;
+
+LEXICON Nouns
+cat Number ;
+mouse+Noun+Number=Plur:mice # ;
+
+LEXICON Number
++Noun:0 # ;
++Noun+Number=Plur:^s # ;
+";
+
+    #[test]
+    fn it_compiles_continuation_classes() {
+        let transducer = compile(NOUNS).unwrap();
+
+        assert_eq!(transducer.lookup("cat+Noun+Number=Plur"), vec!["cat^s"]);
+        assert_eq!(transducer.lookup("cat+Noun"), vec!["cat"]);
+        assert_eq!(transducer.lookup_up("mice"), vec!["mouse+Noun+Number=Plur"]);
+        assert!(transducer.lookup_up("cats").is_empty());
+    }
+
+    #[test]
+    fn it_shares_the_prefixes_of_entries() {
+        let transducer = compile("LEXICON Root\ncats # ;\ncatch # ;\ncat # ;\n").unwrap();
+
+        // The start and end states, c, ca, cat and catc.
+        assert_eq!(transducer.num_states(), 6);
+        assert_eq!(transducer.lookup("catch"), vec!["catch"]);
+        assert_eq!(transducer.lookup("cat"), vec!["cat"]);
+    }
+
+    #[test]
+    fn it_splits_symbols() {
+        let multichar = vec![String::from("+Number=Plur"), String::from("+Noun")];
+
+        assert_eq!(split_symbols("a%0+Noun+Number=Plur0", &multichar), vec!["a", "0", "+Noun", "+Number=Plur"]);
+        assert_eq!(split_symbols("%+Noun", &multichar), vec!["+", "N", "o", "u", "n"]);
+    }
+
+    #[test]
+    fn it_reports_errors() {
+        assert_eq!(compile("LEXICON Root\ncat Nouns ;").unwrap_err(), FstError::new(2, String::from("unknown lexicon 'Nouns'")));
+        assert_eq!(compile("LEXICON Root\ncat #").unwrap_err().line, 2);
+        assert!(compile("cat # ;").is_err());
+        assert!(compile("LEXICON Root\n< a+ > # ;").is_err());
+    }
+}
@@ -0,0 +1,344 @@
+// Finite-state morphology. A lexicon written in lexc syntax compiles to a
+// transducer from analyses such as `carry+Verb+Tense=Past` to intermediate
+// forms such as `carry^ed`, and rules compile to transducers that rewrite
+// those into surface forms such as `carried`. Composed, they give a single
+// transducer that generates forms when looked up downwards and analyses them
+// when looked up upwards.
+//
+// Both file formats share their lexical conventions: `!` starts a comment,
+// `%` makes the next character literal, `0` is the empty string and `;` ends
+// an entry or a rule.
+
+use std::fmt;
+use crate::features::Features;
+use crate::language::Analysis;
+use crate::lexicon::Lexeme;
+use crate::morphology;
+use crate::text::part_for_upos;
+
+pub mod lexc;
+pub mod rules;
+pub mod transducer;
+
+pub use self::rules::RuleSet;
+pub use self::transducer::{Arc, Direction, Transducer, EPSILON};
+
+/// The most tags `Morphology` follows after a lemma when it generates forms.
+const MAX_TAGS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FstError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl FstError {
+    pub(crate) fn new(line: usize, message: String) -> FstError {
+        FstError { line, message }
+    }
+}
+
+impl fmt::Display for FstError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for FstError {}
+
+/// A word of a lexc or rules file, with its escapes still in place.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Word {
+    pub raw: String,
+    pub line: usize,
+}
+
+/// Splits a file into words separated by whitespace. Comments are dropped
+/// and `;` is a word of its own.
+pub(crate) fn words(source: &str) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut current = String::new();
+    let mut line = 1;
+    let mut chars = source.chars();
+
+    let mut finish = |current: &mut String, line: usize| {
+        if !current.is_empty() {
+            words.push(Word {
+                raw: std::mem::take(current),
+                line,
+            });
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => {
+                current.push(c);
+
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            '!' => {
+                finish(&mut current, line);
+
+                for skipped in chars.by_ref() {
+                    if skipped == '\n' {
+                        line += 1;
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                finish(&mut current, line);
+                current.push(';');
+                finish(&mut current, line);
+            }
+            _ if c.is_whitespace() => {
+                finish(&mut current, line);
+
+                if c == '\n' {
+                    line += 1;
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+
+    finish(&mut current, line);
+
+    words
+}
+
+/// Removes the `%` escapes from a word.
+pub(crate) fn unescape(raw: &str) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => text.extend(chars.next()),
+            _ => text.push(c),
+        }
+    }
+
+    text
+}
+
+/// Splits a word at its first unescaped `:`.
+pub(crate) fn split_pair(raw: &str) -> (&str, Option<&str>) {
+    let mut escaped = false;
+
+    for (index, c) in raw.char_indices() {
+        match c {
+            '%' if !escaped => escaped = true,
+            ':' if !escaped => return (&raw[..index], Some(&raw[index + 1..])),
+            _ => escaped = false,
+        }
+    }
+
+    (raw, None)
+}
+
+/// Whether a symbol is a tag such as `+Verb` or `+Tense=Past`.
+pub fn is_tag(symbol: &str) -> bool {
+    symbol.len() > 1 && symbol.starts_with('+')
+}
+
+/// Reads an analysis written as a lemma followed by tags. `+Noun`, `+Verb`,
+/// `+Adj` and `+Adv` (or their UPOS names) give the part of speech and
+/// `+Name=Value` a feature. All tags are kept in the analysis's tag.
+pub fn analysis_from_symbols(symbols: &[String]) -> Analysis {
+    let mut lemma = String::new();
+    let mut tags = String::new();
+    let mut pos = None;
+    let mut features = Features::new();
+
+    for symbol in symbols {
+        if !is_tag(symbol) {
+            lemma.push_str(symbol);
+            continue;
+        }
+
+        tags.push_str(symbol);
+
+        let tag = &symbol[1..];
+
+        match tag.split_once('=') {
+            Some((name, value)) => features.insert(name, value),
+            None => pos = pos.or_else(|| part_for_upos(&tag.to_uppercase())),
+        }
+    }
+
+    let mut analysis = Analysis::new(&lemma, pos, features);
+
+    if !tags.is_empty() {
+        analysis.tag = Some(tags);
+    }
+
+    analysis
+}
+
+/// The morphology of a language as one transducer from analyses to forms.
+#[derive(Clone, Debug)]
+pub struct Morphology {
+    transducer: Transducer,
+}
+
+impl Morphology {
+    pub fn new(transducer: Transducer) -> Morphology {
+        Morphology { transducer }
+    }
+
+    /// Compiles a lexc lexicon and the rules that turn its lower side into
+    /// surface forms.
+    pub fn compile(lexc: &str, rules: Option<&str>) -> Result<Morphology, FstError> {
+        let lexicon = lexc::compile(lexc)?;
+
+        let rules = match rules {
+            Some(rules) => Some(RuleSet::parse(rules)?),
+            None => None,
+        };
+
+        Ok(Morphology::with_rules(&lexicon, rules.as_ref()))
+    }
+
+    /// Composes a compiled lexicon with rules over the symbols of its lower
+    /// side.
+    pub fn with_rules(lexicon: &Transducer, rules: Option<&RuleSet>) -> Morphology {
+        let transducer = match rules {
+            Some(rules) => lexicon.compose(&rules.compile(&lexicon.output_symbols())),
+            None => lexicon.clone(),
+        };
+
+        Morphology { transducer }
+    }
+
+    pub fn transducer(&self) -> &Transducer {
+        &self.transducer
+    }
+
+    /// The analyses of a form, such as `carry+Verb+Tense=Past` for `carried`.
+    pub fn analyses(&self, form: &str) -> Vec<String> {
+        self.transducer.lookup_up(form)
+    }
+
+    /// The forms of an analysis.
+    pub fn generate(&self, analysis: &str) -> Vec<String> {
+        self.transducer.lookup(analysis)
+    }
+}
+
+impl morphology::Inflector for Morphology {
+    /// The forms of every analysis of the lemma whose tags have the part of
+    /// speech of the lexeme and include the features.
+    fn inflect(&self, lexeme: &Lexeme, features: &Features) -> Vec<String> {
+        let mut forms: Vec<String> = Vec::new();
+
+        for (upper, lower) in self.transducer.complete(&lexeme.lemma, is_tag, MAX_TAGS) {
+            let analysis = analysis_from_symbols(&upper);
+            let form = lower.concat();
+
+            if analysis.pos.is_none_or(|pos| pos == lexeme.pos) && analysis.features.contains(features) && !forms.contains(&form) {
+                forms.push(form);
+            }
+        }
+
+        forms
+    }
+}
+
+impl morphology::Analyzer for Morphology {
+    fn analyze(&self, form: &str) -> Vec<Analysis> {
+        let mut analyses: Vec<Analysis> = Vec::new();
+
+        for symbols in self.transducer.apply(form, Direction::Up) {
+            let analysis = analysis_from_symbols(&symbols);
+
+            if !analyses.contains(&analysis) {
+                analyses.push(analysis);
+            }
+        }
+
+        analyses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wn::pos::Part;
+    use crate::morphology::round_trip;
+
+    const LEXC: &str = "
+Multichar_Symbols +Verb +Tense=Past +Tense=Pres +VerbForm=Part +VerbForm=Fin +Number=Sing +Person=3 ^
+
+LEXICON Root
+Verbs ;
+
+LEXICON Verbs
+walk Regular ;
+carry Regular ;
+stop Regular ;
+go+Verb+Tense=Past+VerbForm=Fin:went # ;   ! irregular
+
+LEXICON Regular
++Verb+Tense=Past+VerbForm=Fin:^ed # ;
++Verb+Tense=Pres+VerbForm=Part:^ing # ;
++Verb+Number=Sing+Person=3+Tense=Pres+VerbForm=Fin:^s # ;
+";
+
+    const RULES: &str = "
+Sets
+Consonant = b c d f g h j k l m n p q r s t v w x z ;
+Vowel = a e i o u ;
+
+Rules
+y -> i || Consonant _ ^ e ;
+y -> i e || Consonant _ ^ s ;
+p -> p p || # s t o _ ^ Vowel ;
+^ -> 0 ;
+";
+
+    #[test]
+    fn it_reads_words() {
+        let words: Vec<String> = words("a:b Cont ; ! comment\n%;x%!y;").into_iter().map(|word| word.raw).collect();
+
+        assert_eq!(words, vec!["a:b", "Cont", ";", "%;x%!y", ";"]);
+        assert_eq!(split_pair("%:a:b"), ("%:a", Some("b")));
+        assert_eq!(unescape("%+Pl%0"), "+Pl0");
+    }
+
+    #[test]
+    fn it_generates_and_analyzes() {
+        let morphology = Morphology::compile(LEXC, Some(RULES)).unwrap();
+
+        assert_eq!(morphology.generate("carry+Verb+Tense=Past+VerbForm=Fin"), vec!["carried"]);
+        assert_eq!(morphology.generate("carry+Verb+Number=Sing+Person=3+Tense=Pres+VerbForm=Fin"), vec!["carries"]);
+        assert_eq!(morphology.generate("carry+Verb+Tense=Pres+VerbForm=Part"), vec!["carrying"]);
+        assert_eq!(morphology.generate("stop+Verb+Tense=Past+VerbForm=Fin"), vec!["stopped"]);
+        assert_eq!(morphology.analyses("walked"), vec!["walk+Verb+Tense=Past+VerbForm=Fin"]);
+        assert_eq!(morphology.analyses("went"), vec!["go+Verb+Tense=Past+VerbForm=Fin"]);
+        assert!(morphology.analyses("carryed").is_empty());
+    }
+
+    #[test]
+    fn it_agrees_with_itself() {
+        let morphology = Morphology::compile(LEXC, Some(RULES)).unwrap();
+
+        let analyses = morphology::Analyzer::analyze(&morphology, "carried");
+
+        assert_eq!(analyses[0].lemma, "carry");
+        assert_eq!(analyses[0].pos, Some(Part::Verb));
+        assert_eq!(analyses[0].features.get("Tense"), Some("Past"));
+
+        let features: Vec<Features> = ["Tense=Past", "Tense=Pres|VerbForm=Part", "Person=3"]
+            .iter()
+            .map(|features| Features::parse(features).unwrap())
+            .collect();
+
+        for lemma in &["walk", "carry", "stop"] {
+            assert_eq!(round_trip(&morphology, &Lexeme::new(lemma, Part::Verb), &features), vec![]);
+        }
+    }
+}
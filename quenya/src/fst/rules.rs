@@ -0,0 +1,567 @@
+// Rules that rewrite the lower side of a lexicon into surface forms:
+//
+//     Sets
+//     Consonant = b c d f g h j k l m n p q r s t v w x z ;
+//
+//     Rules
+//     y -> i || Consonant _ ^ e ;
+//     ^ -> 0 ;
+//     e:0 <=> Consonant _ ^ e ;
+//
+// A replace rule `a -> b c || left _ right` rewrites every `a` between the
+// contexts as `b c`; with `(->)` the rewriting is optional. A two-level rule
+// `a:b` rewrites `a` as `b` with `<=>` exactly in its contexts, with `=>` only
+// in its contexts and with `<=` always in its contexts. The target is one
+// symbol or set, the replacement any number of symbols or `0`, and contexts
+// are symbols, sets, `?` for any symbol and `#` for the word boundary,
+// separated by spaces. Contexts are matched against the side the rule reads.
+//
+// Replace rules apply one after the other, each to the output of the one
+// before. Two-level rules that follow each other apply together, each
+// reading the same input, so that they cannot feed or bleed one another.
+
+use std::collections::{HashMap, VecDeque};
+use crate::fst::{FstError, Transducer, EPSILON, Word, split_pair, unescape, words};
+
+/// The longest context a rule may have.
+const MAX_CONTEXT: usize = 63;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Element {
+    Symbol(String),
+    Set(Vec<String>),
+    Any,
+    Boundary,
+}
+
+impl Element {
+    /// Whether the element matches a symbol of the alphabet, or the word
+    /// boundary for `None`.
+    fn matches(&self, symbol: Option<&str>) -> bool {
+        match (self, symbol) {
+            (Element::Symbol(expected), Some(symbol)) => expected == symbol,
+            (Element::Set(members), Some(symbol)) => members.iter().any(|member| member == symbol),
+            (Element::Any, Some(_)) => true,
+            (Element::Boundary, None) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Rule {
+    pub target: Element,
+    pub replacement: Vec<String>,
+    pub left: Vec<Element>,
+    pub right: Vec<Element>,
+    /// Whether the target may only be rewritten in the context.
+    pub restricted: bool,
+    /// Whether the target must be rewritten in the context.
+    pub obligatory: bool,
+    pub two_level: bool,
+}
+
+/// The rules of a rules file, in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RuleSet {
+    sets: HashMap<String, Vec<String>>,
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    pub fn parse(source: &str) -> Result<RuleSet, FstError> {
+        let mut rules = RuleSet::default();
+        let mut statement: Vec<&Word> = Vec::new();
+
+        let words = words(source);
+
+        for word in &words {
+            match word.raw.as_str() {
+                "Sets" | "Rules" if statement.is_empty() => continue,
+                ";" => {
+                    rules.parse_statement(&statement, word.line)?;
+                    statement.clear();
+                }
+                _ => statement.push(word),
+            }
+        }
+
+        if let Some(word) = statement.first() {
+            return Err(FstError::new(word.line, String::from("expected ';' after the rule")));
+        }
+
+        Ok(rules)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The symbols the rules write.
+    pub fn output_symbols(&self) -> Vec<&str> {
+        let mut symbols: Vec<&str> = Vec::new();
+
+        for symbol in self.rules.iter().flat_map(|rule| &rule.replacement) {
+            if !symbols.contains(&symbol.as_str()) {
+                symbols.push(symbol);
+            }
+        }
+
+        symbols
+    }
+
+    /// Compiles the rules into one transducer over an alphabet, which should
+    /// hold every symbol the rules will read. Other symbols are not accepted.
+    pub fn compile(&self, alphabet: &[&str]) -> Transducer {
+        let mut alphabet: Vec<String> = alphabet.iter().map(|symbol| String::from(*symbol)).collect();
+        let mut cascade: Option<Transducer> = None;
+        let mut start = 0;
+
+        while start < self.rules.len() {
+            let end = match self.rules[start].two_level {
+                true => start + self.rules[start..].iter().take_while(|rule| rule.two_level).count(),
+                false => start + 1,
+            };
+
+            let group = &self.rules[start..end];
+            let transducer = compile_parallel(group, &alphabet);
+
+            for symbol in group.iter().flat_map(|rule| &rule.replacement) {
+                if !alphabet.contains(symbol) {
+                    alphabet.push(symbol.clone());
+                }
+            }
+
+            cascade = Some(match cascade {
+                Some(previous) => previous.compose(&transducer),
+                None => transducer,
+            });
+
+            start = end;
+        }
+
+        cascade.unwrap_or_else(|| Transducer::identity(&alphabet.iter().map(String::as_str).collect::<Vec<&str>>()))
+    }
+
+    fn parse_statement(&mut self, statement: &[&Word], line: usize) -> Result<(), FstError> {
+        if statement.len() >= 2 && statement[1].raw == "=" {
+            return self.parse_set(statement, line);
+        }
+
+        let operator = statement
+            .iter()
+            .position(|word| matches!(word.raw.as_str(), "->" | "(->)" | "<=>" | "=>" | "<="))
+            .ok_or_else(|| FstError::new(line, String::from("expected a rule with '->', '(->)', '<=>', '=>' or '<='")))?;
+
+        let (before, after) = (&statement[..operator], &statement[operator + 1..]);
+
+        let target = match before {
+            [target] => target,
+            _ => return Err(FstError::new(line, String::from("expected one symbol before the operator"))),
+        };
+
+        let rule = match statement[operator].raw.as_str() {
+            operator @ ("->" | "(->)") => {
+                let (replacement, context) = match after.iter().position(|word| word.raw == "||") {
+                    Some(bars) => (&after[..bars], Some(&after[bars + 1..])),
+                    None => (after, None),
+                };
+
+                if replacement.is_empty() {
+                    return Err(FstError::new(line, String::from("expected a replacement or 0")));
+                }
+
+                let (left, right) = match context {
+                    Some(context) => self.parse_context(context, line)?,
+                    None => (Vec::new(), Vec::new()),
+                };
+
+                Rule {
+                    target: self.parse_target(&target.raw, line)?,
+                    replacement: replacement.iter().filter(|word| word.raw != "0").map(|word| unescape(&word.raw)).collect(),
+                    left,
+                    right,
+                    restricted: true,
+                    obligatory: operator == "->",
+                    two_level: false,
+                }
+            }
+            operator => {
+                let (symbol, replacement) = match split_pair(&target.raw) {
+                    (symbol, Some(replacement)) => (symbol, replacement),
+                    _ => return Err(FstError::new(line, String::from("expected a pair such as 'a:b' before the operator"))),
+                };
+
+                let (left, right) = self.parse_context(after, line)?;
+
+                Rule {
+                    target: self.parse_target(symbol, line)?,
+                    replacement: match replacement {
+                        "0" => Vec::new(),
+                        replacement => vec![unescape(replacement)],
+                    },
+                    left,
+                    right,
+                    restricted: operator != "<=",
+                    obligatory: operator != "=>",
+                    two_level: true,
+                }
+            }
+        };
+
+        self.rules.push(rule);
+
+        Ok(())
+    }
+
+    fn parse_set(&mut self, statement: &[&Word], line: usize) -> Result<(), FstError> {
+        let name = &statement[0].raw;
+
+        if self.sets.contains_key(name) {
+            return Err(FstError::new(line, format!("set '{}' is defined twice", name)));
+        }
+
+        let mut members: Vec<String> = Vec::new();
+
+        for word in &statement[2..] {
+            match self.sets.get(&word.raw) {
+                Some(set) => members.extend(set.iter().cloned()),
+                None => members.push(unescape(&word.raw)),
+            }
+        }
+
+        self.sets.insert(name.clone(), members);
+
+        Ok(())
+    }
+
+    fn parse_target(&self, raw: &str, line: usize) -> Result<Element, FstError> {
+        match self.parse_element(raw) {
+            Element::Any | Element::Boundary => Err(FstError::new(line, String::from("the target must be a symbol or a set"))),
+            element => Ok(element),
+        }
+    }
+
+    fn parse_element(&self, raw: &str) -> Element {
+        match raw {
+            "?" => Element::Any,
+            "#" => Element::Boundary,
+            _ => match self.sets.get(raw) {
+                Some(members) => Element::Set(members.clone()),
+                None => Element::Symbol(unescape(raw)),
+            },
+        }
+    }
+
+    fn parse_context(&self, context: &[&Word], line: usize) -> Result<(Vec<Element>, Vec<Element>), FstError> {
+        let position = match context.iter().position(|word| word.raw == "_") {
+            Some(position) if !context[position + 1..].iter().any(|word| word.raw == "_") => position,
+            _ => return Err(FstError::new(line, String::from("expected a context with one '_'"))),
+        };
+
+        let left: Vec<Element> = context[..position].iter().map(|word| self.parse_element(&word.raw)).collect();
+        let right: Vec<Element> = context[position + 1..].iter().map(|word| self.parse_element(&word.raw)).collect();
+
+        if left.len() > MAX_CONTEXT || right.len() > MAX_CONTEXT {
+            return Err(FstError::new(line, format!("contexts are limited to {} symbols", MAX_CONTEXT)));
+        }
+
+        Ok((left, right))
+    }
+}
+
+/// How far a rule has got at one position of the input: the lengths of the
+/// prefixes of the left context that end here, and the progress of the
+/// right contexts that must and must not follow earlier decisions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct RuleState {
+    left: u64,
+    must: u64,
+    must_not: u64,
+}
+
+/// A rule with its elements matched against every symbol of the alphabet,
+/// where the word boundary is the symbol after the last.
+struct Table<'a> {
+    rule: &'a Rule,
+    target: Vec<bool>,
+    left: Vec<Vec<bool>>,
+    right: Vec<Vec<bool>>,
+}
+
+impl<'a> Table<'a> {
+    fn new(rule: &'a Rule, alphabet: &[String]) -> Table<'a> {
+        let symbols: Vec<Option<&str>> = alphabet.iter().map(|symbol| Some(symbol.as_str())).chain(Some(None)).collect();
+        let table = |element: &Element| symbols.iter().map(|symbol| element.matches(*symbol)).collect::<Vec<bool>>();
+
+        Table {
+            rule,
+            target: table(&rule.target),
+            left: rule.left.iter().map(table).collect(),
+            right: rule.right.iter().map(table).collect(),
+        }
+    }
+
+    fn step_left(&self, left: u64, symbol: usize) -> u64 {
+        let mut next = 1;
+
+        for (length, element) in self.left.iter().enumerate() {
+            if left & (1 << length) != 0 && element[symbol] {
+                next |= 1 << (length + 1);
+            }
+        }
+
+        next
+    }
+
+    fn in_left_context(&self, left: u64) -> bool {
+        left & (1 << self.left.len()) != 0
+    }
+
+    /// Advances the right contexts over a symbol, or returns `None` if one
+    /// that must follow does not or one that must not follow does.
+    fn step_right(&self, state: &RuleState, symbol: usize) -> Option<(u64, u64)> {
+        let mut must = 0;
+        let mut must_not = 0;
+
+        for (progress, element) in self.right.iter().enumerate() {
+            let next = progress + 1 < self.right.len();
+
+            if state.must & (1 << progress) != 0 {
+                if !element[symbol] {
+                    return None;
+                }
+
+                if next {
+                    must |= 1 << (progress + 1);
+                }
+            }
+
+            if state.must_not & (1 << progress) != 0 && element[symbol] {
+                if !next {
+                    return None;
+                }
+
+                must_not |= 1 << (progress + 1);
+            }
+        }
+
+        Some((must, must_not))
+    }
+}
+
+/// Compiles rules that read the same input into one transducer. Its states
+/// are the states of the rules, built as they are reached.
+fn compile_parallel(rules: &[Rule], alphabet: &[String]) -> Transducer {
+    let tables: Vec<Table> = rules.iter().map(|rule| Table::new(rule, alphabet)).collect();
+    let boundary = alphabet.len();
+
+    let mut transducer = Transducer::new();
+    let ids: Vec<usize> = alphabet.iter().map(|symbol| transducer.symbol(symbol)).collect();
+
+    let start: Vec<RuleState> = tables
+        .iter()
+        .map(|table| RuleState {
+            left: table.step_left(1, boundary),
+            must: 0,
+            must_not: 0,
+        })
+        .collect();
+
+    let mut states: HashMap<Vec<RuleState>, usize> = HashMap::new();
+    let mut queue: VecDeque<Vec<RuleState>> = VecDeque::new();
+
+    states.insert(start.clone(), 0);
+    queue.push_back(start);
+
+    while let Some(current) = queue.pop_front() {
+        let from = states[&current];
+        let ends = tables.iter().zip(&current).all(|(table, state)| table.step_right(state, boundary).is_some());
+
+        transducer.set_final(from, ends);
+
+        for (symbol, &id) in ids.iter().enumerate() {
+            let stepped: Option<Vec<(u64, u64)>> = tables.iter().zip(&current).map(|(table, state)| table.step_right(state, symbol)).collect();
+
+            let stepped = match stepped {
+                Some(stepped) => stepped,
+                None => continue,
+            };
+
+            // Every symbol may stay as it is or be rewritten by a rule that
+            // targets it; the rules then say which of these may be chosen.
+            let choices = Some(None).into_iter().chain((0..tables.len()).filter(|&rule| tables[rule].target[symbol]).map(Some));
+
+            for choice in choices {
+                let next = match next_states(&tables, &current, &stepped, symbol, choice) {
+                    Some(next) => next,
+                    None => continue,
+                };
+
+                let target = match states.get(&next) {
+                    Some(&target) => target,
+                    None => {
+                        let target = transducer.add_state();
+
+                        states.insert(next.clone(), target);
+                        queue.push_back(next);
+
+                        target
+                    }
+                };
+
+                let output: Vec<usize> = match choice {
+                    Some(rule) => rules[rule].replacement.iter().map(|symbol| transducer.symbol(symbol)).collect(),
+                    None => vec![id],
+                };
+
+                add_output(&mut transducer, from, id, &output, target);
+            }
+        }
+    }
+
+    transducer
+}
+
+/// The states of the rules after a symbol is left as it is (`None`) or
+/// rewritten by a rule, or `None` if a rule forbids that.
+fn next_states(tables: &[Table], current: &[RuleState], stepped: &[(u64, u64)], symbol: usize, choice: Option<usize>) -> Option<Vec<RuleState>> {
+    let mut next: Vec<RuleState> = Vec::with_capacity(tables.len());
+
+    for (rule, table) in tables.iter().enumerate() {
+        let (mut must, mut must_not) = stepped[rule];
+
+        if table.target[symbol] {
+            let in_context = table.in_left_context(current[rule].left);
+
+            if choice == Some(rule) {
+                if table.rule.restricted {
+                    if !in_context {
+                        return None;
+                    }
+
+                    if !table.right.is_empty() {
+                        must |= 1;
+                    }
+                }
+            } else if table.rule.obligatory && in_context {
+                if table.right.is_empty() {
+                    return None;
+                }
+
+                must_not |= 1;
+            }
+        }
+
+        next.push(RuleState {
+            left: table.step_left(current[rule].left, symbol),
+            must,
+            must_not,
+        });
+    }
+
+    Some(next)
+}
+
+fn add_output(transducer: &mut Transducer, from: usize, input: usize, output: &[usize], target: usize) {
+    match output {
+        [] => transducer.add_arc(from, input, EPSILON, target),
+        [first, rest @ ..] => {
+            let mut state = from;
+            let mut input = input;
+            let mut symbol = *first;
+
+            for next in rest {
+                let middle = transducer.add_state();
+
+                transducer.add_arc(state, input, symbol, middle);
+
+                state = middle;
+                input = EPSILON;
+                symbol = *next;
+            }
+
+            transducer.add_arc(state, input, symbol, target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(source: &str, alphabet: &[&str]) -> Transducer {
+        RuleSet::parse(source).unwrap().compile(alphabet)
+    }
+
+    #[test]
+    fn it_replaces_in_context() {
+        let rules = compile("a -> b || c _ d ;", &["a", "b", "c", "d"]);
+
+        assert_eq!(rules.lookup("cad"), vec!["cbd"]);
+        assert_eq!(rules.lookup("cab"), vec!["cab"]);
+        assert_eq!(rules.lookup("acadca"), vec!["acbdca"]);
+
+        let mut inputs = rules.lookup_up("cbd");
+        inputs.sort();
+
+        assert_eq!(inputs, vec!["cad", "cbd"]);
+    }
+
+    #[test]
+    fn it_reads_contexts_from_the_input() {
+        let rules = compile("a -> b || a _ ;", &["a", "b"]);
+
+        assert_eq!(rules.lookup("aaa"), vec!["abb"]);
+
+        let edges = compile("Vowel = a e ;\na -> 0 || # _ ;\ne -> a e || _ Vowel # ;", &["a", "e", "k"]);
+
+        assert_eq!(edges.lookup("akee"), vec!["kaee"]);
+        assert_eq!(edges.lookup("ka"), vec!["ka"]);
+    }
+
+    #[test]
+    fn it_replaces_optionally() {
+        let rules = compile("a (->) b c ;", &["a", "b", "c"]);
+
+        let mut outputs = rules.lookup("aa");
+        outputs.sort();
+
+        assert_eq!(outputs, vec!["aa", "abc", "bca", "bcbc"]);
+    }
+
+    #[test]
+    fn it_applies_two_level_rules_in_parallel() {
+        let parallel = compile("a:b <=> _ ;\nb:a <=> _ ;", &["a", "b"]);
+        let cascade = compile("a -> b ;\nb -> a ;", &["a", "b"]);
+
+        assert_eq!(parallel.lookup("ab"), vec!["ba"]);
+        assert_eq!(cascade.lookup("ab"), vec!["aa"]);
+
+        let restricted = compile("e:0 => _ s ;", &["e", "s"]);
+        let mut outputs = restricted.lookup("ees");
+        outputs.sort();
+
+        assert_eq!(outputs, vec!["ees", "es"]);
+
+        let required = compile("e:i <= _ s ;", &["e", "i", "s"]);
+        let mut outputs = required.lookup("ees");
+        outputs.sort();
+
+        assert_eq!(outputs, vec!["eis", "iis"]);
+    }
+
+    #[test]
+    fn it_reports_errors() {
+        assert_eq!(RuleSet::parse("a b ;").unwrap_err().line, 1);
+        assert!(RuleSet::parse("a -> b || c d ;").is_err());
+        assert!(RuleSet::parse("a <=> _ b ;").is_err());
+        assert_eq!(RuleSet::parse("\n? -> b ;").unwrap_err(), FstError::new(2, String::from("the target must be a symbol or a set")));
+        assert!(RuleSet::parse("a -> b").is_err());
+        assert_eq!(RuleSet::parse("Sets\nV = a e ;\nRules\nV -> 0 || _ # ;").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,611 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::OnceLock;
+
+/// The symbol id of the empty string.
+pub const EPSILON: usize = 0;
+
+/// An arc from one state to another that reads `input` and writes `output`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Arc {
+    pub input: usize,
+    pub output: usize,
+    pub target: usize,
+}
+
+/// The side of a transducer a lookup starts from. Looking up `Down` reads
+/// the input (upper) side and returns the output (lower) side, and `Up` the
+/// other way around.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Down,
+    Up,
+}
+
+/// A finite-state transducer over named symbols. Symbols may be single
+/// characters or multi-character symbols such as `+Verb`; a symbol id refers
+/// to the symbol table of the transducer it comes from. State 0 is the start
+/// state.
+#[derive(Clone, Debug)]
+pub struct Transducer {
+    symbols: Vec<String>,
+    index: HashMap<String, usize>,
+    arcs: Vec<Vec<Arc>>,
+    /// Every arc with the state it leaves, so that arcs are added once.
+    present: HashSet<(usize, Arc)>,
+    finals: Vec<bool>,
+    /// The symbols of the input and the output side, longest first, for
+    /// `tokenize`. They are cleared whenever an arc changes.
+    sides: [OnceLock<Vec<usize>>; 2],
+}
+
+impl Transducer {
+    /// The empty transducer, which accepts nothing.
+    pub fn new() -> Transducer {
+        Transducer {
+            symbols: vec![String::new()],
+            index: HashMap::new(),
+            arcs: vec![Vec::new()],
+            present: HashSet::new(),
+            finals: vec![false],
+            sides: Default::default(),
+        }
+    }
+
+    /// The transducer that maps the empty string to itself.
+    pub fn epsilon() -> Transducer {
+        let mut transducer = Transducer::new();
+        transducer.set_final(0, true);
+
+        transducer
+    }
+
+    /// Maps one sequence of symbols to another. The shorter sequence is padded
+    /// with epsilons at the end.
+    pub fn from_pair(upper: &[&str], lower: &[&str]) -> Transducer {
+        let mut transducer = Transducer::new();
+        let mut state = 0;
+
+        for position in 0..upper.len().max(lower.len()) {
+            let input = upper.get(position).map_or(EPSILON, |symbol| transducer.symbol(symbol));
+            let output = lower.get(position).map_or(EPSILON, |symbol| transducer.symbol(symbol));
+            let target = transducer.add_state();
+
+            transducer.add_arc(state, input, output, target);
+            state = target;
+        }
+
+        transducer.set_final(state, true);
+
+        transducer
+    }
+
+    /// Maps every symbol of an alphabet to itself, any number of times.
+    pub fn identity(alphabet: &[&str]) -> Transducer {
+        let mut transducer = Transducer::epsilon();
+
+        for symbol in alphabet {
+            let id = transducer.symbol(symbol);
+            transducer.add_arc(0, id, id, 0);
+        }
+
+        transducer
+    }
+
+    /// The id of a symbol, adding it to the symbol table if it is new.
+    pub fn symbol(&mut self, name: &str) -> usize {
+        if name.is_empty() {
+            return EPSILON;
+        }
+
+        if let Some(&id) = self.index.get(name) {
+            return id;
+        }
+
+        self.symbols.push(String::from(name));
+        self.index.insert(String::from(name), self.symbols.len() - 1);
+
+        self.symbols.len() - 1
+    }
+
+    pub fn symbol_id(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    /// The name of a symbol, which is empty for `EPSILON`.
+    pub fn symbol_name(&self, id: usize) -> &str {
+        &self.symbols[id]
+    }
+
+    pub fn add_state(&mut self) -> usize {
+        self.arcs.push(Vec::new());
+        self.finals.push(false);
+
+        self.arcs.len() - 1
+    }
+
+    pub fn add_arc(&mut self, from: usize, input: usize, output: usize, target: usize) {
+        let arc = Arc { input, output, target };
+
+        if self.present.insert((from, arc)) {
+            self.arcs[from].push(arc);
+            self.sides = Default::default();
+        }
+    }
+
+    pub fn set_final(&mut self, state: usize, is_final: bool) {
+        self.finals[state] = is_final;
+    }
+
+    pub fn is_final(&self, state: usize) -> bool {
+        self.finals[state]
+    }
+
+    pub fn arcs(&self, state: usize) -> &[Arc] {
+        &self.arcs[state]
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.arcs.len()
+    }
+
+    pub fn num_arcs(&self) -> usize {
+        self.arcs.iter().map(Vec::len).sum()
+    }
+
+    /// The symbols read on the input side.
+    pub fn input_symbols(&self) -> Vec<&str> {
+        self.side_symbols(|arc| arc.input)
+    }
+
+    /// The symbols written on the output side.
+    pub fn output_symbols(&self) -> Vec<&str> {
+        self.side_symbols(|arc| arc.output)
+    }
+
+    fn side_symbols(&self, side: impl Fn(&Arc) -> usize) -> Vec<&str> {
+        let mut ids: Vec<usize> = self.arcs.iter().flatten().map(side).filter(|&id| id != EPSILON).collect();
+
+        ids.sort_unstable();
+        ids.dedup();
+
+        ids.into_iter().map(|id| self.symbol_name(id)).collect()
+    }
+
+    /// Copies the states of another transducer into this one, returning the
+    /// number of the state its start state became.
+    fn append(&mut self, other: &Transducer) -> usize {
+        let offset = self.arcs.len();
+        let symbols: Vec<usize> = other.symbols.iter().map(|name| self.symbol(name)).collect();
+
+        for (state, arcs) in other.arcs.iter().enumerate() {
+            self.add_state();
+            self.set_final(offset + state, other.finals[state]);
+
+            for arc in arcs {
+                self.add_arc(offset + state, symbols[arc.input], symbols[arc.output], offset + arc.target);
+            }
+        }
+
+        offset
+    }
+
+    /// The transducer that does what either of two transducers does.
+    pub fn union(&self, other: &Transducer) -> Transducer {
+        let mut result = Transducer::new();
+
+        let first = result.append(self);
+        let second = result.append(other);
+
+        result.add_arc(0, EPSILON, EPSILON, first);
+        result.add_arc(0, EPSILON, EPSILON, second);
+
+        result
+    }
+
+    /// The transducer that does what one transducer does and then another.
+    pub fn concat(&self, other: &Transducer) -> Transducer {
+        let mut result = self.clone();
+        let second = result.append(other);
+
+        for state in 0..second {
+            if result.finals[state] {
+                result.set_final(state, false);
+                result.add_arc(state, EPSILON, EPSILON, second);
+            }
+        }
+
+        result
+    }
+
+    /// The transducer that repeats a transducer zero or more times.
+    pub fn closure(&self) -> Transducer {
+        let mut result = Transducer::epsilon();
+        let start = result.append(self);
+
+        result.add_arc(0, EPSILON, EPSILON, start);
+
+        for state in start..result.num_states() {
+            if result.finals[state] {
+                result.add_arc(state, EPSILON, EPSILON, start);
+            }
+        }
+
+        result
+    }
+
+    /// Swaps the input and the output side.
+    pub fn invert(&self) -> Transducer {
+        let mut result = self.clone();
+
+        for arcs in result.arcs.iter_mut() {
+            for arc in arcs.iter_mut() {
+                std::mem::swap(&mut arc.input, &mut arc.output);
+            }
+        }
+
+        result.present = result.arcs.iter().enumerate().flat_map(|(state, arcs)| arcs.iter().map(move |&arc| (state, arc))).collect();
+        result.sides = Default::default();
+
+        result
+    }
+
+    /// The transducer that feeds the output of this transducer into another,
+    /// reading the input of this one and writing the output of the other.
+    pub fn compose(&self, other: &Transducer) -> Transducer {
+        let mut result = Transducer::new();
+        let mut states: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+
+        // Symbols of this transducer's output side in the other's table.
+        let shared: Vec<Option<usize>> = self.symbols.iter().map(|name| other.symbol_id(name)).collect();
+
+        states.insert((0, 0), 0);
+        queue.push_back((0, 0));
+
+        while let Some((first, second)) = queue.pop_front() {
+            let from = states[&(first, second)];
+            let mut moves: Vec<(&str, &str, usize, usize)> = Vec::new();
+
+            result.set_final(from, self.finals[first] && other.finals[second]);
+
+            for arc in &self.arcs[first] {
+                let input = self.symbol_name(arc.input);
+
+                if arc.output == EPSILON {
+                    moves.push((input, "", arc.target, second));
+                    continue;
+                }
+
+                let symbol = match shared[arc.output] {
+                    Some(symbol) => symbol,
+                    None => continue,
+                };
+
+                for next in other.arcs[second].iter().filter(|next| next.input == symbol) {
+                    moves.push((input, other.symbol_name(next.output), arc.target, next.target));
+                }
+            }
+
+            for next in other.arcs[second].iter().filter(|next| next.input == EPSILON) {
+                moves.push(("", other.symbol_name(next.output), first, next.target));
+            }
+
+            for (input, output, first, second) in moves {
+                let target = match states.get(&(first, second)) {
+                    Some(&target) => target,
+                    None => {
+                        let target = result.add_state();
+
+                        states.insert((first, second), target);
+                        queue.push_back((first, second));
+
+                        target
+                    }
+                };
+
+                let input = result.symbol(input);
+                let output = result.symbol(output);
+
+                result.add_arc(from, input, output, target);
+            }
+        }
+
+        result.trim()
+    }
+
+    /// Removes the states that are not on a path from the start state to a
+    /// final state.
+    pub fn trim(&self) -> Transducer {
+        let mut reachable = vec![false; self.num_states()];
+        let mut stack = vec![0];
+
+        reachable[0] = true;
+
+        while let Some(state) = stack.pop() {
+            for arc in &self.arcs[state] {
+                if !reachable[arc.target] {
+                    reachable[arc.target] = true;
+                    stack.push(arc.target);
+                }
+            }
+        }
+
+        let mut sources: Vec<Vec<usize>> = vec![Vec::new(); self.num_states()];
+
+        for (state, arcs) in self.arcs.iter().enumerate() {
+            for arc in arcs {
+                sources[arc.target].push(state);
+            }
+        }
+
+        let mut useful: Vec<bool> = self.finals.clone();
+        let mut stack: Vec<usize> = (0..self.num_states()).filter(|&state| self.finals[state]).collect();
+
+        while let Some(state) = stack.pop() {
+            for &source in &sources[state] {
+                if !useful[source] {
+                    useful[source] = true;
+                    stack.push(source);
+                }
+            }
+        }
+
+        let mut result = Transducer::new();
+        let mut numbers: Vec<Option<usize>> = vec![None; self.num_states()];
+
+        numbers[0] = Some(0);
+
+        for state in 1..self.num_states() {
+            if reachable[state] && useful[state] {
+                numbers[state] = Some(result.add_state());
+            }
+        }
+
+        for (state, arcs) in self.arcs.iter().enumerate() {
+            let from = match numbers[state] {
+                Some(from) => from,
+                None => continue,
+            };
+
+            result.set_final(from, self.finals[state]);
+
+            for arc in arcs {
+                if let Some(target) = numbers[arc.target] {
+                    let input = result.symbol(self.symbol_name(arc.input));
+                    let output = result.symbol(self.symbol_name(arc.output));
+
+                    result.add_arc(from, input, output, target);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Splits text into the symbols of one side, preferring the longest
+    /// symbol at every position. Returns `None` for text with characters
+    /// that are not symbols of that side.
+    pub fn tokenize(&self, text: &str, direction: Direction) -> Option<Vec<usize>> {
+        let symbols = self.sides[direction as usize].get_or_init(|| {
+            let mut symbols: Vec<usize> = match direction {
+                Direction::Down => self.input_symbols(),
+                Direction::Up => self.output_symbols(),
+            }
+            .into_iter()
+            .map(|name| self.index[name])
+            .collect();
+
+            symbols.sort_by_key(|&id| std::cmp::Reverse(self.symbols[id].len()));
+
+            symbols
+        });
+
+        let mut ids: Vec<usize> = Vec::new();
+        let mut rest = text;
+
+        while !rest.is_empty() {
+            let id = *symbols.iter().find(|&&id| rest.starts_with(self.symbols[id].as_str()))?;
+
+            ids.push(id);
+            rest = &rest[self.symbols[id].len()..];
+        }
+
+        Some(ids)
+    }
+
+    /// The symbol sequences the transducer maps text to.
+    pub fn apply(&self, text: &str, direction: Direction) -> Vec<Vec<String>> {
+        let symbols = match self.tokenize(text, direction) {
+            Some(symbols) => symbols,
+            None => return Vec::new(),
+        };
+
+        // Epsilon arcs may form cycles, which are followed at most as many
+        // times in a row as there are states.
+        let limit = self.num_states();
+
+        let mut results: Vec<Vec<String>> = Vec::new();
+        let mut seen: HashSet<(usize, usize, Vec<usize>)> = HashSet::new();
+        let mut stack: Vec<(usize, usize, Vec<usize>, usize)> = vec![(0, 0, Vec::new(), 0)];
+
+        while let Some((state, position, output, epsilons)) = stack.pop() {
+            if !seen.insert((state, position, output.clone())) {
+                continue;
+            }
+
+            if position == symbols.len() && self.finals[state] {
+                let result: Vec<String> = output.iter().map(|&id| String::from(self.symbol_name(id))).collect();
+
+                if !results.contains(&result) {
+                    results.push(result);
+                }
+            }
+
+            for arc in &self.arcs[state] {
+                let (read, write) = match direction {
+                    Direction::Down => (arc.input, arc.output),
+                    Direction::Up => (arc.output, arc.input),
+                };
+
+                let mut next = output.clone();
+
+                if write != EPSILON {
+                    next.push(write);
+                }
+
+                if read == EPSILON {
+                    if epsilons < limit {
+                        stack.push((arc.target, position, next, epsilons + 1));
+                    }
+                } else if symbols.get(position) == Some(&read) {
+                    stack.push((arc.target, position + 1, next, 0));
+                }
+            }
+        }
+
+        results
+    }
+
+    /// Maps input text to the output strings of the transducer.
+    pub fn lookup(&self, text: &str) -> Vec<String> {
+        self.apply(text, Direction::Down).into_iter().map(|symbols| symbols.concat()).collect()
+    }
+
+    /// Maps output text back to the input strings of the transducer.
+    pub fn lookup_up(&self, text: &str) -> Vec<String> {
+        self.apply(text, Direction::Up).into_iter().map(|symbols| symbols.concat()).collect()
+    }
+
+    /// The paths whose input starts with `prefix` and continues with symbols
+    /// for which `extend` holds, as pairs of input and output symbols. At
+    /// most `limit` symbols follow the prefix.
+    pub fn complete(&self, prefix: &str, extend: impl Fn(&str) -> bool, limit: usize) -> Vec<(Vec<String>, Vec<String>)> {
+        let symbols = match self.tokenize(prefix, Direction::Down) {
+            Some(symbols) => symbols,
+            None => return Vec::new(),
+        };
+
+        let epsilon_limit = self.num_states();
+
+        let mut results: Vec<(Vec<String>, Vec<String>)> = Vec::new();
+        let mut stack: Vec<(usize, Vec<usize>, Vec<usize>, usize)> = vec![(0, Vec::new(), Vec::new(), 0)];
+
+        while let Some((state, input, output, epsilons)) = stack.pop() {
+            if input.len() >= symbols.len() && self.finals[state] {
+                let result = (
+                    input.iter().map(|&id| String::from(self.symbol_name(id))).collect(),
+                    output.iter().map(|&id| String::from(self.symbol_name(id))).collect(),
+                );
+
+                if !results.contains(&result) {
+                    results.push(result);
+                }
+            }
+
+            for arc in &self.arcs[state] {
+                let mut next_output = output.clone();
+
+                if arc.output != EPSILON {
+                    next_output.push(arc.output);
+                }
+
+                if arc.input == EPSILON {
+                    if epsilons < epsilon_limit {
+                        stack.push((arc.target, input.clone(), next_output, epsilons + 1));
+                    }
+
+                    continue;
+                }
+
+                let allowed = match symbols.get(input.len()) {
+                    Some(&symbol) => symbol == arc.input,
+                    None => input.len() < symbols.len() + limit && extend(self.symbol_name(arc.input)),
+                };
+
+                if allowed {
+                    let mut next_input = input.clone();
+                    next_input.push(arc.input);
+
+                    stack.push((arc.target, next_input, next_output, 0));
+                }
+            }
+        }
+
+        results
+    }
+}
+
+impl Default for Transducer {
+    fn default() -> Transducer {
+        Transducer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_looks_up_in_both_directions() {
+        let transducer = Transducer::from_pair(&["g", "o", "+Past"], &["w", "e", "n", "t"]);
+
+        assert_eq!(transducer.lookup("go+Past"), vec!["went"]);
+        assert_eq!(transducer.lookup_up("went"), vec!["go+Past"]);
+        assert_eq!(transducer.invert().lookup("went"), vec!["go+Past"]);
+        assert!(transducer.lookup("go").is_empty());
+    }
+
+    #[test]
+    fn it_tokenizes_with_symbols_added_later() {
+        let mut transducer = Transducer::from_pair(&["a"], &["a"]);
+
+        assert_eq!(transducer.tokenize("ab", Direction::Down), None);
+
+        let b = transducer.symbol("b");
+        transducer.add_arc(1, b, b, 1);
+        transducer.add_arc(1, b, b, 1);
+
+        assert_eq!(transducer.tokenize("ab", Direction::Down), Some(vec![1, b]));
+        assert_eq!(transducer.arcs(1).len(), 1);
+        assert_eq!(transducer.lookup("abb"), vec!["abb"]);
+    }
+
+    #[test]
+    fn it_combines_transducers() {
+        let cat = Transducer::from_pair(&["c", "a", "t"], &["c", "a", "t"]);
+        let dog = Transducer::from_pair(&["d", "o", "g"], &["d", "o", "g"]);
+        let plural = Transducer::from_pair(&["+Pl"], &["s"]);
+
+        let nouns = cat.union(&dog).concat(&Transducer::epsilon().union(&plural));
+
+        assert_eq!(nouns.lookup("dog+Pl"), vec!["dogs"]);
+        assert_eq!(nouns.lookup_up("cat"), vec!["cat"]);
+
+        let repeated = plural.closure();
+
+        assert_eq!(repeated.lookup("+Pl+Pl"), vec!["ss"]);
+        assert_eq!(repeated.lookup(""), vec![""]);
+    }
+
+    #[test]
+    fn it_composes_transducers() {
+        let lexicon = Transducer::from_pair(&["c", "a", "t", "+Pl"], &["c", "a", "t", "^", "s"]);
+        let boundary = Transducer::from_pair(&["^"], &[]);
+        let cleanup = Transducer::identity(&["a", "c", "s", "t"]).union(&boundary).closure();
+
+        let composed = lexicon.compose(&cleanup);
+
+        assert_eq!(composed.lookup("cat+Pl"), vec!["cats"]);
+        assert_eq!(composed.lookup_up("cats"), vec!["cat+Pl"]);
+    }
+
+    #[test]
+    fn it_completes_prefixes() {
+        let sing = Transducer::from_pair(&["w", "a", "l", "k", "+Sg"], &["w", "a", "l", "k"]);
+        let plural = Transducer::from_pair(&["w", "a", "l", "k", "+Pl"], &["w", "a", "l", "k", "s"]);
+        let paths = sing.union(&plural).complete("walk", |symbol| symbol.starts_with('+'), 4);
+
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&(vec![String::from("w"), String::from("a"), String::from("l"), String::from("k"), String::from("+Pl")], vec![String::from("w"), String::from("a"), String::from("l"), String::from("k"), String::from("s")])));
+    }
+}
@@ -0,0 +1,468 @@
+// The affix file (`.aff`) of a Hunspell dictionary: how flags are written,
+// the prefix and suffix tables, compounding flags and the tables used for
+// suggestions. Directives Quenya does not use are skipped, as Hunspell skips
+// directives it does not know.
+
+use std::collections::{HashMap, HashSet};
+
+/// A flag on a dictionary word or affix, as a number whatever way the
+/// affix file writes flags.
+pub type Flag = u32;
+
+/// How flags are written, set with the `FLAG` directive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlagMode {
+    /// One character per flag, the default.
+    Char,
+    /// Two characters per flag (`FLAG long`).
+    Long,
+    /// Comma-separated numbers (`FLAG num`).
+    Numeric,
+    /// One Unicode character per flag (`FLAG UTF-8`).
+    Utf8,
+}
+
+impl FlagMode {
+    pub fn parse(&self, flags: &str) -> Result<Vec<Flag>, String> {
+        match self {
+            FlagMode::Char | FlagMode::Utf8 => Ok(flags.chars().map(|c| c as Flag).collect()),
+            FlagMode::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+
+                if !chars.len().is_multiple_of(2) {
+                    return Err(format!("long flags '{}' have an odd number of characters", flags));
+                }
+
+                Ok(chars.chunks(2).map(|pair| ((pair[0] as Flag) << 16) | pair[1] as Flag).collect())
+            }
+            FlagMode::Numeric => flags
+                .split(',')
+                .filter(|flag| !flag.is_empty())
+                .map(|flag| flag.trim().parse::<Flag>().map_err(|_| format!("'{}' is not a numeric flag", flag)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum CharClass {
+    Any,
+    Char(char),
+    Set { chars: Vec<char>, negated: bool },
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Any => true,
+            CharClass::Char(expected) => *expected == c,
+            CharClass::Set { chars, negated } => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+/// The condition of an affix on the word it attaches to, a sequence of
+/// characters, `.` and `[...]` or `[^...]` sets.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    classes: Vec<CharClass>,
+}
+
+impl Condition {
+    pub fn parse(condition: &str) -> Result<Condition, String> {
+        let mut classes: Vec<CharClass> = Vec::new();
+        let mut chars = condition.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => classes.push(CharClass::Any),
+                '[' => {
+                    let mut set: Vec<char> = Vec::new();
+                    let mut negated = false;
+                    let mut closed = false;
+
+                    for c in chars.by_ref() {
+                        match c {
+                            ']' => {
+                                closed = true;
+                                break;
+                            }
+                            '^' if set.is_empty() && !negated => negated = true,
+                            _ => set.push(c),
+                        }
+                    }
+
+                    if !closed {
+                        return Err(format!("condition '{}' has an unclosed '['", condition));
+                    }
+
+                    classes.push(CharClass::Set { chars: set, negated });
+                }
+                _ => classes.push(CharClass::Char(c)),
+            }
+        }
+
+        // A lone `.` is how affix files say there is no condition.
+        if classes == [CharClass::Any] {
+            classes.clear();
+        }
+
+        Ok(Condition { classes })
+    }
+
+    pub fn matches_start(&self, word: &str) -> bool {
+        let mut chars = word.chars();
+
+        self.classes.iter().all(|class| chars.next().is_some_and(|c| class.matches(c)))
+    }
+
+    pub fn matches_end(&self, word: &str) -> bool {
+        let mut chars = word.chars().rev();
+
+        self.classes.iter().rev().all(|class| chars.next().is_some_and(|c| class.matches(c)))
+    }
+}
+
+/// One entry of a prefix or suffix table: `strip` is removed from the root
+/// and `add` put in its place.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Affix {
+    pub flag: Flag,
+    pub prefix: bool,
+    /// Whether the affix combines with affixes from the other side.
+    pub cross_product: bool,
+    pub strip: String,
+    pub add: String,
+    /// Flags of the affixed word, such as those of further suffixes.
+    pub continuation: Vec<Flag>,
+    pub condition: Condition,
+    /// Morphological fields such as `is:plural`.
+    pub morph: Vec<String>,
+}
+
+impl Affix {
+    /// The root a word would have if it carried this affix.
+    pub fn root_of(&self, word: &str) -> Option<String> {
+        let root = if self.prefix {
+            let rest = word.strip_prefix(self.add.as_str())?;
+
+            if rest.is_empty() {
+                return None;
+            }
+
+            [self.strip.as_str(), rest].concat()
+        } else {
+            let rest = word.strip_suffix(self.add.as_str())?;
+
+            if rest.is_empty() {
+                return None;
+            }
+
+            [rest, self.strip.as_str()].concat()
+        };
+
+        let matches = if self.prefix { self.condition.matches_start(&root) } else { self.condition.matches_end(&root) };
+
+        if matches {
+            Some(root)
+        } else {
+            None
+        }
+    }
+
+    /// The word formed by adding this affix to a root.
+    pub fn apply(&self, root: &str) -> Option<String> {
+        if self.prefix {
+            let rest = root.strip_prefix(self.strip.as_str())?;
+
+            if !self.condition.matches_start(root) {
+                return None;
+            }
+
+            Some([self.add.as_str(), rest].concat())
+        } else {
+            let rest = root.strip_suffix(self.strip.as_str())?;
+
+            if !self.condition.matches_end(root) {
+                return None;
+            }
+
+            Some([rest, self.add.as_str()].concat())
+        }
+    }
+}
+
+/// The parsed directives of an affix file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AffixFile {
+    pub encoding: String,
+    pub flag_mode: FlagMode,
+    pub prefixes: Vec<Affix>,
+    pub suffixes: Vec<Affix>,
+    /// Characters tried when suggesting, most frequent first.
+    pub try_chars: String,
+    /// Keyboard rows, whose neighbouring keys are tried when suggesting.
+    pub keys: Vec<String>,
+    /// Common misspellings and their corrections.
+    pub replacements: Vec<(String, String)>,
+    /// Groups of related characters, such as `aáà`.
+    pub maps: Vec<Vec<String>>,
+    /// Characters removed from words and dictionary entries.
+    pub ignore: Vec<char>,
+    pub flag_aliases: Vec<Vec<Flag>>,
+    pub morph_aliases: Vec<Vec<String>>,
+    pub compound_flag: Option<Flag>,
+    pub compound_begin: Option<Flag>,
+    pub compound_middle: Option<Flag>,
+    pub compound_end: Option<Flag>,
+    /// The shortest part of a compound, in characters.
+    pub compound_min: usize,
+    pub compound_word_max: Option<usize>,
+    pub only_in_compound: Option<Flag>,
+    pub need_affix: Option<Flag>,
+    pub forbidden_word: Option<Flag>,
+    pub no_suggest: Option<Flag>,
+    pub keep_case: Option<Flag>,
+    pub circumfix: Option<Flag>,
+}
+
+impl Default for AffixFile {
+    fn default() -> AffixFile {
+        AffixFile {
+            encoding: String::from("ISO8859-1"),
+            flag_mode: FlagMode::Char,
+            prefixes: Vec::new(),
+            suffixes: Vec::new(),
+            try_chars: String::new(),
+            keys: Vec::new(),
+            replacements: Vec::new(),
+            maps: Vec::new(),
+            ignore: Vec::new(),
+            flag_aliases: Vec::new(),
+            morph_aliases: Vec::new(),
+            compound_flag: None,
+            compound_begin: None,
+            compound_middle: None,
+            compound_end: None,
+            compound_min: 3,
+            compound_word_max: None,
+            only_in_compound: None,
+            need_affix: None,
+            forbidden_word: None,
+            no_suggest: None,
+            keep_case: None,
+            circumfix: None,
+        }
+    }
+}
+
+impl AffixFile {
+    /// Parses an affix file. Errors carry the line number they were found on.
+    pub fn parse(source: &str) -> Result<AffixFile, (usize, String)> {
+        let mut affixes = AffixFile::default();
+        let mut cross_products: HashMap<(bool, Flag), bool> = HashMap::new();
+        let mut tables: HashSet<&str> = HashSet::new();
+
+        for (number, line) in source.lines().enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            if fields.is_empty() || fields[0].starts_with('#') {
+                continue;
+            }
+
+            // The first line of a table holds the number of lines after it.
+            if matches!(fields[0], "REP" | "MAP" | "AF" | "AM") && tables.insert(fields[0]) {
+                continue;
+            }
+
+            affixes.parse_directive(&fields, &mut cross_products).map_err(|message| (number + 1, message))?;
+        }
+
+        Ok(affixes)
+    }
+
+    /// The flags of a dictionary word or affix, which are an index into the
+    /// `AF` aliases if the file has them.
+    pub fn parse_flags(&self, flags: &str) -> Result<Vec<Flag>, String> {
+        if self.flag_aliases.is_empty() {
+            return self.flag_mode.parse(flags);
+        }
+
+        match flags.parse::<usize>() {
+            Ok(index) if index >= 1 && index <= self.flag_aliases.len() => Ok(self.flag_aliases[index - 1].clone()),
+            _ => Err(format!("'{}' is not a flag alias", flags)),
+        }
+    }
+
+    /// The morphological fields of a dictionary word or affix, which may be
+    /// an index into the `AM` aliases.
+    pub fn parse_morph(&self, fields: &[&str]) -> Vec<String> {
+        if let [alias] = fields {
+            if let Ok(index) = alias.parse::<usize>() {
+                if index >= 1 && index <= self.morph_aliases.len() {
+                    return self.morph_aliases[index - 1].clone();
+                }
+            }
+        }
+
+        fields.iter().map(|field| String::from(*field)).collect()
+    }
+
+    fn flag(&self, fields: &[&str]) -> Result<Option<Flag>, String> {
+        let value = fields.get(1).ok_or_else(|| format!("{} needs a flag", fields[0]))?;
+
+        Ok(self.flag_mode.parse(value)?.first().copied())
+    }
+
+    fn parse_directive(&mut self, fields: &[&str], cross_products: &mut HashMap<(bool, Flag), bool>) -> Result<(), String> {
+        let argument = fields.get(1).copied().unwrap_or("");
+
+        match fields[0] {
+            "SET" => self.encoding = String::from(argument),
+            "FLAG" => {
+                self.flag_mode = match argument {
+                    "long" => FlagMode::Long,
+                    "num" => FlagMode::Numeric,
+                    "UTF-8" => FlagMode::Utf8,
+                    _ => return Err(format!("unknown flag type '{}'", argument)),
+                }
+            }
+            "TRY" => self.try_chars = String::from(argument),
+            "KEY" => self.keys = argument.split('|').map(String::from).collect(),
+            "IGNORE" => self.ignore = argument.chars().collect(),
+            "REP" if fields.len() >= 3 => self.replacements.push((argument.replace('_', " "), fields[2].replace('_', " "))),
+            "MAP" => self.maps.push(parse_map(argument)),
+            "AF" => {
+                let flags = self.flag_mode.parse(argument)?;
+
+                self.flag_aliases.push(flags);
+            }
+            "AM" => self.morph_aliases.push(fields[1..].iter().map(|field| String::from(*field)).collect()),
+            "COMPOUNDFLAG" => self.compound_flag = self.flag(fields)?,
+            "COMPOUNDBEGIN" => self.compound_begin = self.flag(fields)?,
+            "COMPOUNDMIDDLE" => self.compound_middle = self.flag(fields)?,
+            "COMPOUNDEND" | "COMPOUNDLAST" => self.compound_end = self.flag(fields)?,
+            "ONLYINCOMPOUND" => self.only_in_compound = self.flag(fields)?,
+            "NEEDAFFIX" | "PSEUDOROOT" => self.need_affix = self.flag(fields)?,
+            "FORBIDDENWORD" => self.forbidden_word = self.flag(fields)?,
+            "NOSUGGEST" => self.no_suggest = self.flag(fields)?,
+            "KEEPCASE" => self.keep_case = self.flag(fields)?,
+            "CIRCUMFIX" => self.circumfix = self.flag(fields)?,
+            "COMPOUNDMIN" => self.compound_min = argument.parse().map_err(|_| format!("'{}' is not a number", argument))?,
+            "COMPOUNDWORDMAX" => self.compound_word_max = Some(argument.parse().map_err(|_| format!("'{}' is not a number", argument))?),
+            "PFX" | "SFX" => self.parse_affix(fields, cross_products)?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn parse_affix(&mut self, fields: &[&str], cross_products: &mut HashMap<(bool, Flag), bool>) -> Result<(), String> {
+        let prefix = fields[0] == "PFX";
+
+        if fields.len() < 4 {
+            return Err(format!("{} needs a flag, strip, affix and condition", fields[0]));
+        }
+
+        let flag = self
+            .flag_mode
+            .parse(fields[1])?
+            .first()
+            .copied()
+            .ok_or_else(|| format!("{} needs a flag", fields[0]))?;
+
+        // The header of a table: `SFX A Y 2`.
+        if fields.len() == 4 && matches!(fields[2], "Y" | "N") && fields[3].parse::<usize>().is_ok() {
+            cross_products.insert((prefix, flag), fields[2] == "Y");
+
+            return Ok(());
+        }
+
+        let (add, continuation) = match fields[3].split_once('/') {
+            Some((add, flags)) => (add, self.parse_flags(flags)?),
+            None => (fields[3], Vec::new()),
+        };
+
+        let affix = Affix {
+            flag,
+            prefix,
+            cross_product: cross_products.get(&(prefix, flag)).copied().unwrap_or(false),
+            strip: zero_to_empty(fields[2]),
+            add: zero_to_empty(add),
+            continuation,
+            condition: Condition::parse(fields.get(4).copied().unwrap_or("."))?,
+            morph: self.parse_morph(fields.get(5..).unwrap_or(&[])),
+        };
+
+        if prefix {
+            self.prefixes.push(affix);
+        } else {
+            self.suffixes.push(affix);
+        }
+
+        Ok(())
+    }
+}
+
+fn zero_to_empty(text: &str) -> String {
+    match text {
+        "0" => String::new(),
+        _ => String::from(text),
+    }
+}
+
+/// Splits a `MAP` group into characters, or strings written in parentheses.
+fn parse_map(group: &str) -> Vec<String> {
+    let mut members: Vec<String> = Vec::new();
+    let mut chars = group.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '(' {
+            members.push(chars.by_ref().take_while(|&c| c != ')').collect());
+        } else {
+            members.push(c.to_string());
+        }
+    }
+
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_flags() {
+        assert_eq!(FlagMode::Char.parse("AB").unwrap(), vec!['A' as Flag, 'B' as Flag]);
+        assert_eq!(FlagMode::Long.parse("AaBb").unwrap().len(), 2);
+        assert!(FlagMode::Long.parse("AaB").is_err());
+        assert_eq!(FlagMode::Numeric.parse("101,7").unwrap(), vec![101, 7]);
+    }
+
+    #[test]
+    fn it_matches_conditions() {
+        let condition = Condition::parse("[^aeiou]y").unwrap();
+
+        assert!(condition.matches_end("carry"));
+        assert!(!condition.matches_end("play"));
+        assert!(Condition::parse(".").unwrap().matches_end(""));
+        assert!(Condition::parse("[ab").is_err());
+    }
+
+    #[test]
+    fn it_parses_affix_tables() {
+        let affixes = AffixFile::parse("SET UTF-8\nTRY esiarn\n\nSFX D Y 2\nSFX D y ied [^aeiou]y is:past\nSFX D 0 ed [^y]\nPFX U N 1\nPFX U 0 un .\nCOMPOUNDMIN 2").unwrap();
+
+        assert_eq!(affixes.encoding, "UTF-8");
+        assert_eq!(affixes.suffixes.len(), 2);
+        assert!(affixes.suffixes[0].cross_product);
+        assert_eq!(affixes.suffixes[0].morph, vec!["is:past"]);
+        assert_eq!(affixes.suffixes[0].root_of("carried"), Some(String::from("carry")));
+        assert_eq!(affixes.suffixes[1].root_of("walked"), Some(String::from("walk")));
+        assert_eq!(affixes.suffixes[0].apply("carry"), Some(String::from("carried")));
+        assert_eq!(affixes.prefixes[0].root_of("undo"), Some(String::from("do")));
+        assert!(!affixes.prefixes[0].cross_product);
+        assert_eq!(affixes.compound_min, 2);
+        assert_eq!(AffixFile::parse("FLAG wide").unwrap_err().0, 1);
+    }
+}
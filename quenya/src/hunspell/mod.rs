@@ -0,0 +1,702 @@
+// Hunspell dictionaries: an affix file (`.aff`) and a word list (`.dic`)
+// whose entries carry flags naming the affixes they take, as in
+// `walk/DGS po:verb`. Words are checked by stripping affixes until a root in
+// the word list is found that takes them, or by splitting them into
+// compound parts. The morphological fields of the roots and affixes describe
+// the word, and are read into `Analysis` values.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use wn::pos::Part;
+use crate::features::Features;
+use crate::language::Analysis;
+use crate::text::part_for_upos;
+
+pub mod affix;
+mod suggest;
+
+pub use self::affix::{Affix, AffixFile, Flag, FlagMode};
+
+/// The most parts a compound is split into when the affix file does not
+/// say.
+const MAX_COMPOUND_PARTS: usize = 4;
+
+#[derive(Debug)]
+pub enum HunspellError {
+    Io { path: PathBuf, error: io::Error },
+    Malformed { file: &'static str, line: usize, message: String },
+    Encoding(String),
+}
+
+impl fmt::Display for HunspellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HunspellError::Io { path, error } => write!(f, "could not read {}: {}", path.display(), error),
+            HunspellError::Malformed { file, line, message } => write!(f, "{} line {}: {}", file, line, message),
+            HunspellError::Encoding(encoding) => write!(f, "unsupported encoding '{}'", encoding),
+        }
+    }
+}
+
+impl std::error::Error for HunspellError {}
+
+/// A root in the word list, with its flags and morphological fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub flags: Vec<Flag>,
+    pub morph: Vec<String>,
+}
+
+impl Entry {
+    fn has(&self, flag: Option<Flag>) -> bool {
+        flag.is_some_and(|flag| self.flags.contains(&flag))
+    }
+}
+
+/// One part of a word: the root it was found under and the affixes that
+/// were stripped to get there, innermost suffix first.
+#[derive(Clone, Debug)]
+struct Piece<'a> {
+    surface: String,
+    root: String,
+    entry: &'a Entry,
+    prefix: Option<&'a Affix>,
+    suffixes: Vec<&'a Affix>,
+}
+
+impl Piece<'_> {
+    /// The morphological fields of the piece, starting with its stem.
+    fn fields(&self) -> Vec<String> {
+        let mut fields: Vec<String> = Vec::new();
+
+        if !self.entry.morph.iter().any(|field| field.starts_with("st:")) {
+            fields.push(format!("st:{}", self.root));
+        }
+
+        fields.extend(self.entry.morph.iter().cloned());
+        fields.extend(self.prefix.iter().flat_map(|prefix| prefix.morph.iter().cloned()));
+        fields.extend(self.suffixes.iter().flat_map(|suffix| suffix.morph.iter().cloned()));
+
+        fields
+    }
+
+    fn stem(&self) -> String {
+        match self.entry.morph.iter().find_map(|field| field.strip_prefix("st:")) {
+            Some(stem) => String::from(stem),
+            None => self.root.clone(),
+        }
+    }
+
+    fn has(&self, flag: Option<Flag>) -> bool {
+        self.entry.has(flag)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Position {
+    Alone,
+    Begin,
+    Middle,
+    End,
+}
+
+/// A Hunspell dictionary.
+#[derive(Clone, Debug)]
+pub struct Dictionary {
+    affixes: AffixFile,
+    words: HashMap<String, Vec<Entry>>,
+}
+
+impl Dictionary {
+    /// Reads `<path>.aff` and `<path>.dic`, such as `nl.aff` and `nl.dic` for
+    /// `nl`.
+    pub fn open(path: &Path) -> Result<Dictionary, HunspellError> {
+        let read = |extension: &str| {
+            let path = path.with_extension(extension);
+
+            fs::read(&path).map_err(|error| HunspellError::Io { path, error })
+        };
+
+        Dictionary::from_bytes(&read("aff")?, &read("dic")?)
+    }
+
+    /// Reads the two files in the encoding the affix file names with `SET`.
+    /// UTF-8 and ISO 8859-1 and -15 are supported.
+    pub fn from_bytes(aff: &[u8], dic: &[u8]) -> Result<Dictionary, HunspellError> {
+        let encoding = aff
+            .split(|&byte| byte == b'\n')
+            .find_map(|line| line.strip_prefix(b"SET"))
+            .map(|rest| String::from_utf8_lossy(rest).trim().to_uppercase())
+            .unwrap_or_else(|| String::from("ISO8859-1"));
+
+        let decode = |bytes: &[u8]| match encoding.as_str() {
+            "UTF-8" | "UTF8" => Ok(String::from_utf8_lossy(bytes).into_owned()),
+            "ISO8859-1" | "ISO-8859-1" => Ok(bytes.iter().map(|&byte| byte as char).collect()),
+            "ISO8859-15" | "ISO-8859-15" => Ok(bytes.iter().map(|&byte| latin9_char(byte)).collect()),
+            _ => Err(HunspellError::Encoding(encoding.clone())),
+        };
+
+        Dictionary::from_strings(&decode(aff)?, &decode(dic)?)
+    }
+
+    pub fn from_strings(aff: &str, dic: &str) -> Result<Dictionary, HunspellError> {
+        let affixes = AffixFile::parse(aff).map_err(|(line, message)| HunspellError::Malformed {
+            file: "aff",
+            line,
+            message,
+        })?;
+
+        let mut dictionary = Dictionary {
+            affixes,
+            words: HashMap::new(),
+        };
+
+        let mut lines = dic.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+
+        // The first line is the number of entries, which is only a hint.
+        if let Some((number, line)) = lines.next() {
+            if line.trim().parse::<usize>().is_err() {
+                return Err(HunspellError::Malformed {
+                    file: "dic",
+                    line: number + 1,
+                    message: String::from("expected the number of entries"),
+                });
+            }
+        }
+
+        for (number, line) in lines {
+            dictionary.add_line(line).map_err(|message| HunspellError::Malformed {
+                file: "dic",
+                line: number + 1,
+                message,
+            })?;
+        }
+
+        Ok(dictionary)
+    }
+
+    fn add_line(&mut self, line: &str) -> Result<(), String> {
+        // Words end at the first unescaped `/`, or else at whitespace.
+        let mut word = String::new();
+        let mut flags = "";
+        let mut rest = "";
+        let mut chars = line.char_indices();
+
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '\\' if line[index + 1..].starts_with('/') => {
+                    word.push('/');
+                    chars.next();
+                }
+                '/' => {
+                    let after = &line[index + 1..];
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+
+                    flags = &after[..end];
+                    rest = &after[end..];
+                    break;
+                }
+                '\t' | ' ' => {
+                    rest = &line[index..];
+                    break;
+                }
+                _ => word.push(c),
+            }
+        }
+
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+
+        let entry = Entry {
+            flags: if flags.is_empty() { Vec::new() } else { self.affixes.parse_flags(flags)? },
+            morph: self.affixes.parse_morph(&fields),
+        };
+
+        let word = self.without_ignored(&word);
+
+        self.words.entry(word).or_default().push(entry);
+
+        Ok(())
+    }
+
+    pub fn affixes(&self) -> &AffixFile {
+        &self.affixes
+    }
+
+    /// The number of roots in the word list.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn entries(&self, root: &str) -> &[Entry] {
+        self.words.get(root).map_or(&[], Vec::as_slice)
+    }
+
+    fn without_ignored(&self, word: &str) -> String {
+        match self.affixes.ignore.is_empty() {
+            true => String::from(word),
+            false => word.chars().filter(|c| !self.affixes.ignore.contains(c)).collect(),
+        }
+    }
+
+    /// Whether a word is spelled correctly. Numbers are always correct.
+    pub fn check(&self, word: &str) -> bool {
+        let word = self.without_ignored(word.trim());
+
+        if word.is_empty() || word.chars().all(|c| c.is_ascii_digit() || ".,-".contains(c)) {
+            return true;
+        }
+
+        if self.entries(&word).iter().any(|entry| entry.has(self.affixes.forbidden_word)) {
+            return false;
+        }
+
+        !self.readings(&word).is_empty()
+    }
+
+    /// Corrections for a misspelled word, best first.
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        suggest::suggest(self, word)
+    }
+
+    /// The distinct stems of a word.
+    pub fn stems(&self, word: &str) -> Vec<String> {
+        let mut stems: Vec<String> = Vec::new();
+
+        for reading in self.readings(word) {
+            let stem = compound_stem(&reading);
+
+            if !stems.contains(&stem) {
+                stems.push(stem);
+            }
+        }
+
+        stems
+    }
+
+    /// The morphological descriptions of a word, such as
+    /// `st:walk po:verb is:past`, with the fields of compound parts in turn.
+    pub fn describe(&self, word: &str) -> Vec<String> {
+        let mut descriptions: Vec<String> = Vec::new();
+
+        for reading in self.readings(word) {
+            let description = reading.iter().flat_map(Piece::fields).collect::<Vec<String>>().join(" ");
+
+            if !descriptions.contains(&description) {
+                descriptions.push(description);
+            }
+        }
+
+        descriptions
+    }
+
+    /// Reads the descriptions of a word as analyses. The lemma is the stem,
+    /// `po:` gives the part of speech, fields with values such as
+    /// `is:Number=Plur` give features, and the whole description is kept as
+    /// the tag.
+    pub fn analyze(&self, word: &str) -> Vec<Analysis> {
+        let mut analyses: Vec<Analysis> = Vec::new();
+
+        for reading in self.readings(word) {
+            let fields: Vec<String> = reading.iter().flat_map(Piece::fields).collect();
+            let last = reading.last().map(Piece::fields).unwrap_or_default();
+
+            let pos = last.iter().find_map(|field| field.strip_prefix("po:")).and_then(part_for_po);
+            let mut features = Features::new();
+
+            for field in &last {
+                if let Some((name, value)) = field.get(3..).and_then(|value| value.split_once('=')) {
+                    features.insert(name, value);
+                }
+            }
+
+            let mut analysis = Analysis::new(&compound_stem(&reading), pos, features);
+            analysis.tag = Some(fields.join(" "));
+
+            if !analyses.contains(&analysis) {
+                analyses.push(analysis);
+            }
+        }
+
+        analyses
+    }
+
+    /// The ways a word can be read, as it is written or with its case
+    /// changed the way Hunspell does for capitalised and upper-case words.
+    fn readings(&self, word: &str) -> Vec<Vec<Piece<'_>>> {
+        let word = self.without_ignored(word);
+        let mut readings = self.readings_as_written(&word);
+
+        for variant in case_variants(&word) {
+            let keep_case = self.affixes.keep_case;
+
+            readings.extend(self.readings_as_written(&variant).into_iter().filter(|reading| !reading.iter().any(|piece| piece.has(keep_case))));
+        }
+
+        readings
+    }
+
+    fn readings_as_written(&self, word: &str) -> Vec<Vec<Piece<'_>>> {
+        let mut readings: Vec<Vec<Piece>> = self.pieces(word, Position::Alone).into_iter().map(|piece| vec![piece]).collect();
+
+        if readings.is_empty() && self.compounds() {
+            readings = self.compound_parts(word, Position::Begin, 1);
+        }
+
+        readings
+    }
+
+    fn compounds(&self) -> bool {
+        let affixes = &self.affixes;
+
+        affixes.compound_flag.is_some() || affixes.compound_begin.is_some()
+    }
+
+    /// Splits a word into parts that may start, continue and end a compound.
+    fn compound_parts(&self, word: &str, position: Position, count: usize) -> Vec<Vec<Piece<'_>>> {
+        let mut readings: Vec<Vec<Piece>> = Vec::new();
+        let minimum = self.affixes.compound_min.max(1);
+        let maximum = self.affixes.compound_word_max.unwrap_or(MAX_COMPOUND_PARTS);
+
+        if position != Position::Begin {
+            readings.extend(self.pieces(word, Position::End).into_iter().map(|piece| vec![piece]));
+        }
+
+        if count >= maximum {
+            return readings;
+        }
+
+        for (split, _) in word.char_indices().skip(minimum) {
+            let (head, tail) = word.split_at(split);
+
+            if tail.chars().count() < minimum {
+                break;
+            }
+
+            for piece in self.pieces(head, position) {
+                for rest in self.compound_parts(tail, Position::Middle, count + 1) {
+                    let mut reading = vec![piece.clone()];
+                    reading.extend(rest);
+
+                    readings.push(reading);
+                }
+            }
+        }
+
+        readings
+    }
+
+    /// The roots and affixes a word, or a part of a compound at a position,
+    /// can be read as.
+    fn pieces(&self, word: &str, position: Position) -> Vec<Piece<'_>> {
+        let affixes = &self.affixes;
+        let mut pieces: Vec<Piece> = Vec::new();
+
+        let allowed = |entry: &Entry| {
+            let compound = match position {
+                Position::Alone => return !entry.has(affixes.only_in_compound) && !entry.has(affixes.forbidden_word),
+                Position::Begin => entry.has(affixes.compound_begin),
+                Position::Middle => entry.has(affixes.compound_middle),
+                Position::End => entry.has(affixes.compound_end),
+            };
+
+            (compound || entry.has(affixes.compound_flag)) && !entry.has(affixes.forbidden_word)
+        };
+
+        let circumfix = |affix: &Affix| affixes.circumfix.is_some_and(|flag| affix.continuation.contains(&flag));
+
+        let mut push = |root: &str, entry, prefix, suffixes| {
+            pieces.push(Piece {
+                surface: String::from(word),
+                root: String::from(root),
+                entry,
+                prefix,
+                suffixes,
+            });
+        };
+
+        for entry in self.entries(word) {
+            if allowed(entry) && !entry.has(affixes.need_affix) {
+                push(word, entry, None, Vec::new());
+            }
+        }
+
+        // Parts before the end of a compound take no suffixes, and parts
+        // after its beginning no prefixes.
+        let suffixes = matches!(position, Position::Alone | Position::End);
+        let prefixes = matches!(position, Position::Alone | Position::Begin);
+
+        for suffix in affixes.suffixes.iter().filter(|_| suffixes) {
+            let root = match suffix.root_of(word) {
+                Some(root) => root,
+                None => continue,
+            };
+
+            for entry in self.entries(&root) {
+                if allowed(entry) && entry.flags.contains(&suffix.flag) && !circumfix(suffix) {
+                    push(&root, entry, None, vec![suffix]);
+                }
+            }
+
+            // A suffix after another, which lists it among its flags.
+            for inner in affixes.suffixes.iter().filter(|inner| inner.continuation.contains(&suffix.flag)) {
+                if let Some(inner_root) = inner.root_of(&root) {
+                    for entry in self.entries(&inner_root) {
+                        if allowed(entry) && entry.flags.contains(&inner.flag) {
+                            push(&inner_root, entry, None, vec![inner, suffix]);
+                        }
+                    }
+                }
+            }
+
+            if !prefixes || !suffix.cross_product {
+                continue;
+            }
+
+            for prefix in affixes.prefixes.iter().filter(|prefix| prefix.cross_product) {
+                let prefix_root = match prefix.root_of(&root) {
+                    Some(prefix_root) => prefix_root,
+                    None => continue,
+                };
+
+                for entry in self.entries(&prefix_root) {
+                    let takes_prefix = entry.flags.contains(&prefix.flag) || suffix.continuation.contains(&prefix.flag);
+
+                    if allowed(entry) && entry.flags.contains(&suffix.flag) && takes_prefix && circumfix(prefix) == circumfix(suffix) {
+                        push(&prefix_root, entry, Some(prefix), vec![suffix]);
+                    }
+                }
+            }
+        }
+
+        for prefix in affixes.prefixes.iter().filter(|_| prefixes) {
+            if let Some(root) = prefix.root_of(word) {
+                for entry in self.entries(&root) {
+                    if allowed(entry) && entry.flags.contains(&prefix.flag) && !circumfix(prefix) {
+                        push(&root, entry, Some(prefix), Vec::new());
+                    }
+                }
+            }
+        }
+
+        pieces
+    }
+
+    /// Whether a word is correct and may be suggested.
+    pub(crate) fn suggestable(&self, word: &str) -> bool {
+        if word.split(' ').count() > 1 {
+            return word.split(' ').all(|part| !part.is_empty() && self.suggestable(part));
+        }
+
+        let no_suggest = self.affixes.no_suggest;
+
+        self.check(word) && self.readings(word).iter().any(|reading| !reading.iter().any(|piece| piece.has(no_suggest)))
+    }
+
+    /// The roots that may be suggested.
+    pub(crate) fn suggestable_roots(&self) -> impl Iterator<Item = &str> {
+        let affixes = &self.affixes;
+
+        self.words
+            .iter()
+            .filter(move |(_, entries)| {
+                entries.iter().any(|entry| {
+                    !entry.has(affixes.no_suggest) && !entry.has(affixes.forbidden_word) && !entry.has(affixes.only_in_compound) && !entry.has(affixes.need_affix)
+                })
+            })
+            .map(|(word, _)| word.as_str())
+    }
+}
+
+/// The stem of a word: the stem of its last part after the other parts of a
+/// compound as they are written.
+fn compound_stem(reading: &[Piece]) -> String {
+    match reading.split_last() {
+        Some((last, parts)) => parts.iter().map(|piece| piece.surface.as_str()).chain(Some(last.stem().as_str())).collect(),
+        None => String::new(),
+    }
+}
+
+fn part_for_po(po: &str) -> Option<Part> {
+    match po.to_lowercase().as_str() {
+        "noun" | "n" | "nn" => Some(Part::Noun),
+        "verb" | "v" | "vb" => Some(Part::Verb),
+        "adj" | "adjective" | "a" | "jj" => Some(Part::Adjective),
+        "adv" | "adverb" | "rb" => Some(Part::Adverb),
+        _ => part_for_upos(&po.to_uppercase()),
+    }
+}
+
+/// Other ways of writing a capitalised or upper-case word that the word
+/// list may have: `Walk` as `walk`, and `WALK` as `Walk` and `walk`.
+pub(crate) fn case_variants(word: &str) -> Vec<String> {
+    let lower = word.to_lowercase();
+
+    if lower == word {
+        return Vec::new();
+    }
+
+    let mut chars = word.chars();
+    let first = chars.next().is_some_and(char::is_uppercase);
+    let rest: String = chars.collect();
+
+    if rest.chars().count() > 0 && !rest.chars().any(char::is_lowercase) {
+        vec![capitalize(&lower), lower]
+    } else if first && !rest.chars().any(char::is_uppercase) {
+        vec![lower]
+    } else {
+        Vec::new()
+    }
+}
+
+pub(crate) fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// A byte of ISO 8859-15, which differs from ISO 8859-1 in eight places.
+fn latin9_char(byte: u8) -> char {
+    match byte {
+        0xA4 => '€',
+        0xA6 => 'Š',
+        0xA8 => 'š',
+        0xB4 => 'Ž',
+        0xB8 => 'ž',
+        0xBC => 'Œ',
+        0xBD => 'œ',
+        0xBE => 'Ÿ',
+        _ => byte as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) const AFF: &str = "SET UTF-8
+TRY esianrtolcdugmphbyfvkwzESIANRTOLCDUGMPHBYFVKWZ'
+KEY qwertyuiop|asdfghjkl|zxcvbnm
+REP 1
+REP f ph
+COMPOUNDFLAG X
+COMPOUNDMIN 3
+ONLYINCOMPOUND O
+FORBIDDENWORD F
+NOSUGGEST N
+KEEPCASE K
+
+PFX U Y 1
+PFX U 0 un . ip:un
+
+SFX D Y 3
+SFX D y ied [^aeiou]y is:Tense=Past
+SFX D 0 ed [^ey] is:Tense=Past
+SFX D 0 d e is:Tense=Past
+
+SFX S Y 2
+SFX S y ies [^aeiou]y is:Number=Plur
+SFX S 0 s [^sy] is:Number=Plur
+";
+
+    pub(crate) const DIC: &str = "10
+walk/DS po:verb
+carry/DSU po:verb
+do/U po:verb
+love/DSU po:verb
+book/SX po:noun
+case/SX po:noun
+s/O
+foo/N
+Paris/K po:propn
+irregardless/F
+";
+
+    fn dictionary() -> Dictionary {
+        Dictionary::from_strings(AFF, DIC).unwrap()
+    }
+
+    #[test]
+    fn it_checks_affixed_words() {
+        let dictionary = dictionary();
+
+        assert!(dictionary.check("walked"));
+        assert!(dictionary.check("carried"));
+        assert!(dictionary.check("carries"));
+        assert!(dictionary.check("loved"));
+        assert!(dictionary.check("unloved"));
+        assert!(dictionary.check("undo"));
+        assert!(!dictionary.check("carryed"));
+        assert!(!dictionary.check("walkied"));
+        assert!(!dictionary.check("unwalked"));
+        assert!(dictionary.check("1984"));
+    }
+
+    #[test]
+    fn it_follows_flags() {
+        let dictionary = dictionary();
+
+        assert!(!dictionary.check("irregardless"));
+        assert!(!dictionary.check("s"));
+        assert!(dictionary.check("Walked"));
+        assert!(dictionary.check("WALKED"));
+        assert!(!dictionary.check("PARIS"));
+        assert!(!dictionary.check("paris"));
+        assert!(dictionary.check("Paris"));
+    }
+
+    #[test]
+    fn it_checks_compounds() {
+        let dictionary = dictionary();
+
+        assert!(dictionary.check("bookcase"));
+        assert!(dictionary.check("bookcases"));
+        assert!(!dictionary.check("booksbook"));
+        assert!(!dictionary.check("bookscase"));
+        assert_eq!(dictionary.stems("bookcases"), vec!["bookcase"]);
+    }
+
+    #[test]
+    fn it_describes_words() {
+        let dictionary = dictionary();
+
+        assert_eq!(dictionary.describe("carried"), vec!["st:carry po:verb is:Tense=Past"]);
+        assert_eq!(dictionary.describe("unloved"), vec!["st:love po:verb ip:un is:Tense=Past"]);
+        assert_eq!(dictionary.stems("Carries"), vec!["carry"]);
+
+        let analyses = dictionary.analyze("books");
+
+        assert_eq!(analyses.len(), 1);
+        assert_eq!(analyses[0].lemma, "book");
+        assert_eq!(analyses[0].pos, Some(Part::Noun));
+        assert_eq!(analyses[0].features.get("Number"), Some("Plur"));
+        assert_eq!(analyses[0].tag, Some(String::from("st:book po:noun is:Number=Plur")));
+    }
+
+    #[test]
+    fn it_reads_encodings_and_aliases() {
+        let aff = b"SET ISO8859-1\nFLAG long\nAF 1\nAF AaBb\nSFX Aa Y 1\nSFX Aa 0 s .\n";
+        let dic = b"1\ncaf\xe9/1\n";
+
+        let dictionary = Dictionary::from_bytes(aff, dic).unwrap();
+
+        assert!(dictionary.check("café"));
+        assert!(dictionary.check("cafés"));
+        assert!(matches!(Dictionary::from_bytes(b"SET KOI8-R\n", b"0\n"), Err(HunspellError::Encoding(_))));
+
+        let latin9 = Dictionary::from_bytes(b"SET ISO8859-15\n", b"2\n\xbduvre\ncaf\xe9\n").unwrap();
+
+        assert!(latin9.check("œuvre"));
+        assert!(latin9.check("café"));
+        assert!(matches!(Dictionary::from_strings("", "walk\n"), Err(HunspellError::Malformed { file: "dic", line: 1, .. })));
+    }
+}
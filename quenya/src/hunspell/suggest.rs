@@ -0,0 +1,234 @@
+// Suggestions for misspelled words, found the way Hunspell looks for them:
+// first by small edits guided by the affix file (its `REP` table of common
+// misspellings, `MAP` groups of related characters, `KEY` rows of
+// neighbouring keys and `TRY` characters), and only when those find nothing
+// by comparing the word with every root in the word list.
+
+use crate::hunspell::{capitalize, case_variants, Dictionary};
+
+/// The most suggestions given for a word.
+const MAX_SUGGESTIONS: usize = 15;
+
+/// The most suggestions found by comparing the word with the word list.
+const MAX_SIMILAR: usize = 4;
+
+pub(crate) fn suggest(dictionary: &Dictionary, word: &str) -> Vec<String> {
+    let word = word.trim();
+
+    if word.is_empty() || dictionary.check(word) {
+        return Vec::new();
+    }
+
+    let mut suggestions: Vec<String> = Vec::new();
+    let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+
+    let mut candidates: Vec<String> = Vec::new();
+    let mut searched: Vec<String> = vec![String::from(word)];
+
+    searched.extend(case_variants(word));
+
+    for word in &searched {
+        candidates.extend(edits(dictionary, word));
+    }
+
+    for candidate in case_variants(word).into_iter().chain(candidates) {
+        if suggestions.len() >= MAX_SUGGESTIONS {
+            break;
+        }
+
+        let candidate = match capitalized {
+            true => capitalize(&candidate),
+            false => candidate,
+        };
+
+        if !suggestions.contains(&candidate) && dictionary.suggestable(&candidate) {
+            suggestions.push(candidate);
+        }
+    }
+
+    if suggestions.is_empty() {
+        for similar in similar_roots(dictionary, &searched[searched.len() - 1]) {
+            let similar = match capitalized {
+                true => capitalize(&similar),
+                false => similar,
+            };
+
+            if !suggestions.contains(&similar) {
+                suggestions.push(similar);
+            }
+        }
+    }
+
+    suggestions
+}
+
+/// Words one edit away from a word, the likeliest edits first.
+fn edits(dictionary: &Dictionary, word: &str) -> Vec<String> {
+    let affixes = dictionary.affixes();
+    let chars: Vec<char> = word.chars().collect();
+    let mut edits: Vec<String> = Vec::new();
+
+    // Common misspellings, anchored with `^` and `$` to the start and end of
+    // the word.
+    for (from, to) in &affixes.replacements {
+        let start = from.starts_with('^');
+        let end = from.ends_with('$') && from.len() > 1;
+        let from = from.trim_start_matches('^').trim_end_matches('$');
+
+        if from.is_empty() {
+            continue;
+        }
+
+        for (index, _) in word.match_indices(from) {
+            if (start && index != 0) || (end && index + from.len() != word.len()) {
+                continue;
+            }
+
+            edits.push([&word[..index], to.as_str(), &word[index + from.len()..]].concat());
+        }
+    }
+
+    // Related characters, such as a letter with and without an accent.
+    for group in &affixes.maps {
+        for member in group {
+            for (index, _) in word.match_indices(member.as_str()) {
+                for other in group.iter().filter(|other| *other != member) {
+                    edits.push([&word[..index], other.as_str(), &word[index + member.len()..]].concat());
+                }
+            }
+        }
+    }
+
+    // Swapped neighbours.
+    for index in 1..chars.len() {
+        let mut swapped = chars.clone();
+        swapped.swap(index - 1, index);
+
+        edits.push(swapped.into_iter().collect());
+    }
+
+    // Neighbouring keys.
+    for (index, c) in chars.iter().enumerate() {
+        for row in &affixes.keys {
+            let row: Vec<char> = row.chars().collect();
+
+            for position in row.iter().enumerate().filter(|(_, key)| *key == c).map(|(position, _)| position) {
+                let neighbours = [position.checked_sub(1), Some(position + 1)];
+
+                for neighbour in neighbours.iter().flatten().filter_map(|&neighbour| row.get(neighbour)) {
+                    edits.push(replace_at(&chars, index, Some(*neighbour)));
+                }
+            }
+        }
+    }
+
+    // A letter too many.
+    for index in 0..chars.len() {
+        edits.push(replace_at(&chars, index, None));
+    }
+
+    // A letter missing or wrong.
+    for c in affixes.try_chars.chars() {
+        for index in 0..=chars.len() {
+            let mut inserted = chars.clone();
+            inserted.insert(index, c);
+
+            edits.push(inserted.into_iter().collect());
+        }
+
+        for index in 0..chars.len() {
+            if chars[index] != c {
+                edits.push(replace_at(&chars, index, Some(c)));
+            }
+        }
+    }
+
+    // Two words written together.
+    for index in 1..chars.len() {
+        let (head, tail) = chars.split_at(index);
+
+        edits.push(format!("{} {}", head.iter().collect::<String>(), tail.iter().collect::<String>()));
+    }
+
+    edits
+}
+
+/// A word with the character at an index replaced, or removed for `None`.
+fn replace_at(chars: &[char], index: usize, c: Option<char>) -> String {
+    chars[..index].iter().chain(c.as_ref()).chain(&chars[index + 1..]).collect()
+}
+
+/// The roots most like a word, by the character n-grams they share.
+fn similar_roots(dictionary: &Dictionary, word: &str) -> Vec<String> {
+    let word: Vec<char> = word.chars().collect();
+    let mut scored: Vec<(f64, &str)> = Vec::new();
+
+    for root in dictionary.suggestable_roots() {
+        let chars: Vec<char> = root.to_lowercase().chars().collect();
+
+        // Roots of a very different length are not worth comparing.
+        if chars.len() + 3 < word.len() || word.len() + 3 < chars.len() {
+            continue;
+        }
+
+        let score = similarity(&word, &chars);
+
+        if score > 0.5 {
+            scored.push((score, root));
+        }
+    }
+
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.1.cmp(b.1)));
+
+    scored.into_iter().take(MAX_SIMILAR).map(|(_, root)| String::from(root)).collect()
+}
+
+/// The share of the n-grams of up to three characters of two words that
+/// they have in common, counted both ways.
+fn similarity(a: &[char], b: &[char]) -> f64 {
+    let shared = |a: &[char], b: &[char]| -> (usize, usize) {
+        let mut found = 0;
+        let mut total = 0;
+
+        for n in 1..=3 {
+            for gram in a.windows(n) {
+                total += 1;
+
+                if b.windows(n).any(|other| other == gram) {
+                    found += 1;
+                }
+            }
+        }
+
+        (found, total)
+    };
+
+    let (found_a, total_a) = shared(a, b);
+    let (found_b, total_b) = shared(b, a);
+
+    if total_a + total_b == 0 {
+        return 0.0;
+    }
+
+    (found_a + found_b) as f64 / (total_a + total_b) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hunspell::tests::{AFF, DIC};
+    use crate::hunspell::Dictionary;
+
+    #[test]
+    fn it_suggests_corrections() {
+        let dictionary = Dictionary::from_strings(AFF, DIC).unwrap();
+
+        assert!(dictionary.suggest("walked").is_empty());
+        assert_eq!(dictionary.suggest("wlaked")[0], "walked");
+        assert!(dictionary.suggest("carryed").contains(&String::from("carried")));
+        assert_eq!(dictionary.suggest("Wakled")[0], "Walked");
+        assert!(dictionary.suggest("walkbook").contains(&String::from("walk book")));
+        assert!(dictionary.suggest("foo").is_empty());
+        assert!(!dictionary.suggest("fo").contains(&String::from("foo")));
+        assert_eq!(dictionary.suggest("carrrry"), vec!["carry"]);
+    }
+}
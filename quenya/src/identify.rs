@@ -0,0 +1,459 @@
+// Identifying the language of a text. Every run of letters is assigned to a
+// script, and the runs of each script are scored against the character n-gram
+// profiles of the languages written in that script.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use unicode_script::{Script, UnicodeScript};
+use crate::lexicon::Lexicon;
+
+/// The longest n-grams that profiles count.
+const MAX_ORDER: usize = 3;
+
+/// The share of its letters a script needs for a profile to cover it.
+const MIN_SCRIPT_SHARE: f64 = 0.05;
+
+/// A language a text may be written in, with the estimated share of the text
+/// that is written in it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Identification {
+    pub language: String,
+    pub confidence: f64,
+}
+
+/// Character n-gram counts of one language. Words are padded with spaces, so
+/// n-grams at the start and end of words are counted separately.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Profile {
+    language: String,
+    counts: HashMap<String, u64>,
+    totals: [u64; MAX_ORDER],
+    distinct: [u64; MAX_ORDER],
+}
+
+impl Profile {
+    pub fn new(language: &str) -> Profile {
+        Profile {
+            language: String::from(language),
+            ..Profile::default()
+        }
+    }
+
+    /// Trains a profile on the lemmas and forms of a lexicon. Lemmas are
+    /// weighted by their corpus frequency.
+    pub fn from_lexicon(language: &str, lexicon: &Lexicon) -> Profile {
+        let mut profile = Profile::new(language);
+
+        for lexeme in lexicon.lexemes() {
+            profile.add_word(&lexeme.lemma, lexeme.frequency + 1);
+
+            for form in &lexeme.forms {
+                profile.add_word(&form.form, 1);
+            }
+        }
+
+        profile
+    }
+
+    /// Reads a profile written by `write`, with one `n-gram<TAB>count` line per
+    /// n-gram.
+    pub fn read<R: Read>(language: &str, reader: R) -> io::Result<Profile> {
+        let mut profile = Profile::new(language);
+
+        for (number, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let count = line.rsplit_once('\t').and_then(|(ngram, count)| {
+                let order = ngram.chars().count();
+
+                match count.parse::<u64>() {
+                    Ok(count) if (1..=MAX_ORDER).contains(&order) => Some((ngram, count)),
+                    _ => None,
+                }
+            });
+
+            match count {
+                Some((ngram, count)) => profile.add_ngram(ngram, count),
+                None => return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: expected an n-gram and a count", number + 1),
+                )),
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /// Writes the n-gram counts, most frequent first.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut counts: Vec<(&String, &u64)> = self.counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+        for (ngram, count) in counts {
+            writeln!(writer, "{}\t{}", ngram, count)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Counts the n-grams of every word of a text.
+    pub fn add_text(&mut self, text: &str) {
+        for word in letter_runs(text) {
+            self.add_word(&word.text, 1);
+        }
+    }
+
+    pub fn add_word(&mut self, word: &str, weight: u64) {
+        for (_, ngram) in ngrams(word) {
+            self.add_ngram(&ngram, weight);
+        }
+    }
+
+    fn add_ngram(&mut self, ngram: &str, count: u64) {
+        let order = ngram.chars().count() - 1;
+        let entry = self.counts.entry(String::from(ngram)).or_insert(0);
+
+        if *entry == 0 {
+            self.distinct[order] += 1;
+        }
+
+        *entry += count;
+        self.totals[order] += count;
+    }
+
+    /// The scripts that make up at least 5% of the letters the profile has
+    /// seen.
+    pub fn scripts(&self) -> Vec<Script> {
+        let mut letters: Vec<(Script, u64)> = Vec::new();
+
+        for (ngram, count) in &self.counts {
+            let mut chars = ngram.chars();
+
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                let script = c.script();
+
+                match letters.iter_mut().find(|(seen, _)| *seen == script) {
+                    Some((_, total)) => *total += count,
+                    None => letters.push((script, *count)),
+                }
+            }
+        }
+
+        let total: u64 = letters.iter().map(|(_, count)| count).sum();
+
+        letters.into_iter()
+            .filter(|(_, count)| *count as f64 >= total as f64 * MIN_SCRIPT_SHARE)
+            .map(|(script, _)| script)
+            .collect()
+    }
+
+    /// The log-likelihood of the n-grams of every order in the words, with
+    /// add-one smoothing for unseen n-grams.
+    fn log_likelihood(&self, words: &[&str]) -> f64 {
+        let mut likelihood = 0.0;
+
+        for word in words {
+            for (order, ngram) in ngrams(word) {
+                let count = self.counts.get(&ngram).copied().unwrap_or(0);
+                let total = self.totals[order - 1] + self.distinct[order - 1] + 1;
+
+                likelihood += ((count + 1) as f64 / total as f64).ln();
+            }
+        }
+
+        likelihood
+    }
+}
+
+/// The n-grams of a lowercased word padded with spaces, with their order.
+fn ngrams(word: &str) -> Vec<(usize, String)> {
+    let padded: Vec<char> = format!(" {} ", word.to_lowercase()).chars().collect();
+    let mut ngrams = Vec::new();
+
+    for order in 1..=MAX_ORDER {
+        for ngram in padded.windows(order) {
+            if order > 1 || ngram[0] != ' ' {
+                ngrams.push((order, ngram.iter().collect()));
+            }
+        }
+    }
+
+    ngrams
+}
+
+/// Identifies languages from n-gram profiles. Scripts that no profile covers
+/// fall back to the language they are most commonly written in, if they have
+/// one.
+#[derive(Debug, Default)]
+pub struct Identifier {
+    profiles: Vec<(Profile, Vec<Script>)>,
+}
+
+impl Identifier {
+    pub fn new() -> Identifier {
+        Identifier {
+            profiles: Vec::new(),
+        }
+    }
+
+    /// Adds a profile, replacing any earlier profile of the same language.
+    pub fn add_profile(&mut self, profile: Profile) {
+        self.profiles.retain(|(existing, _)| existing.language != profile.language);
+
+        let scripts = profile.scripts();
+
+        self.profiles.push((profile, scripts));
+    }
+
+    pub fn languages(&self) -> Vec<&str> {
+        self.profiles.iter().map(|(profile, _)| profile.language()).collect()
+    }
+
+    /// Ranks the languages the text may be written in. The confidences add up
+    /// to the share of letters whose language could be determined.
+    pub fn identify(&self, text: &str) -> Vec<Identification> {
+        let runs = letter_runs(text);
+        let letters: usize = runs.iter().map(|run| run.letters).sum();
+
+        let mut classes: Vec<Script> = Vec::new();
+
+        for run in &runs {
+            if !classes.contains(&run.class) {
+                classes.push(run.class);
+            }
+        }
+
+        let mut confidences: HashMap<String, f64> = HashMap::new();
+
+        for class in classes {
+            let class_runs: Vec<&LetterRun> = runs.iter().filter(|run| run.class == class).collect();
+
+            let share = class_runs.iter().map(|run| run.letters).sum::<usize>() as f64 / letters as f64;
+
+            for (language, probability) in self.score(&class_runs) {
+                *confidences.entry(language).or_insert(0.0) += share * probability;
+            }
+        }
+
+        let mut identifications: Vec<Identification> = confidences.into_iter()
+            .map(|(language, confidence)| Identification { language, confidence })
+            .collect();
+
+        identifications.sort_by(|a, b| {
+            b.confidence.partial_cmp(&a.confidence)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.language.cmp(&b.language))
+        });
+
+        identifications
+    }
+
+    /// The probability of each language given runs of letters in related
+    /// scripts.
+    fn score(&self, runs: &[&LetterRun]) -> Vec<(String, f64)> {
+        let mut scripts: Vec<Script> = Vec::new();
+
+        for run in runs {
+            for script in &run.scripts {
+                if !scripts.contains(script) {
+                    scripts.push(*script);
+                }
+            }
+        }
+
+        let candidates: Vec<&Profile> = self.profiles.iter()
+            .filter(|(_, covered)| scripts.iter().any(|script| covered.contains(script)))
+            .map(|(profile, _)| profile)
+            .collect();
+
+        if candidates.is_empty() {
+            return match language_for_scripts(&scripts) {
+                Some(language) => vec![(String::from(language), 1.0)],
+                None => Vec::new(),
+            };
+        }
+
+        let words: Vec<&str> = runs.iter().map(|run| run.text.as_str()).collect();
+        let likelihoods: Vec<f64> = candidates.iter().map(|profile| profile.log_likelihood(&words)).collect();
+
+        let best = likelihoods.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = likelihoods.iter().map(|likelihood| (likelihood - best).exp()).collect();
+        let total: f64 = weights.iter().sum();
+
+        candidates.iter()
+            .zip(weights)
+            .map(|(profile, weight)| (String::from(profile.language()), weight / total))
+            .collect()
+    }
+}
+
+/// Counts the letters of a text per script, most frequent first. Characters
+/// shared between scripts, such as digits and punctuation, are not counted.
+pub fn detect_scripts(text: &str) -> Vec<(Script, usize)> {
+    let mut counts: Vec<(Script, usize)> = Vec::new();
+
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        let script = c.script();
+
+        if script == Script::Common || script == Script::Inherited || script == Script::Unknown {
+            continue;
+        }
+
+        match counts.iter_mut().find(|(seen, _)| *seen == script) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((script, 1)),
+        }
+    }
+
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    counts
+}
+
+/// A word-like run of letters from related scripts.
+struct LetterRun {
+    text: String,
+    class: Script,
+    scripts: Vec<Script>,
+    letters: usize,
+}
+
+/// Splits text into runs of letters. Han and kana are kept together, since
+/// Japanese mixes them within words.
+fn letter_runs(text: &str) -> Vec<LetterRun> {
+    let mut runs: Vec<LetterRun> = Vec::new();
+    let mut current: Option<LetterRun> = None;
+
+    for c in text.chars() {
+        let script = c.script();
+
+        if script == Script::Inherited && current.is_some() {
+            continue;
+        }
+
+        let is_letter = c.is_alphabetic() && script != Script::Common && script != Script::Unknown;
+
+        if !is_letter {
+            runs.extend(current.take());
+            continue;
+        }
+
+        let class = match script {
+            Script::Hiragana | Script::Katakana => Script::Han,
+            script => script,
+        };
+
+        if current.as_ref().is_some_and(|run| run.class != class) {
+            runs.extend(current.take());
+        }
+
+        let run = current.get_or_insert_with(|| LetterRun {
+            text: String::new(),
+            class,
+            scripts: Vec::new(),
+            letters: 0,
+        });
+
+        run.text.push(c);
+        run.letters += 1;
+
+        if !run.scripts.contains(&script) {
+            run.scripts.push(script);
+        }
+    }
+
+    runs.extend(current);
+
+    runs
+}
+
+/// The language a script is written in when no profile covers it, for scripts
+/// that are mostly used for one language.
+pub(crate) fn language_for_scripts(scripts: &[Script]) -> Option<&'static str> {
+    if scripts.contains(&Script::Hiragana) || scripts.contains(&Script::Katakana) {
+        return Some("jpn");
+    }
+
+    match scripts.first()? {
+        Script::Arabic => Some("ara"),
+        Script::Hangul => Some("kor"),
+        Script::Greek => Some("ell"),
+        Script::Hebrew => Some("heb"),
+        Script::Thai => Some("tha"),
+        Script::Han => Some("zho"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identifier() -> Identifier {
+        let mut identifier = Identifier::new();
+
+        let mut english = Profile::new("eng");
+        english.add_text("the cat sat on the mat and then the dog went to the house with the phone");
+
+        let mut dutch = Profile::new("nld");
+        dutch.add_text("de kat zat op de mat en toen ging de hond naar het huis van de buurman");
+
+        let mut japanese = Profile::new("jpn");
+        japanese.add_text("東京で本を買った。私は学生です。猫が好きです。");
+
+        identifier.add_profile(english);
+        identifier.add_profile(dutch);
+        identifier.add_profile(japanese);
+
+        identifier
+    }
+
+    #[test]
+    fn it_detects_scripts() {
+        assert_eq!(detect_scripts("東京でiPhoneを買った"), vec![(Script::Latin, 6), (Script::Hiragana, 4), (Script::Han, 3)]);
+    }
+
+    #[test]
+    fn it_ranks_languages_with_ngram_profiles() {
+        let identifications = identifier().identify("de hond zat in het huis");
+
+        assert_eq!(identifications[0].language, "nld");
+        assert_eq!(identifications[0].confidence > 0.9, true);
+    }
+
+    #[test]
+    fn it_identifies_mixed_script_text() {
+        let identifications = identifier().identify("私はphoneを買った");
+
+        assert_eq!(identifications[0].language, "jpn");
+        assert_eq!(identifications[1].language, "eng");
+        assert_eq!((identifications.iter().map(|identification| identification.confidence).sum::<f64>() - 1.0).abs() < 1e-9, true);
+    }
+
+    #[test]
+    fn it_falls_back_to_scripts_without_profiles() {
+        let identifications = Identifier::new().identify("كتب الولد");
+
+        assert_eq!(identifications, vec![Identification { language: String::from("ara"), confidence: 1.0 }]);
+    }
+
+    #[test]
+    fn it_reads_written_profiles() {
+        let mut profile = Profile::new("nld");
+        profile.add_text("de kat");
+
+        let mut written: Vec<u8> = Vec::new();
+        profile.write(&mut written).unwrap();
+
+        assert_eq!(Profile::read("nld", written.as_slice()).unwrap(), profile);
+        assert_eq!(Profile::read("nld", "de kat".as_bytes()).is_err(), true);
+    }
+}
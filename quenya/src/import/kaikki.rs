@@ -0,0 +1,327 @@
+// Importer for Wiktionary dumps extracted by Wiktextract, as published on
+// https://kaikki.org. Every line of a dump is one JSON object describing a
+// word with a single part of speech.
+
+use std::io::{BufRead, BufReader, Read};
+use serde_json::Value;
+use wn::pos::Part;
+use crate::features::Features;
+use crate::import::ImportError;
+use crate::lexicon::{Form, Lexeme, Lexicon, Translation};
+
+/// Form tags that mark the headword or describe the inflection table rather
+/// than an inflected form.
+const IGNORED_FORM_TAGS: [&str; 5] = ["canonical", "table-tags", "inflection-template", "class", "romanization"];
+
+pub fn part_for_pos(pos: &str) -> Option<Part> {
+    match pos {
+        "noun" | "name" => Some(Part::Noun),
+        "verb" => Some(Part::Verb),
+        "adj" => Some(Part::Adjective),
+        "adv" => Some(Part::Adverb),
+        _ => None,
+    }
+}
+
+/// Reads a kaikki.org JSONL dump into `lexicon`. Entries for a lemma and part
+/// of speech that is already present are merged into the existing lexeme.
+/// Entries with a part of speech that has no `Part` counterpart are skipped.
+/// Form-of entries, such as `went` as the past tense of `go`, are added as
+/// forms of their lemma once the whole dump is read, and skipped when the
+/// lemma is not in the lexicon. Returns the number of entries that were
+/// imported, counting a form-of entry when it was attached to a lemma.
+pub fn import_kaikki<R: Read>(reader: R, lexicon: &mut Lexicon) -> Result<usize, ImportError> {
+    let mut imported = 0;
+    let mut forms_of: Vec<(usize, String, Part, String, Features)> = Vec::new();
+
+    for (number, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry: Value = serde_json::from_str(&line).map_err(|error| ImportError::Malformed {
+            line: number + 1,
+            message: error.to_string(),
+        })?;
+
+        let word = match entry["word"].as_str() {
+            Some(word) => word,
+            None => return Err(ImportError::Malformed {
+                line: number + 1,
+                message: String::from("entry has no word"),
+            }),
+        };
+
+        let pos = match entry["pos"].as_str().and_then(part_for_pos) {
+            Some(pos) => pos,
+            None => continue,
+        };
+
+        if let Some(form_of) = form_of(&entry) {
+            forms_of.extend(form_of.into_iter().map(|(lemma, features)| (number, lemma, pos, String::from(word), features)));
+            continue;
+        }
+
+        let mut lexeme = match lexicon.remove_lexeme(word, pos) {
+            Some(lexeme) => lexeme,
+            None => Lexeme::new(word, pos),
+        };
+
+        add_entry(&entry, &mut lexeme);
+
+        lexicon.add_lexeme(lexeme);
+
+        imported += 1;
+    }
+
+    // The forms of an entry are next to each other, so an entry is counted
+    // the first time one of its forms is attached.
+    let mut attached: Option<usize> = None;
+
+    for (number, lemma, pos, form, features) in &forms_of {
+        if lexicon.find_lexeme_by_lemma(lemma, *pos).is_none() {
+            continue;
+        }
+
+        lexicon.add_form(lemma, *pos, form, features.clone());
+
+        if attached != Some(*number) {
+            attached = Some(*number);
+            imported += 1;
+        }
+    }
+
+    Ok(imported)
+}
+
+/// The lemmas and features of an entry whose senses are all form-of senses,
+/// or `None` for an entry that describes a lemma of its own.
+fn form_of(entry: &Value) -> Option<Vec<(String, Features)>> {
+    let mut lemmas: Vec<(String, Features)> = Vec::new();
+
+    for sense in array(&entry["senses"]) {
+        let tags: Vec<&str> = array(&sense["tags"])
+            .filter_map(Value::as_str)
+            .filter(|tag| *tag != "form-of")
+            .collect();
+
+        let mut found = false;
+
+        for lemma in array(&sense["form_of"]).filter_map(|form_of| form_of["word"].as_str()) {
+            lemmas.push((String::from(lemma), parse_tags(&tags)));
+            found = true;
+        }
+
+        if !found {
+            return None;
+        }
+    }
+
+    match lemmas.is_empty() {
+        true => None,
+        false => Some(lemmas),
+    }
+}
+
+fn add_entry(entry: &Value, lexeme: &mut Lexeme) {
+    for sense in array(&entry["senses"]) {
+        for gloss in array(&sense["glosses"]).filter_map(Value::as_str) {
+            push_unique(&mut lexeme.glosses, gloss);
+        }
+
+        add_translations(&sense["translations"], lexeme);
+    }
+
+    add_translations(&entry["translations"], lexeme);
+
+    for sound in array(&entry["sounds"]) {
+        if let Some(ipa) = sound["ipa"].as_str() {
+            push_unique(&mut lexeme.pronunciations, ipa);
+        }
+    }
+
+    if let Some(etymology) = entry["etymology_text"].as_str() {
+        lexeme.etymology = match lexeme.etymology.take() {
+            Some(existing) if existing != etymology => Some(format!("{}\n{}", existing, etymology)),
+            _ => Some(String::from(etymology)),
+        };
+    }
+
+    for form in array(&entry["forms"]) {
+        let text = match form["form"].as_str() {
+            Some(text) if !text.is_empty() && text != "-" => text,
+            _ => continue,
+        };
+
+        let tags: Vec<&str> = array(&form["tags"]).filter_map(Value::as_str).collect();
+
+        if tags.iter().any(|tag| IGNORED_FORM_TAGS.contains(tag)) {
+            continue;
+        }
+
+        let form = Form::new(text, parse_tags(&tags));
+
+        if !lexeme.forms.iter().any(|existing| existing.same_as(&form)) {
+            lexeme.forms.push(form);
+        }
+    }
+}
+
+fn add_translations(translations: &Value, lexeme: &mut Lexeme) {
+    for translation in array(translations) {
+        let language = translation["code"].as_str().or_else(|| translation["lang"].as_str());
+
+        if let (Some(language), Some(word)) = (language, translation["word"].as_str()) {
+            let translation = Translation {
+                language: String::from(language),
+                word: String::from(word),
+            };
+
+            if !lexeme.translations.contains(&translation) {
+                lexeme.translations.push(translation);
+            }
+        }
+    }
+}
+
+/// Maps Wiktionary form tags onto UD-style features. Tags without a mapping
+/// are kept together under the `Wiktionary` feature.
+pub fn parse_tags(tags: &[&str]) -> Features {
+    let mut features = Features::new();
+    let mut unmapped: Vec<&str> = Vec::new();
+
+    for tag in tags {
+        match feature_for_tag(tag) {
+            Some((name, value)) => features.insert(name, value),
+            None => unmapped.push(tag),
+        }
+    }
+
+    if !unmapped.is_empty() {
+        features.insert("Wiktionary", &unmapped.join(","));
+    }
+
+    features
+}
+
+fn feature_for_tag(tag: &str) -> Option<(&'static str, &'static str)> {
+    let feature = match tag {
+        "first-person" => ("Person", "1"),
+        "second-person" => ("Person", "2"),
+        "third-person" => ("Person", "3"),
+        "singular" => ("Number", "Sing"),
+        "plural" => ("Number", "Plur"),
+        "dual" => ("Number", "Dual"),
+        "masculine" => ("Gender", "Masc"),
+        "feminine" => ("Gender", "Fem"),
+        "neuter" => ("Gender", "Neut"),
+        "nominative" => ("Case", "Nom"),
+        "accusative" => ("Case", "Acc"),
+        "genitive" => ("Case", "Gen"),
+        "dative" => ("Case", "Dat"),
+        "definite" => ("Definite", "Def"),
+        "indefinite" => ("Definite", "Ind"),
+        "present" => ("Tense", "Pres"),
+        "past" => ("Tense", "Past"),
+        "future" => ("Tense", "Fut"),
+        "indicative" => ("Mood", "Ind"),
+        "subjunctive" => ("Mood", "Sub"),
+        "imperative" => ("Mood", "Imp"),
+        "conditional" => ("Mood", "Cnd"),
+        "infinitive" => ("VerbForm", "Inf"),
+        "participle" => ("VerbForm", "Part"),
+        "gerund" => ("VerbForm", "Ger"),
+        "perfective" => ("Aspect", "Perf"),
+        "imperfective" => ("Aspect", "Imp"),
+        "active" => ("Voice", "Act"),
+        "passive" => ("Voice", "Pass"),
+        "comparative" => ("Degree", "Cmp"),
+        "superlative" => ("Degree", "Sup"),
+        "negative" => ("Polarity", "Neg"),
+        "formal" => ("Polite", "Form"),
+        "informal" => ("Polite", "Infm"),
+        _ => return None,
+    };
+
+    Some(feature)
+}
+
+fn array(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
+
+fn push_unique(values: &mut Vec<String>, value: &str) {
+    if !values.iter().any(|existing| existing == value) {
+        values.push(String::from(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRY: &str = r#"{"word": "zoeken", "pos": "verb", "lang": "Dutch", "lang_code": "nl", "etymology_text": "From Middle Dutch soeken.", "sounds": [{"ipa": "/ˈzu.kə(n)/"}], "senses": [{"glosses": ["to search, look for"], "translations": [{"code": "en", "lang": "English", "word": "search"}]}], "forms": [{"form": "zocht", "tags": ["past", "singular"]}, {"form": "gezocht", "tags": ["participle", "past"]}, {"form": "nl-conj", "tags": ["table-tags"]}]}"#;
+
+    #[test]
+    fn it_imports_a_dutch_verb() {
+        let mut lexicon = Lexicon::new();
+
+        let imported = import_kaikki(ENTRY.as_bytes(), &mut lexicon).unwrap();
+        let lexeme = lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap();
+
+        assert_eq!(imported, 1);
+        assert_eq!(lexeme.glosses, vec!["to search, look for"]);
+        assert_eq!(lexeme.pronunciations, vec!["/ˈzu.kə(n)/"]);
+        assert_eq!(lexeme.etymology, Some(String::from("From Middle Dutch soeken.")));
+        assert_eq!(lexeme.translations[0], Translation { language: String::from("en"), word: String::from("search") });
+        assert_eq!(lexeme.forms.len(), 2);
+        assert_eq!(lexicon.find_readings_by_form("zocht")[0].features.to_string(), "Number=Sing|Tense=Past");
+    }
+
+    #[test]
+    fn it_merges_entries_of_the_same_lexeme() {
+        let mut lexicon = Lexicon::new();
+        let second = r#"{"word": "zoeken", "pos": "verb", "senses": [{"glosses": ["to seek"]}]}"#;
+
+        import_kaikki(format!("{}\n{}\n", ENTRY, second).as_bytes(), &mut lexicon).unwrap();
+
+        assert_eq!(lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap().glosses.len(), 2);
+    }
+
+    #[test]
+    fn it_skips_unsupported_parts_of_speech() {
+        let mut lexicon = Lexicon::new();
+
+        let imported = import_kaikki(r#"{"word": "de", "pos": "article"}"#.as_bytes(), &mut lexicon).unwrap();
+
+        assert_eq!(imported, 0);
+        assert!(lexicon.is_empty());
+    }
+
+    #[test]
+    fn it_attaches_form_of_entries_to_their_lemma() {
+        let mut lexicon = Lexicon::new();
+        let form_of = r#"{"word": "zocht", "pos": "verb", "senses": [{"glosses": ["singular past indicative of zoeken"], "tags": ["form-of", "past", "singular"], "form_of": [{"word": "zoeken"}]}]}"#;
+        let subjunctive = r#"{"word": "zocht", "pos": "verb", "senses": [{"glosses": ["singular past subjunctive of zoeken"], "tags": ["form-of", "past", "subjunctive"], "form_of": [{"word": "zoeken"}]}]}"#;
+        let orphan = r#"{"word": "liep", "pos": "verb", "senses": [{"glosses": ["singular past indicative of lopen"], "tags": ["form-of", "past"], "form_of": [{"word": "lopen"}]}]}"#;
+
+        let imported = import_kaikki(format!("{}\n{}\n{}\n{}\n", form_of, subjunctive, orphan, ENTRY).as_bytes(), &mut lexicon).unwrap();
+
+        assert_eq!(imported, 3);
+        assert_eq!(lexicon.len(), 1);
+        assert!(lexicon.find_lexeme_by_lemma("zocht", Part::Verb).is_none());
+        assert_eq!(lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap().forms.len(), 3);
+        assert_eq!(lexicon.find_readings_by_form("zocht").len(), 2);
+        assert!(lexicon.find_readings_by_form("liep").is_empty());
+    }
+
+    #[test]
+    fn it_keeps_unmapped_tags() {
+        let features = parse_tags(&["plural", "diminutive"]);
+
+        assert_eq!(features.get("Number"), Some("Plur"));
+        assert_eq!(features.get("Wiktionary"), Some("diminutive"));
+    }
+}
@@ -0,0 +1,31 @@
+use std::fmt;
+use std::io;
+
+pub mod kaikki;
+pub mod treebank;
+pub mod unimorph;
+pub mod wordnet;
+
+/// An error raised while importing an external resource into a `Lexicon`.
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    Malformed { line: usize, message: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(error) => write!(f, "could not read input: {}", error),
+            ImportError::Malformed { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(error: io::Error) -> ImportError {
+        ImportError::Io(error)
+    }
+}
@@ -0,0 +1,86 @@
+// Builds lexicons from Universal Dependencies treebanks in CoNLL-U format.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use crate::conllu::{read_conllu, Sentence};
+use crate::import::ImportError;
+use crate::lexicon::Lexicon;
+use crate::text::part_for_upos;
+
+/// Adds every attested word of the sentences to `lexicon`: its form and
+/// feature bundle are recorded on the lexeme and the lexeme's frequency is
+/// incremented. Words without a lemma, or with a part of speech that has no
+/// `Part` counterpart, are skipped. Returns the number of words counted.
+pub fn add_sentences(sentences: &[Sentence], lexicon: &mut Lexicon) -> usize {
+    let mut counted = 0;
+
+    for sentence in sentences {
+        for word in sentence.syntactic_words() {
+            let lemma = match &word.lemma {
+                Some(lemma) => lemma,
+                None => continue,
+            };
+
+            let pos = match word.upos.as_deref().and_then(part_for_upos) {
+                Some(pos) => pos,
+                None => continue,
+            };
+
+            lexicon.add_form(lemma, pos, &word.form, word.feats.clone());
+            lexicon.increment_frequency(lemma, pos);
+
+            counted += 1;
+        }
+    }
+
+    counted
+}
+
+pub fn import_treebank<R: Read>(reader: R, lexicon: &mut Lexicon) -> Result<usize, ImportError> {
+    let sentences = read_conllu(reader)?;
+
+    Ok(add_sentences(&sentences, lexicon))
+}
+
+/// Builds a lexicon from one or more `.conllu` files.
+pub fn build_lexicon_from_treebanks<P: AsRef<Path>>(paths: &[P]) -> Result<Lexicon, ImportError> {
+    let mut lexicon = Lexicon::new();
+
+    for path in paths {
+        import_treebank(File::open(path)?, &mut lexicon)?;
+    }
+
+    Ok(lexicon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wn::pos::Part;
+
+    const TREEBANK: &str = "1\tHij\thij\tPRON\t_\tCase=Nom|Person=3\t2\tnsubj\t_\t_\n2\tzoekt\tzoeken\tVERB\t_\tNumber=Sing|Person=3|Tense=Pres\t0\troot\t_\t_\n\n1\tWij\twij\tPRON\t_\t_\t2\tnsubj\t_\t_\n2\tzoeken\tzoeken\tVERB\t_\tNumber=Plur|Tense=Pres\t0\troot\t_\t_\n3\tzoekt\tzoeken\tVERB\t_\tNumber=Sing|Person=3|Tense=Pres\t0\troot\t_\t_\n";
+
+    #[test]
+    fn it_counts_lexeme_frequencies() {
+        let mut lexicon = Lexicon::new();
+
+        let counted = import_treebank(TREEBANK.as_bytes(), &mut lexicon).unwrap();
+
+        assert_eq!(counted, 3);
+        assert_eq!(lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap().frequency, 3);
+        assert!(lexicon.find_lexeme_by_lemma("hij", Part::Noun).is_none());
+    }
+
+    #[test]
+    fn it_records_attested_forms_once() {
+        let mut lexicon = Lexicon::new();
+
+        import_treebank(TREEBANK.as_bytes(), &mut lexicon).unwrap();
+
+        let lexeme = lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap();
+
+        assert_eq!(lexeme.forms.len(), 2);
+        assert_eq!(lexicon.find_readings_by_form("zoekt")[0].features.get("Person"), Some("3"));
+    }
+}
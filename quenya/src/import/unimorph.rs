@@ -0,0 +1,175 @@
+// Importer for UniMorph paradigm files (https://unimorph.github.io).
+//
+// Every line holds `lemma<TAB>form<TAB>features`, where the features are a
+// semicolon-separated list of UniMorph tags such as `V;IND;PRS;3;SG`.
+
+use std::io::{BufRead, BufReader, Read};
+use wn::pos::Part;
+use crate::features::Features;
+use crate::import::ImportError;
+use crate::lexicon::Lexicon;
+
+/// Reads a UniMorph file into `lexicon`, adding every form to the lexeme of
+/// its lemma. Lines whose part of speech has no `Part` counterpart are skipped.
+/// Returns the number of forms that were imported.
+pub fn import_unimorph<R: Read>(reader: R, lexicon: &mut Lexicon) -> Result<usize, ImportError> {
+    let mut imported = 0;
+
+    for (number, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split('\t');
+
+        let (lemma, form, tags) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(lemma), Some(form), Some(tags)) => (lemma, form, tags),
+            _ => return Err(ImportError::Malformed {
+                line: number + 1,
+                message: String::from("expected lemma, form and features separated by tabs"),
+            }),
+        };
+
+        if let Some((pos, features)) = parse_tags(tags) {
+            lexicon.add_form(lemma, pos, form, features);
+
+            imported += 1;
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Maps a UniMorph tag string onto a part of speech and UD-style features.
+/// Tags without a mapping are kept together under the `UniMorph` feature.
+pub fn parse_tags(tags: &str) -> Option<(Part, Features)> {
+    let mut pos = None;
+    let mut features = Features::new();
+    let mut unmapped: Vec<&str> = Vec::new();
+
+    for tag in tags.split(';') {
+        match tag {
+            "V" => pos = Some(Part::Verb),
+            "V.PTCP" => {
+                pos = Some(Part::Verb);
+                features.insert("VerbForm", "Part");
+            }
+            "V.CVB" => {
+                pos = Some(Part::Verb);
+                features.insert("VerbForm", "Conv");
+            }
+            "V.MSDR" => {
+                pos = Some(Part::Verb);
+                features.insert("VerbForm", "Vnoun");
+            }
+            "N" | "PROPN" => pos = Some(Part::Noun),
+            "ADJ" => pos = Some(Part::Adjective),
+            "ADV" => pos = Some(Part::Adverb),
+            _ => match feature_for_tag(tag) {
+                Some((name, value)) => features.insert(name, value),
+                None => unmapped.push(tag),
+            }
+        }
+    }
+
+    if !unmapped.is_empty() {
+        features.insert("UniMorph", &unmapped.join(","));
+    }
+
+    pos.map(|pos| (pos, features))
+}
+
+fn feature_for_tag(tag: &str) -> Option<(&'static str, &'static str)> {
+    let feature = match tag {
+        "1" => ("Person", "1"),
+        "2" => ("Person", "2"),
+        "3" => ("Person", "3"),
+        "SG" => ("Number", "Sing"),
+        "PL" => ("Number", "Plur"),
+        "DU" => ("Number", "Dual"),
+        "MASC" => ("Gender", "Masc"),
+        "FEM" => ("Gender", "Fem"),
+        "NEUT" => ("Gender", "Neut"),
+        "NOM" => ("Case", "Nom"),
+        "ACC" => ("Case", "Acc"),
+        "GEN" => ("Case", "Gen"),
+        "DAT" => ("Case", "Dat"),
+        "DEF" => ("Definite", "Def"),
+        "INDF" => ("Definite", "Ind"),
+        "PRS" => ("Tense", "Pres"),
+        "PST" => ("Tense", "Past"),
+        "FUT" => ("Tense", "Fut"),
+        "IND" => ("Mood", "Ind"),
+        "SBJV" => ("Mood", "Sub"),
+        "IMP" => ("Mood", "Imp"),
+        "COND" => ("Mood", "Cnd"),
+        "FIN" => ("VerbForm", "Fin"),
+        "NFIN" => ("VerbForm", "Inf"),
+        "IPFV" => ("Aspect", "Imp"),
+        "PFV" => ("Aspect", "Perf"),
+        "PROG" => ("Aspect", "Prog"),
+        "ACT" => ("Voice", "Act"),
+        "PASS" => ("Voice", "Pass"),
+        "CMPR" => ("Degree", "Cmp"),
+        "SPRL" => ("Degree", "Sup"),
+        "NEG" => ("Polarity", "Neg"),
+        "FORM" => ("Polite", "Form"),
+        "INFM" => ("Polite", "Infm"),
+        _ => return None,
+    };
+
+    Some(feature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_maps_verb_tags() {
+        let (pos, features) = parse_tags("V;IND;PRS;3;SG").unwrap();
+
+        assert_eq!(pos, Part::Verb);
+        assert_eq!(features.to_string(), "Mood=Ind|Number=Sing|Person=3|Tense=Pres");
+    }
+
+    #[test]
+    fn it_keeps_unmapped_tags() {
+        let (pos, features) = parse_tags("N;PL;LGSPEC1").unwrap();
+
+        assert_eq!(pos, Part::Noun);
+        assert_eq!(features.get("UniMorph"), Some("LGSPEC1"));
+    }
+
+    #[test]
+    fn it_skips_unsupported_parts_of_speech() {
+        assert_eq!(parse_tags("PRO;NOM;1;SG").is_none(), true);
+    }
+
+    #[test]
+    fn it_imports_a_dutch_paradigm() {
+        let data = "zoeken\tzoekt\tV;IND;PRS;3;SG\nzoeken\tzocht\tV;IND;PST;3;SG\nzoeken\tgezocht\tV.PTCP;PST\n\nik\tik\tPRO;NOM;1;SG\n";
+
+        let mut lexicon = Lexicon::new();
+
+        let imported = import_unimorph(data.as_bytes(), &mut lexicon).unwrap();
+
+        assert_eq!(imported, 3);
+        assert_eq!(lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap().forms.len(), 3);
+        assert_eq!(lexicon.find_readings_by_form("gezocht")[0].features.get("VerbForm"), Some("Part"));
+    }
+
+    #[test]
+    fn it_reports_malformed_lines() {
+        let mut lexicon = Lexicon::new();
+
+        let result = import_unimorph("zoeken\tzoekt\n".as_bytes(), &mut lexicon);
+
+        match result {
+            Err(ImportError::Malformed { line, .. }) => assert_eq!(line, 1),
+            _ => panic!("expected a malformed line error"),
+        }
+    }
+}
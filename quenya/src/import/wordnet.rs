@@ -0,0 +1,63 @@
+// Importer for the tab-separated wordnets of the Open Multilingual Wordnet.
+
+use std::io::{BufRead, BufReader, Read};
+use wn::multi::parse::{try_parse_multilingual_wordnet_line, Entry};
+use crate::import::ImportError;
+use crate::lexicon::{Lexeme, Lexicon};
+
+/// Adds a lexeme for every lemma in an OMW `wn-data-<language>.tab` file.
+/// Definitions and examples are skipped. Returns the number of lemmas read,
+/// or an error for the first line that is not an OMW line.
+pub fn import_multilingual_wordnet<R: Read>(reader: R, lexicon: &mut Lexicon) -> Result<usize, ImportError> {
+    let mut imported = 0;
+
+    for (number, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        let entry = match try_parse_multilingual_wordnet_line(&line) {
+            Some(entry) => entry,
+            None => return Err(ImportError::Malformed {
+                line: number + 1,
+                message: String::from("expected '<offset>-<pos>\\t<language>:<type>\\t<content>'"),
+            }),
+        };
+
+        if let Entry::Lemma(lemma) = entry {
+            if lexicon.find_lexeme_by_lemma(&lemma.lemma, lemma.pos).is_none() {
+                lexicon.add_lexeme(Lexeme::new(&lemma.lemma, lemma.pos));
+            }
+
+            imported += 1;
+        }
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wn::pos::Part;
+
+    #[test]
+    fn it_imports_lemmas() {
+        let mut lexicon = Lexicon::new();
+        let tab = "# Dutch\n01315613-v\tnld:lemma\tzoeken\n01315613-v\tnld:def\t0\tproberen te vinden\n00186616-s\tnld:lemma\tgroot\n";
+
+        assert_eq!(import_multilingual_wordnet(tab.as_bytes(), &mut lexicon).unwrap(), 2);
+        assert!(lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).is_some());
+        assert!(lexicon.find_lexeme_by_lemma("groot", Part::Adjective).is_some());
+    }
+
+    #[test]
+    fn it_reports_malformed_lines() {
+        let mut lexicon = Lexicon::new();
+        let tab = "01315613-v\tnld:lemma\tzoeken\nzoeken\n";
+
+        assert!(matches!(import_multilingual_wordnet(tab.as_bytes(), &mut lexicon), Err(ImportError::Malformed { line: 2, .. })));
+    }
+}
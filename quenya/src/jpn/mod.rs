@@ -1 +1,55 @@
+use wn::pos::Part;
+use crate::features::Features;
+use crate::language::{Analysis, Capability, Language, analyze_with_lexicon, inflect_with_lexicon};
+use crate::stem::Algorithm;
+use crate::lexicon::Lexicon;
+
 pub mod particles;
+
+/// Japanese. Words are analysed and inflected through the lexicon, if there
+/// is one.
+pub struct Japanese {
+    lexicon: Option<Lexicon>,
+}
+
+impl Japanese {
+    pub fn new(lexicon: Option<Lexicon>) -> Japanese {
+        Japanese { lexicon }
+    }
+}
+
+impl Language for Japanese {
+    fn code(&self) -> &str {
+        "jpn"
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        match self.lexicon {
+            Some(_) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Inflect, Capability::Stem],
+            None => &[Capability::Tokenize, Capability::Stem],
+        }
+    }
+
+    fn lexicon(&self) -> Option<&Lexicon> {
+        self.lexicon.as_ref()
+    }
+
+    fn analyze_word(&self, word: &str) -> Vec<Analysis> {
+        match &self.lexicon {
+            Some(lexicon) => analyze_with_lexicon(lexicon, word),
+            None => Vec::new(),
+        }
+    }
+
+    fn inflect(&self, lemma: &str, pos: Part, features: &Features) -> Vec<String> {
+        match &self.lexicon {
+            Some(lexicon) => inflect_with_lexicon(lexicon, lemma, pos, features),
+            None => Vec::new(),
+        }
+    }
+
+    /// Stems katakana loanwords. Other words are their own stem.
+    fn stem(&self, word: &str) -> Option<String> {
+        Some(Algorithm::Katakana.stem(word))
+    }
+}
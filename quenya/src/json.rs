@@ -0,0 +1,118 @@
+// JSON serialisation of the annotation and lexicon types, available with the
+// `serde` feature.
+//
+// The representation follows the Rust types field by field, with these rules:
+//
+// - offsets are byte offsets in the UTF-8 text;
+// - parts of speech and segment kinds are lowercase strings, such as "noun";
+// - features are an object of names to values, such as {"Number": "Sing"};
+// - absent optional values are null;
+// - OMW entries carry their kind in "type": "definition", "example" or "lemma".
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+pub use serde_json::Error;
+
+/// Writes a value as compact JSON.
+pub fn to_json<T: Serialize>(value: &T) -> Result<String, Error> {
+    serde_json::to_string(value)
+}
+
+/// Writes a value as indented JSON.
+pub fn to_json_pretty<T: Serialize>(value: &T) -> Result<String, Error> {
+    serde_json::to_string_pretty(value)
+}
+
+/// Reads a value written by `to_json` or `to_json_pretty`.
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    serde_json::from_str(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wn::multi::parse::Entry;
+    use wn::pos::Part;
+    use crate::ara::aramorph::Solution;
+    use crate::features::Features;
+    use crate::lexicon::{Form, Lexeme, Lexicon, Translation};
+    use crate::omni::Omniglot;
+    use crate::pipeline::annotate_with;
+    use crate::text::{AnnotatedText, Layer, Span};
+
+    #[test]
+    fn it_round_trips_annotated_text() {
+        let mut annotated = annotate_with(&Omniglot::builtin(), "She works 9 days.");
+        annotated.add_span("entities", Span::new(0, 3, "PERSON"));
+
+        let json = to_json(&annotated).unwrap();
+
+        assert!(json.contains("\"kind\":\"number\""));
+        assert!(json.contains("\"features\":{\"Number\":\"Sing\",\"Person\":\"3\",\"Tense\":\"Pres\",\"VerbForm\":\"Fin\"}"));
+        assert_eq!(from_json::<AnnotatedText>(&json).unwrap(), annotated);
+    }
+
+    #[test]
+    fn it_round_trips_lexemes() {
+        let mut lexeme = Lexeme::new("book", Part::Noun);
+        lexeme.forms.push(Form::new("books", Features::new().with("Number", "Plur")));
+        lexeme.translations.push(Translation {
+            language: String::from("nld"),
+            word: String::from("boek"),
+        });
+
+        let json = to_json_pretty(&lexeme).unwrap();
+
+        assert!(json.contains("\"pos\": \"noun\""));
+        assert_eq!(from_json::<Lexeme>(&json).unwrap(), lexeme);
+    }
+
+    #[test]
+    fn it_round_trips_lexicons() {
+        let mut lexicon = Lexicon::new();
+        lexicon.add_form("book", Part::Noun, "books", Features::new().with("Number", "Plur"));
+        lexicon.index_forms(&|lexeme: &Lexeme| vec![Form::new(&format!("{}ish", lexeme.lemma), Features::new())]);
+
+        let json = to_json(&lexicon).unwrap();
+        let read: Lexicon = from_json(&json).unwrap();
+
+        assert!(json.contains("\"forms\":{\"book\":[{\"lexeme\":\"book:n\",\"features\":{}}]"));
+        assert_eq!(read.len(), 1);
+        assert_eq!(read.find_readings_by_form("books"), lexicon.find_readings_by_form("books"));
+        assert_eq!(read.find_readings_by_form("bookish"), lexicon.find_readings_by_form("bookish"));
+        assert_eq!(to_json(&read).unwrap(), json);
+    }
+
+    #[test]
+    fn it_restores_the_order_of_spans() {
+        let json = "{\"name\":\"entities\",\"spans\":[{\"start\":4,\"end\":9,\"label\":\"B\",\"features\":{}},{\"start\":0,\"end\":3,\"label\":\"A\",\"features\":{}}]}";
+
+        let layer: Layer = from_json(json).unwrap();
+
+        assert_eq!(layer.spans()[0].label, "A");
+        assert_eq!(layer.covering(0, 3).len(), 1);
+    }
+
+    #[test]
+    fn it_round_trips_solutions() {
+        let solution = Solution {
+            vocalization: String::from("katab"),
+            lemma: String::from("katab-u_1"),
+            pos: String::from("katab/PV"),
+            gloss: String::from("write"),
+        };
+
+        assert_eq!(from_json::<Solution>(&to_json(&solution).unwrap()).unwrap(), solution);
+    }
+
+    #[test]
+    fn it_tags_wordnet_entries() {
+        let json = "{\"type\":\"lemma\",\"language\":\"nld\",\"offset\":\"02084071\",\"pos\":\"noun\",\"lemma\":\"hond\"}";
+
+        let entry: Entry = from_json(json).unwrap();
+
+        assert_eq!(to_json(&entry).unwrap(), json);
+        assert_eq!(from_json::<Part>("\"verb\"").unwrap(), Part::Verb);
+    }
+}
@@ -0,0 +1,433 @@
+use std::fmt;
+use wn::pos::Part;
+use crate::features::Features;
+use crate::fst::Morphology;
+use crate::hunspell::Dictionary;
+use crate::lexicon::{Lexeme, Lexicon};
+use crate::morphology;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Something a language module can do with text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Capability {
+    Tokenize,
+    Analyze,
+    Lemmatize,
+    Inflect,
+    Transliterate,
+    SpellCheck,
+    Stem,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Capability::Tokenize => "tokenize",
+            Capability::Analyze => "analyze",
+            Capability::Lemmatize => "lemmatize",
+            Capability::Inflect => "inflect",
+            Capability::Transliterate => "transliterate",
+            Capability::SpellCheck => "spellcheck",
+            Capability::Stem => "stem",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// One morphological reading of a word form.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Analysis {
+    pub lemma: String,
+    pub pos: Option<Part>,
+    pub features: Features,
+    /// A language-specific tag, such as a Buckwalter part-of-speech string.
+    pub tag: Option<String>,
+    pub gloss: Option<String>,
+}
+
+impl Analysis {
+    pub fn new(lemma: &str, pos: Option<Part>, features: Features) -> Analysis {
+        Analysis {
+            lemma: String::from(lemma),
+            pos,
+            features,
+            tag: None,
+            gloss: None,
+        }
+    }
+}
+
+/// The analyses of one token of a text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenAnalysis<'a> {
+    pub token: &'a str,
+    pub analyses: Vec<Analysis>,
+}
+
+/// The common interface of the language modules. Methods for capabilities a
+/// language does not list in `capabilities` return empty results.
+pub trait Language: Send + Sync {
+    /// The ISO 639-3 code of the language.
+    fn code(&self) -> &str;
+
+    fn capabilities(&self) -> &[Capability];
+
+    fn supports(&self, capability: Capability) -> bool {
+        self.capabilities().contains(&capability)
+    }
+
+    /// The lexicon the language was loaded with, if any.
+    fn lexicon(&self) -> Option<&Lexicon> {
+        None
+    }
+
+    /// Splits text into word and punctuation tokens.
+    fn tokenize<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        tokenize_words(text)
+    }
+
+    fn analyze_word(&self, _word: &str) -> Vec<Analysis> {
+        Vec::new()
+    }
+
+    fn analyze<'a>(&self, text: &'a str) -> Vec<TokenAnalysis<'a>> {
+        self.tokenize(text)
+            .into_iter()
+            .map(|token| TokenAnalysis {
+                token,
+                analyses: self.analyze_word(token),
+            })
+            .collect()
+    }
+
+    /// The distinct lemmas of a word form.
+    fn lemmatize(&self, word: &str) -> Vec<String> {
+        let mut lemmas: Vec<String> = Vec::new();
+
+        for analysis in self.analyze_word(word) {
+            if !lemmas.contains(&analysis.lemma) {
+                lemmas.push(analysis.lemma);
+            }
+        }
+
+        lemmas
+    }
+
+    /// The forms of a lemma that realise `features`.
+    fn inflect(&self, _lemma: &str, _pos: Part, _features: &Features) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The forms of a lexeme that realise `features`: those the lexeme lists,
+    /// or else those `inflect` gives for its lemma.
+    fn inflect_lexeme(&self, lexeme: &Lexeme, features: &Features) -> Vec<String> {
+        let forms = forms_of(lexeme, features);
+
+        if !forms.is_empty() {
+            return forms;
+        }
+
+        self.inflect(&lexeme.lemma, lexeme.pos, features)
+    }
+
+    fn transliterate(&self, _text: &str) -> Option<String> {
+        None
+    }
+
+    /// The stem of a word for search indexing, which need not be a word of
+    /// the language.
+    fn stem(&self, _word: &str) -> Option<String> {
+        None
+    }
+
+    /// The spelling dictionary the language was loaded with, if any.
+    fn dictionary(&self) -> Option<&Dictionary> {
+        None
+    }
+
+    /// Whether a word is spelled correctly, or `None` without a dictionary.
+    fn check(&self, word: &str) -> Option<bool> {
+        self.dictionary().map(|dictionary| dictionary.check(word))
+    }
+
+    /// Corrections for a misspelled word, best first.
+    fn suggest(&self, word: &str) -> Vec<String> {
+        match self.dictionary() {
+            Some(dictionary) => dictionary.suggest(word),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// The forms a lexeme lists for `features`, such as irregular forms from a
+/// lexicon.
+pub fn forms_of(lexeme: &Lexeme, features: &Features) -> Vec<String> {
+    let mut forms: Vec<String> = Vec::new();
+
+    for form in &lexeme.forms {
+        if form.features.contains(features) && !forms.contains(&form.form) {
+            forms.push(form.form.clone());
+        }
+    }
+
+    forms
+}
+
+/// Splits text on whitespace and punctuation. Runs of letters, digits and
+/// combining marks become tokens, as does every other non-space character.
+pub fn tokenize_words(text: &str) -> Vec<&str> {
+    let mut tokens: Vec<&str> = Vec::new();
+    let mut start: Option<usize> = None;
+
+    for (index, c) in text.char_indices() {
+        let is_word = c.is_alphanumeric() || is_mark(c);
+
+        if let Some(word_start) = start {
+            if is_word {
+                continue;
+            }
+
+            tokens.push(&text[word_start..index]);
+            start = None;
+        }
+
+        if is_word {
+            start = Some(index);
+        } else if !c.is_whitespace() {
+            tokens.push(&text[index..index + c.len_utf8()]);
+        }
+    }
+
+    if let Some(word_start) = start {
+        tokens.push(&text[word_start..]);
+    }
+
+    tokens
+}
+
+fn is_mark(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036F}' | '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{0640}' | '\u{3099}'..='\u{309A}')
+}
+
+/// Analyses words by looking them up in the form index of a lexicon.
+pub fn analyze_with_lexicon(lexicon: &Lexicon, word: &str) -> Vec<Analysis> {
+    lexicon.find_readings_by_form(word)
+        .iter()
+        .filter_map(|reading| {
+            let lexeme = lexicon.find_lexeme_by_id(&reading.lexeme)?;
+
+            Some(Analysis::new(&lexeme.lemma, Some(lexeme.pos), reading.features.clone()))
+        })
+        .collect()
+}
+
+/// Looks up the stored forms of a lemma whose features include `features`.
+pub fn inflect_with_lexicon(lexicon: &Lexicon, lemma: &str, pos: Part, features: &Features) -> Vec<String> {
+    match lexicon.find_lexeme_by_lemma(lemma, pos) {
+        Some(lexeme) => forms_of(lexeme, features),
+        None => Vec::new(),
+    }
+}
+
+/// A language without a dedicated module. It tokenizes text and, if it has a
+/// lexicon, analyses words through the lexicon's form index. A finite-state
+/// morphology adds its analyses and generates forms, and a Hunspell
+/// dictionary checks spelling and adds the analyses it describes.
+pub struct Generic {
+    code: String,
+    lexicon: Option<Lexicon>,
+    morphology: Option<Morphology>,
+    dictionary: Option<Dictionary>,
+}
+
+impl Generic {
+    pub fn new(code: &str, lexicon: Option<Lexicon>) -> Generic {
+        Generic {
+            code: String::from(code),
+            lexicon,
+            morphology: None,
+            dictionary: None,
+        }
+    }
+
+    pub fn with_morphology(mut self, morphology: Morphology) -> Generic {
+        self.morphology = Some(morphology);
+        self
+    }
+
+    pub fn with_dictionary(mut self, dictionary: Dictionary) -> Generic {
+        self.dictionary = Some(dictionary);
+        self
+    }
+}
+
+impl Language for Generic {
+    fn code(&self) -> &str {
+        &self.code
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        let analyzes = self.lexicon.is_some() || self.morphology.is_some() || self.dictionary.is_some();
+        let inflects = self.lexicon.is_some() || self.morphology.is_some();
+
+        match (analyzes, inflects, self.dictionary.is_some()) {
+            (_, true, true) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Inflect, Capability::SpellCheck],
+            (_, true, false) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Inflect],
+            (_, false, true) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::SpellCheck],
+            (true, false, false) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize],
+            (false, false, false) => &[Capability::Tokenize],
+        }
+    }
+
+    fn lexicon(&self) -> Option<&Lexicon> {
+        self.lexicon.as_ref()
+    }
+
+    fn dictionary(&self) -> Option<&Dictionary> {
+        self.dictionary.as_ref()
+    }
+
+    fn analyze_word(&self, word: &str) -> Vec<Analysis> {
+        let mut analyses = match &self.morphology {
+            Some(morphology) => morphology::Analyzer::analyze(morphology, word),
+            None => Vec::new(),
+        };
+
+        if let Some(lexicon) = &self.lexicon {
+            for analysis in analyze_with_lexicon(lexicon, word) {
+                if !analyses.contains(&analysis) {
+                    analyses.push(analysis);
+                }
+            }
+        }
+
+        if let Some(dictionary) = &self.dictionary {
+            for analysis in dictionary.analyze(word) {
+                if !analyses.contains(&analysis) {
+                    analyses.push(analysis);
+                }
+            }
+        }
+
+        analyses
+    }
+
+    /// Inflects through the morphology, with the forms the lexicon lists
+    /// taking precedence.
+    fn inflect(&self, lemma: &str, pos: Part, features: &Features) -> Vec<String> {
+        let lexeme = self.lexicon.as_ref().and_then(|lexicon| lexicon.find_lexeme_by_lemma(lemma, pos));
+
+        match lexeme {
+            Some(lexeme) => self.inflect_lexeme(lexeme, features),
+            None => self.inflect_lexeme(&Lexeme::new(lemma, pos), features),
+        }
+    }
+
+    /// The forms the lexeme lists, or else those the morphology generates.
+    /// Without a morphology, the forms of its entry in the lexicon.
+    fn inflect_lexeme(&self, lexeme: &Lexeme, features: &Features) -> Vec<String> {
+        let forms = forms_of(lexeme, features);
+
+        if !forms.is_empty() {
+            return forms;
+        }
+
+        match (&self.morphology, &self.lexicon) {
+            (Some(morphology), _) => morphology::Inflector::inflect(morphology, lexeme, features),
+            (None, Some(lexicon)) => inflect_with_lexicon(lexicon, &lexeme.lemma, lexeme.pos, features),
+            (None, None) => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexicon::Lexeme;
+
+    #[test]
+    fn it_tokenizes_words_and_punctuation() {
+        assert_eq!(tokenize_words("Hij zoekt het, toch?"), vec!["Hij", "zoekt", "het", ",", "toch", "?"]);
+    }
+
+    #[test]
+    fn it_keeps_combining_marks_in_words() {
+        assert_eq!(tokenize_words("كَتَبَ الوَلَدُ"), vec!["كَتَبَ", "الوَلَدُ"]);
+    }
+
+    #[test]
+    fn it_analyzes_with_a_lexicon() {
+        let mut lexicon = Lexicon::new();
+        let mut lexeme = Lexeme::new("zoeken", Part::Verb);
+        lexeme.add_form("zocht", Features::new().with("Tense", "Past"));
+        lexicon.add_lexeme(lexeme);
+
+        let language = Generic::new("nld", Some(lexicon));
+        let analyses = language.analyze("Hij zocht");
+
+        assert_eq!(analyses.len(), 2);
+        assert_eq!(analyses[0].analyses.is_empty(), true);
+        assert_eq!(analyses[1].analyses[0].lemma, "zoeken");
+        assert_eq!(language.lemmatize("zocht"), vec!["zoeken"]);
+        assert_eq!(language.supports(Capability::Analyze), true);
+        assert!(language.supports(Capability::Inflect));
+    }
+
+    #[test]
+    fn it_inflects_with_a_lexicon() {
+        let mut lexicon = Lexicon::new();
+        lexicon.add_form("zoeken", Part::Verb, "zocht", Features::new().with("Number", "Sing").with("Tense", "Past"));
+        lexicon.add_form("zoeken", Part::Verb, "zochten", Features::new().with("Number", "Plur").with("Tense", "Past"));
+
+        let forms = inflect_with_lexicon(&lexicon, "zoeken", Part::Verb, &Features::new().with("Tense", "Past"));
+
+        assert_eq!(forms, vec!["zocht", "zochten"]);
+    }
+
+    #[test]
+    fn it_checks_any_language_for_round_trips() {
+        let mut lexicon = Lexicon::new();
+        lexicon.add_form("zoeken", Part::Verb, "zocht", Features::new().with("Number", "Sing").with("Tense", "Past"));
+
+        let lexeme = lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap().clone();
+        let language: Box<dyn Language> = Box::new(Generic::new("nld", Some(lexicon)));
+
+        assert_eq!(morphology::round_trip(language.as_ref(), &lexeme, &[Features::new().with("Tense", "Past")]), vec![]);
+    }
+
+    #[test]
+    fn it_inflects_lexemes_through_the_lexicon_without_a_morphology() {
+        let mut lexicon = Lexicon::new();
+        lexicon.add_form("zoeken", Part::Verb, "zocht", Features::new().with("Tense", "Past"));
+
+        let language = Generic::new("nld", Some(lexicon));
+
+        assert_eq!(morphology::Inflector::inflect(&language, &Lexeme::new("zoeken", Part::Verb), &Features::new().with("Tense", "Past")), vec!["zocht"]);
+        assert_eq!(Language::inflect(&language, "zoeken", Part::Verb, &Features::new().with("Tense", "Past")), vec!["zocht"]);
+    }
+
+    #[test]
+    fn it_only_tokenizes_without_a_lexicon() {
+        let language = Generic::new("ind", None);
+
+        assert_eq!(language.capabilities(), &[Capability::Tokenize]);
+        assert_eq!(language.analyze_word("rumah").is_empty(), true);
+        assert_eq!(language.check("rumah"), None);
+    }
+
+    #[test]
+    fn it_spell_checks_with_a_dictionary() {
+        let dictionary = Dictionary::from_strings("SET UTF-8\nTRY aeiou\nSFX N Y 1\nSFX N 0 nya . is:Poss=Yes\n", "1\nrumah/N po:noun\n").unwrap();
+        let language = Generic::new("ind", None).with_dictionary(dictionary);
+
+        assert_eq!(language.supports(Capability::SpellCheck), true);
+        assert_eq!(language.check("rumahnya"), Some(true));
+        assert_eq!(language.check("rumha"), Some(false));
+        assert_eq!(language.suggest("rumha"), vec!["rumah"]);
+        assert_eq!(language.lemmatize("rumahnya"), vec!["rumah"]);
+    }
+}
@@ -1,18 +1,186 @@
 use std::collections::HashMap;
 use wn::pos::{Part, as_char};
+use crate::features::Features;
+use crate::snapshot::{SnapshotBacking, SnapshotError};
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize, Serializer};
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
 
+/// A surface form of a lexeme together with the features it realises.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Form {
+    pub form: String,
+    pub features: Features,
+    /// Names of the sources that asserted this form.
+    pub sources: Vec<String>,
+}
+
+impl Form {
+    pub fn new(form: &str, features: Features) -> Form {
+        Form {
+            form: String::from(form),
+            features,
+            sources: Vec::new(),
+        }
+    }
+
+    /// Whether both forms have the same text and features, regardless of
+    /// their sources.
+    pub fn same_as(&self, other: &Form) -> bool {
+        self.form == other.form && self.features == other.features
+    }
+}
+
+/// A rendering of a lexeme in another language.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Translation {
+    /// ISO 639 code of the language, as given by the source.
+    pub language: String,
+    pub word: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Lexeme {
     pub lemma: String,
     pub pos: Part,
+    pub forms: Vec<Form>,
+    /// How often the lexeme was attested in the corpora it was built from.
+    pub frequency: u64,
+    pub glosses: Vec<String>,
+    /// Pronunciations in IPA.
+    pub pronunciations: Vec<String>,
+    pub etymology: Option<String>,
+    pub translations: Vec<Translation>,
+    /// Names of the sources that asserted this lexeme.
+    pub sources: Vec<String>,
+}
+
+impl Lexeme {
+    pub fn new(lemma: &str, pos: Part) -> Lexeme {
+        Lexeme {
+            lemma: String::from(lemma),
+            pos,
+            forms: Vec::new(),
+            frequency: 0,
+            glosses: Vec::new(),
+            pronunciations: Vec::new(),
+            etymology: None,
+            translations: Vec::new(),
+            sources: Vec::new(),
+        }
+    }
+
+    /// The identifier under which the lexeme is stored in a `Lexicon`.
+    pub fn id(&self) -> String {
+        lexeme_key(self.lemma.as_str(), self.pos)
+    }
+
+    pub fn add_form(&mut self, form: &str, features: Features) {
+        self.forms.push(Form::new(form, features));
+    }
+
+    /// Records `source` as having asserted this lexeme and all of its forms.
+    pub fn add_source(&mut self, source: &str) {
+        add_source(&mut self.sources, source);
+
+        for form in &mut self.forms {
+            add_source(&mut form.sources, source);
+        }
+    }
+}
+
+/// One reading of a surface form: the lexeme it belongs to and the features
+/// it realises there.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FormReading {
+    pub lexeme: String,
+    pub features: Features,
 }
 
+/// Produces the inflected forms of a lexeme, typically from a language's
+/// inflection rules. Used to fill the form index of a `Lexicon`.
+pub trait FormGenerator {
+    fn forms(&self, lexeme: &Lexeme) -> Vec<Form>;
+}
+
+impl<F> FormGenerator for F where F: Fn(&Lexeme) -> Vec<Form> {
+    fn forms(&self, lexeme: &Lexeme) -> Vec<Form> {
+        self(lexeme)
+    }
+}
+
+/// A set of lexemes with an index of their surface forms. In JSON it is an
+/// object with its `lexemes`, sorted by id, and its `forms`, an object of
+/// surface forms to their readings.
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize), serde(from = "LexiconData"))]
 pub struct Lexicon {
-    lexemes: HashMap<String, Lexeme>
+    lexemes: HashMap<String, Lexeme>,
+    forms: HashMap<String, Vec<FormReading>>,
+    /// The forms under which each lexeme is indexed, so that a lexeme can be
+    /// unindexed without scanning the whole form index.
+    indexed: HashMap<String, Vec<String>>,
+    /// The snapshot the lexicon was loaded from, while it is unchanged. The
+    /// maps above are empty until the first change reads it into them.
+    snapshot: Option<SnapshotBacking>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct LexiconRef<'a> {
+    lexemes: Vec<&'a Lexeme>,
+    forms: BTreeMap<&'a str, &'a [FormReading]>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct LexiconData {
+    lexemes: Vec<Lexeme>,
+    forms: BTreeMap<String, Vec<FormReading>>,
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Lexicon {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut lexemes: Vec<&Lexeme> = self.lexemes().collect();
+        lexemes.sort_by_cached_key(|lexeme| lexeme.id());
+
+        LexiconRef { lexemes, forms: self.forms().collect() }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<LexiconData> for Lexicon {
+    fn from(data: LexiconData) -> Lexicon {
+        let mut lexicon = Lexicon::new();
+
+        for lexeme in data.lexemes {
+            lexicon.add_lexeme(lexeme);
+        }
+
+        for (form, readings) in data.forms {
+            for reading in readings {
+                lexicon.index_form(&form, &reading.lexeme, reading.features);
+            }
+        }
+
+        lexicon
+    }
+}
+
+pub(crate) fn add_source(sources: &mut Vec<String>, source: &str) {
+    if !sources.iter().any(|existing| existing == source) {
+        sources.push(String::from(source));
+    }
 }
 
-fn lexeme_key(lemma: &str, pos: Part) -> String {
+pub(crate) fn lexeme_key(lemma: &str, pos: Part) -> String {
     format!("{}:{}", lemma, as_char(pos))
 }
 
@@ -20,21 +188,226 @@ impl Lexicon {
     pub fn new() -> Lexicon {
         Lexicon {
             lexemes: HashMap::new(),
+            forms: HashMap::new(),
+            indexed: HashMap::new(),
+            snapshot: None,
+        }
+    }
+
+    /// A lexicon that reads its lexemes and form index from a snapshot, such
+    /// as one written by `snapshot::write_snapshot`, as they are looked up.
+    /// Nothing is rebuilt until the lexicon is first changed.
+    pub fn from_snapshot(bytes: Vec<u8>) -> Result<Lexicon, SnapshotError> {
+        Ok(Lexicon::from_backing(SnapshotBacking::from_bytes(bytes)?))
+    }
+
+    pub(crate) fn from_backing(snapshot: SnapshotBacking) -> Lexicon {
+        Lexicon {
+            snapshot: Some(snapshot),
+            ..Lexicon::new()
         }
     }
 
+    /// Adds a lexeme and indexes its lemma and explicitly stored forms.
+    /// A lexeme with the same lemma and part of speech is replaced.
     pub fn add_lexeme(&mut self, lexeme: Lexeme) {
-        let key = lexeme_key(lexeme.lemma.as_str(), lexeme.pos);
+        self.unmap();
+
+        let key = lexeme.id();
+
+        if self.lexemes.contains_key(&key) {
+            self.unindex(&key);
+        }
+
+        if !lexeme.forms.iter().any(|form| form.form == lexeme.lemma) {
+            self.index_form(&lexeme.lemma, &key, Features::new());
+        }
+
+        for form in &lexeme.forms {
+            self.index_form(&form.form, &key, form.features.clone());
+        }
 
         self.lexemes.insert(key, lexeme);
     }
 
-    pub fn find_lexeme_by_lemma(&self, lemma: &str, pos: Part) -> Result<&Lexeme, ()> {
+    /// Adds a form to the lexeme with the given lemma and part of speech,
+    /// creating the lexeme if it does not exist yet.
+    pub fn add_form(&mut self, lemma: &str, pos: Part, form: &str, features: Features) {
+        self.unmap();
+
         let key = lexeme_key(lemma, pos);
 
-        match self.lexemes.get(&key) {
-            Some(lexeme) => Ok(lexeme),
-            None => Err(())
+        if !self.lexemes.contains_key(&key) {
+            self.add_lexeme(Lexeme::new(lemma, pos));
+        }
+
+        let lexeme = self.lexemes.get_mut(&key).unwrap();
+        let new_form = Form::new(form, features);
+
+        if lexeme.forms.iter().any(|existing| existing.same_as(&new_form)) {
+            return;
+        }
+
+        lexeme.forms.push(new_form.clone());
+
+        if new_form.form == lexeme.lemma && !new_form.features.is_empty() {
+            // The lemma now has a proper reading, so its bare citation reading can go.
+            if let Some(readings) = self.forms.get_mut(form) {
+                readings.retain(|reading| reading.lexeme != key || !reading.features.is_empty());
+            }
+        }
+
+        self.index_form(&new_form.form, &key, new_form.features);
+    }
+
+    /// Records `source` as having asserted every lexeme and form, typically
+    /// right after importing them.
+    pub fn add_source(&mut self, source: &str) {
+        self.unmap();
+
+        for lexeme in self.lexemes.values_mut() {
+            lexeme.add_source(source);
+        }
+    }
+
+    /// Removes a lexeme and all of its forms from the index.
+    pub fn remove_lexeme(&mut self, lemma: &str, pos: Part) -> Option<Lexeme> {
+        self.unmap();
+
+        let key = lexeme_key(lemma, pos);
+        let lexeme = self.lexemes.remove(&key)?;
+
+        self.unindex(&key);
+
+        Some(lexeme)
+    }
+
+    /// The lexeme with the given id, for changes that leave its forms as
+    /// they are or index the new ones through `index_form`.
+    pub(crate) fn lexeme_mut(&mut self, id: &str) -> Option<&mut Lexeme> {
+        self.unmap();
+
+        self.lexemes.get_mut(id)
+    }
+
+    /// Counts one more corpus occurrence of a lexeme. Unknown lexemes are ignored.
+    pub fn increment_frequency(&mut self, lemma: &str, pos: Part) {
+        self.unmap();
+
+        if let Some(lexeme) = self.lexemes.get_mut(&lexeme_key(lemma, pos)) {
+            lexeme.frequency += 1;
+        }
+    }
+
+    pub fn find_lexeme_by_lemma(&self, lemma: &str, pos: Part) -> Option<&Lexeme> {
+        self.find_lexeme_by_id(&lexeme_key(lemma, pos))
+    }
+
+    pub fn find_lexeme_by_id(&self, id: &str) -> Option<&Lexeme> {
+        match &self.snapshot {
+            Some(snapshot) => snapshot.find_lexeme_by_id(id),
+            None => self.lexemes.get(id),
+        }
+    }
+
+    /// Returns every reading of a surface form, regular or irregular.
+    pub fn find_readings_by_form(&self, form: &str) -> &[FormReading] {
+        if let Some(snapshot) = &self.snapshot {
+            return snapshot.find_readings_by_form(form);
+        }
+
+        match self.forms.get(form) {
+            Some(readings) => readings.as_slice(),
+            None => &[],
+        }
+    }
+
+    /// Adds the forms produced by `generator` for every lexeme to the form
+    /// index. The generated forms are not stored on the lexemes themselves.
+    pub fn index_forms(&mut self, generator: &dyn FormGenerator) {
+        self.unmap();
+
+        let mut generated: Vec<(String, String, Features)> = Vec::new();
+
+        for (key, lexeme) in &self.lexemes {
+            for form in generator.forms(lexeme) {
+                generated.push((form.form, key.clone(), form.features));
+            }
+        }
+
+        for (form, key, features) in generated {
+            self.index_form(&form, &key, features);
+        }
+    }
+
+    pub fn lexemes(&self) -> impl Iterator<Item = &Lexeme> {
+        self.lexemes.values().chain(self.snapshot.iter().flat_map(SnapshotBacking::lexemes))
+    }
+
+    /// Consumes the lexicon, returning its lexemes.
+    pub fn into_lexemes(mut self) -> impl Iterator<Item = Lexeme> {
+        self.unmap();
+
+        self.lexemes.into_values()
+    }
+
+    /// Iterates over the form index.
+    pub fn forms(&self) -> impl Iterator<Item = (&str, &[FormReading])> {
+        self.forms.iter()
+            .map(|(form, readings)| (form.as_str(), readings.as_slice()))
+            .chain(self.snapshot.iter().flat_map(SnapshotBacking::forms))
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.snapshot {
+            Some(snapshot) => snapshot.len(),
+            None => self.lexemes.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads a snapshot-backed lexicon into the maps, before it is changed.
+    fn unmap(&mut self) {
+        if let Some(snapshot) = self.snapshot.take() {
+            *self = snapshot.snapshot().to_lexicon();
+        }
+    }
+
+    pub(crate) fn index_form(&mut self, form: &str, key: &str, features: Features) {
+        self.unmap();
+
+        let reading = FormReading {
+            lexeme: String::from(key),
+            features,
+        };
+
+        let readings = self.forms.entry(String::from(form)).or_default();
+
+        if readings.contains(&reading) {
+            return;
+        }
+
+        readings.push(reading);
+
+        let forms = self.indexed.entry(String::from(key)).or_default();
+
+        if !forms.iter().any(|existing| existing == form) {
+            forms.push(String::from(form));
+        }
+    }
+
+    fn unindex(&mut self, key: &str) {
+        for form in self.indexed.remove(key).unwrap_or_default() {
+            if let Some(readings) = self.forms.get_mut(&form) {
+                readings.retain(|reading| reading.lexeme != key);
+
+                if readings.is_empty() {
+                    self.forms.remove(&form);
+                }
+            }
         }
     }
 }
@@ -47,20 +420,112 @@ mod tests {
     fn it_returns_err_when_lemma_is_not_found() {
         let lexicon = Lexicon::new();
 
-        assert_eq!(lexicon.find_lexeme_by_lemma("search", Part::Verb).is_err(), true);
+        assert!(lexicon.find_lexeme_by_lemma("search", Part::Verb).is_none());
     }
 
     #[test]
     fn it_returns_lexemes_that_were_added() {
         let mut lexicon = Lexicon::new();
 
-        let lexeme = Lexeme {
-            lemma: String::from("search"),
-            pos: Part::Verb,
-        };
+        let lexeme = Lexeme::new("search", Part::Verb);
 
         lexicon.add_lexeme(lexeme);
 
         assert_eq!(lexicon.find_lexeme_by_lemma("search", Part::Verb).unwrap().lemma, String::from("search"));
     }
+
+    #[test]
+    fn it_finds_explicitly_stored_forms() {
+        let mut lexicon = Lexicon::new();
+
+        let mut lexeme = Lexeme::new("go", Part::Verb);
+        lexeme.add_form("went", Features::new().with("Tense", "Past").with("VerbForm", "Fin"));
+
+        lexicon.add_lexeme(lexeme);
+
+        let readings = lexicon.find_readings_by_form("went");
+
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].lexeme, "go:v");
+        assert_eq!(readings[0].features.get("Tense"), Some("Past"));
+    }
+
+    #[test]
+    fn it_adds_forms_to_existing_lexemes() {
+        let mut lexicon = Lexicon::new();
+
+        lexicon.add_form("go", Part::Verb, "went", Features::new().with("Tense", "Past"));
+        lexicon.add_form("go", Part::Verb, "goes", Features::new().with("Person", "3"));
+        lexicon.add_form("go", Part::Verb, "goes", Features::new().with("Person", "3"));
+
+        assert_eq!(lexicon.find_lexeme_by_lemma("go", Part::Verb).unwrap().forms.len(), 2);
+        assert_eq!(lexicon.find_readings_by_form("goes").len(), 1);
+    }
+
+    #[test]
+    fn it_finds_the_lemma_as_a_form() {
+        let mut lexicon = Lexicon::new();
+
+        lexicon.add_lexeme(Lexeme::new("search", Part::Verb));
+        lexicon.add_lexeme(Lexeme::new("search", Part::Noun));
+
+        assert_eq!(lexicon.find_readings_by_form("search").len(), 2);
+    }
+
+    #[test]
+    fn it_finds_generated_forms() {
+        let mut lexicon = Lexicon::new();
+
+        lexicon.add_lexeme(Lexeme::new("walk", Part::Verb));
+
+        lexicon.index_forms(&|lexeme: &Lexeme| vec![Form::new(&format!("{}ed", lexeme.lemma), Features::new().with("Tense", "Past"))]);
+
+        assert_eq!(lexicon.find_readings_by_form("walked")[0].lexeme, "walk:v");
+        assert_eq!(lexicon.find_readings_by_form("walks").is_empty(), true);
+    }
+
+    #[test]
+    fn it_unindexes_removed_lexemes() {
+        let mut lexicon = Lexicon::new();
+
+        lexicon.add_form("go", Part::Verb, "went", Features::new().with("Tense", "Past"));
+
+        assert_eq!(lexicon.remove_lexeme("go", Part::Verb).is_some(), true);
+        assert_eq!(lexicon.find_readings_by_form("went").is_empty(), true);
+        assert_eq!(lexicon.remove_lexeme("go", Part::Verb).is_none(), true);
+    }
+
+    #[test]
+    fn it_unindexes_replaced_lexemes() {
+        let mut lexicon = Lexicon::new();
+
+        let mut lexeme = Lexeme::new("go", Part::Verb);
+        lexeme.add_form("goed", Features::new().with("Tense", "Past"));
+        lexicon.add_lexeme(lexeme);
+
+        let mut lexeme = Lexeme::new("go", Part::Verb);
+        lexeme.add_form("went", Features::new().with("Tense", "Past"));
+        lexicon.add_lexeme(lexeme);
+
+        assert_eq!(lexicon.find_readings_by_form("goed").is_empty(), true);
+        assert_eq!(lexicon.find_readings_by_form("went").len(), 1);
+    }
+
+    #[test]
+    fn it_keeps_shared_forms_of_other_lexemes() {
+        let mut lexicon = Lexicon::new();
+
+        let mut lexeme = Lexeme::new("saw", Part::Noun);
+        lexeme.add_form("saws", Features::new().with("Number", "Plur"));
+        lexicon.add_lexeme(lexeme);
+
+        let mut lexeme = Lexeme::new("see", Part::Verb);
+        lexeme.add_form("saw", Features::new().with("Tense", "Past"));
+        lexicon.add_lexeme(lexeme);
+
+        lexicon.remove_lexeme("see", Part::Verb);
+
+        assert_eq!(lexicon.find_readings_by_form("saw").len(), 1);
+        assert_eq!(lexicon.find_readings_by_form("saws").len(), 1);
+    }
 }
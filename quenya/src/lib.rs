@@ -1,4 +1,25 @@
-mod lexicon;
+pub mod conllu;
+pub mod evaluate;
+pub mod features;
+pub mod fst;
+pub mod hunspell;
+pub mod identify;
+pub mod import;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod language;
+pub mod lexicon;
+pub mod merge;
+pub mod morphology;
+pub mod normalize;
+pub mod paradigm;
+pub mod pattern;
+pub mod pipeline;
+pub mod realize;
+pub mod segmentation;
+pub mod sentences;
+pub mod snapshot;
+pub mod stem;
 pub mod ara;
 pub mod eng;
 pub mod jpn;
@@ -6,10 +27,45 @@ pub mod nld;
 pub mod uxy;
 pub mod text;
 
-mod omni;
+pub mod omni;
 
-pub fn annotate(text: &str) -> &str {
-    text
+use std::sync::OnceLock;
+use crate::omni::{OmniError, Omniglot};
+use crate::pipeline::annotate_with;
+use crate::text::AnnotatedText;
+
+static OMNIGLOT: OnceLock<Omniglot> = OnceLock::new();
+
+/// Annotates text with the languages configured through `QUENYA_CONFIG` or
+/// `QUENYA_DATA`, or with the built-in languages if neither is set.
+///
+/// Panics if the configuration cannot be read; `try_annotate` returns the
+/// error instead.
+pub fn annotate(text: &str) -> AnnotatedText {
+    match try_annotate(text) {
+        Ok(annotated) => annotated,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+/// Annotates text like `annotate`, but returns the error of a configuration
+/// that is set and cannot be read rather than falling back on the built-in
+/// languages.
+pub fn try_annotate(text: &str) -> Result<AnnotatedText, OmniError> {
+    let omni = match OMNIGLOT.get() {
+        Some(omni) => omni,
+        None => {
+            let omni = match Omniglot::from_env() {
+                Ok(omni) => omni,
+                Err(OmniError::NotConfigured) => Omniglot::builtin(),
+                Err(error) => return Err(error),
+            };
+
+            OMNIGLOT.get_or_init(|| omni)
+        }
+    };
+
+    Ok(annotate_with(omni, text))
 }
 
 #[cfg(test)]
@@ -0,0 +1,384 @@
+// Combining lexicons from several sources, and reviewing the differences
+// between two versions of a lexicon before shipping them.
+
+use std::collections::HashSet;
+use std::fmt;
+use crate::lexicon::{Form, FormReading, Lexeme, Lexicon, add_source};
+
+/// How `Lexicon::merge` resolves a lexeme that both lexicons contain. Under
+/// every policy the incoming sources are recorded on the lexeme.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictPolicy {
+    /// Keep the data of the existing lexeme.
+    KeepExisting,
+    /// Replace the data of the existing lexeme with that of the incoming one.
+    PreferIncoming,
+    /// Combine both lexemes. Forms, glosses, pronunciations and translations
+    /// are united, frequencies are added up and an existing etymology wins.
+    Union,
+}
+
+/// The ids of the lexemes that a merge added or combined with existing ones.
+#[derive(Debug, Default, PartialEq)]
+pub struct MergeReport {
+    pub added: Vec<String>,
+    pub merged: Vec<String>,
+}
+
+impl Lexicon {
+    /// Merges `other` into this lexicon. Readings that `other` generated for
+    /// its form index are kept for every lexeme whose incoming data is used.
+    pub fn merge(&mut self, other: Lexicon, policy: ConflictPolicy) -> MergeReport {
+        let mut report = MergeReport::default();
+        let mut added: HashSet<String> = HashSet::new();
+
+        let readings: Vec<(String, FormReading)> = other.forms()
+            .flat_map(|(form, readings)| readings.iter().map(move |reading| (String::from(form), reading.clone())))
+            .collect();
+
+        for incoming in other.into_lexemes() {
+            let id = incoming.id();
+
+            if self.find_lexeme_by_id(&id).is_none() {
+                self.add_lexeme(incoming);
+                added.insert(id);
+                continue;
+            }
+
+            if policy == ConflictPolicy::PreferIncoming {
+                // Every form of the existing lexeme is replaced, so it is indexed afresh.
+                let mut kept = incoming;
+
+                if let Some(existing) = self.remove_lexeme(&kept.lemma, kept.pos) {
+                    merge_lexemes(&mut kept, existing, policy);
+                }
+
+                self.add_lexeme(kept);
+            } else if let Some(existing) = self.lexeme_mut(&id) {
+                for form in merge_lexemes(existing, incoming, policy) {
+                    self.index_form(&form.form, &id, form.features);
+                }
+            }
+
+            report.merged.push(id);
+        }
+
+        for (form, reading) in readings {
+            if policy != ConflictPolicy::KeepExisting || added.contains(&reading.lexeme) {
+                self.index_form(&form, &reading.lexeme, reading.features);
+            }
+        }
+
+        report.added = added.into_iter().collect();
+        report.added.sort();
+        report.merged.sort();
+
+        report
+    }
+}
+
+/// Merges `other` into `kept`, which is the lexeme whose data the policy
+/// keeps. Returns the forms that `kept` gained.
+fn merge_lexemes(kept: &mut Lexeme, other: Lexeme, policy: ConflictPolicy) -> Vec<Form> {
+    let mut gained: Vec<Form> = Vec::new();
+
+    for source in &other.sources {
+        add_source(&mut kept.sources, source);
+    }
+
+    match policy {
+        ConflictPolicy::KeepExisting | ConflictPolicy::PreferIncoming => {
+            for form in &other.forms {
+                if let Some(kept_form) = kept.forms.iter_mut().find(|kept_form| kept_form.same_as(form)) {
+                    for source in &form.sources {
+                        add_source(&mut kept_form.sources, source);
+                    }
+                }
+            }
+        }
+        ConflictPolicy::Union => {
+            for form in other.forms {
+                match kept.forms.iter_mut().find(|kept_form| kept_form.same_as(&form)) {
+                    Some(kept_form) => {
+                        for source in &form.sources {
+                            add_source(&mut kept_form.sources, source);
+                        }
+                    }
+                    None => {
+                        gained.push(form.clone());
+                        kept.forms.push(form);
+                    }
+                }
+            }
+
+            for gloss in other.glosses {
+                if !kept.glosses.contains(&gloss) {
+                    kept.glosses.push(gloss);
+                }
+            }
+
+            for pronunciation in other.pronunciations {
+                if !kept.pronunciations.contains(&pronunciation) {
+                    kept.pronunciations.push(pronunciation);
+                }
+            }
+
+            for translation in other.translations {
+                if !kept.translations.contains(&translation) {
+                    kept.translations.push(translation);
+                }
+            }
+
+            if kept.etymology.is_none() {
+                kept.etymology = other.etymology;
+            }
+
+            kept.frequency += other.frequency;
+        }
+    }
+
+    gained
+}
+
+/// How a lexeme differs between two versions of a lexicon.
+#[derive(Debug, PartialEq)]
+pub struct LexemeChange {
+    pub id: String,
+    pub added_forms: Vec<Form>,
+    pub removed_forms: Vec<Form>,
+    /// Names of the other fields that differ, such as `glosses`.
+    pub changed_fields: Vec<&'static str>,
+}
+
+/// The lexemes that were added, removed or changed between two lexicons.
+#[derive(Debug, Default, PartialEq)]
+pub struct LexiconDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<LexemeChange>,
+}
+
+impl LexiconDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for LexiconDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for id in &self.added {
+            writeln!(f, "+ {}", id)?;
+        }
+
+        for id in &self.removed {
+            writeln!(f, "- {}", id)?;
+        }
+
+        for change in &self.changed {
+            writeln!(f, "~ {}", change.id)?;
+
+            for form in &change.added_forms {
+                writeln!(f, "    + {} {}", form.form, form.features)?;
+            }
+
+            for form in &change.removed_forms {
+                writeln!(f, "    - {} {}", form.form, form.features)?;
+            }
+
+            for field in &change.changed_fields {
+                writeln!(f, "    {} changed", field)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares two versions of a lexicon. All lists are sorted by lexeme id.
+pub fn diff(old: &Lexicon, new: &Lexicon) -> LexiconDiff {
+    let mut diff = LexiconDiff::default();
+
+    for lexeme in old.lexemes() {
+        if new.find_lexeme_by_id(&lexeme.id()).is_none() {
+            diff.removed.push(lexeme.id());
+        }
+    }
+
+    for lexeme in new.lexemes() {
+        match old.find_lexeme_by_id(&lexeme.id()) {
+            Some(previous) => {
+                if let Some(change) = compare(previous, lexeme) {
+                    diff.changed.push(change);
+                }
+            }
+            None => diff.added.push(lexeme.id()),
+        }
+    }
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort_by(|a, b| a.id.cmp(&b.id));
+
+    diff
+}
+
+fn compare(old: &Lexeme, new: &Lexeme) -> Option<LexemeChange> {
+    let added_forms: Vec<Form> = new.forms.iter()
+        .filter(|form| !old.forms.iter().any(|previous| previous.same_as(form)))
+        .cloned()
+        .collect();
+
+    let removed_forms: Vec<Form> = old.forms.iter()
+        .filter(|form| !new.forms.iter().any(|current| current.same_as(form)))
+        .cloned()
+        .collect();
+
+    let mut changed_fields = Vec::new();
+
+    if old.frequency != new.frequency {
+        changed_fields.push("frequency");
+    }
+
+    if old.glosses != new.glosses {
+        changed_fields.push("glosses");
+    }
+
+    if old.pronunciations != new.pronunciations {
+        changed_fields.push("pronunciations");
+    }
+
+    if old.etymology != new.etymology {
+        changed_fields.push("etymology");
+    }
+
+    if old.translations != new.translations {
+        changed_fields.push("translations");
+    }
+
+    if old.sources != new.sources {
+        changed_fields.push("sources");
+    }
+
+    let form_sources_changed = new.forms.iter().any(|form| {
+        old.forms.iter().any(|previous| previous.same_as(form) && previous.sources != form.sources)
+    });
+
+    if form_sources_changed {
+        changed_fields.push("form sources");
+    }
+
+    if added_forms.is_empty() && removed_forms.is_empty() && changed_fields.is_empty() {
+        return None;
+    }
+
+    Some(LexemeChange {
+        id: new.id(),
+        added_forms,
+        removed_forms,
+        changed_fields,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wn::pos::Part;
+    use crate::features::Features;
+
+    fn omw() -> Lexicon {
+        let mut lexicon = Lexicon::new();
+
+        lexicon.add_lexeme(Lexeme::new("zoeken", Part::Verb));
+        lexicon.add_lexeme(Lexeme::new("boek", Part::Noun));
+        lexicon.add_source("omw");
+
+        lexicon
+    }
+
+    fn treebank() -> Lexicon {
+        let mut lexicon = Lexicon::new();
+
+        lexicon.add_form("zoeken", Part::Verb, "zocht", Features::new().with("Tense", "Past"));
+        lexicon.add_form("lopen", Part::Verb, "liep", Features::new().with("Tense", "Past"));
+        lexicon.increment_frequency("zoeken", Part::Verb);
+        lexicon.add_source("treebank");
+
+        lexicon
+    }
+
+    #[test]
+    fn it_records_provenance_when_merging() {
+        let mut lexicon = omw();
+
+        let report = lexicon.merge(treebank(), ConflictPolicy::Union);
+
+        let zoeken = lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap();
+
+        assert_eq!(report.added, vec!["lopen:v"]);
+        assert_eq!(report.merged, vec!["zoeken:v"]);
+        assert_eq!(zoeken.sources, vec!["omw", "treebank"]);
+        assert_eq!(zoeken.forms[0].sources, vec!["treebank"]);
+        assert_eq!(zoeken.frequency, 1);
+        assert_eq!(lexicon.find_readings_by_form("zocht").len(), 1);
+    }
+
+    #[test]
+    fn it_keeps_existing_data() {
+        let mut lexicon = omw();
+
+        lexicon.merge(treebank(), ConflictPolicy::KeepExisting);
+
+        let zoeken = lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap();
+
+        assert!(zoeken.forms.is_empty());
+        assert_eq!(zoeken.sources, vec!["omw", "treebank"]);
+        assert!(lexicon.find_readings_by_form("zocht").is_empty());
+    }
+
+    #[test]
+    fn it_keeps_generated_readings_of_existing_lexemes() {
+        for policy in &[ConflictPolicy::KeepExisting, ConflictPolicy::Union] {
+            let mut lexicon = omw();
+            let generator = |lexeme: &Lexeme| vec![Form::new(&format!("{}s", lexeme.lemma), Features::new())];
+
+            lexicon.index_forms(&generator);
+            lexicon.merge(treebank(), *policy);
+
+            assert_eq!(lexicon.find_readings_by_form("zoekens").len(), 1);
+        }
+    }
+
+    #[test]
+    fn it_prefers_incoming_data() {
+        let mut lexicon = treebank();
+
+        lexicon.merge(omw(), ConflictPolicy::PreferIncoming);
+
+        let zoeken = lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap();
+
+        assert!(zoeken.forms.is_empty());
+        assert_eq!(zoeken.frequency, 0);
+        assert_eq!(zoeken.sources, vec!["omw", "treebank"]);
+    }
+
+    #[test]
+    fn it_diffs_lexicons() {
+        let mut merged = omw();
+        merged.merge(treebank(), ConflictPolicy::Union);
+        merged.remove_lexeme("boek", Part::Noun);
+
+        let diff = diff(&omw(), &merged);
+
+        assert_eq!(diff.added, vec!["lopen:v"]);
+        assert_eq!(diff.removed, vec!["boek:n"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].added_forms[0].form, "zocht");
+        assert_eq!(diff.changed[0].changed_fields, vec!["frequency", "sources"]);
+        assert_eq!(diff.to_string(), "+ lopen:v\n- boek:n\n~ zoeken:v\n    + zocht Tense=Past\n    frequency changed\n    sources changed\n");
+    }
+
+    #[test]
+    fn it_finds_no_differences_between_equal_lexicons() {
+        assert!(diff(&omw(), &omw()).is_empty());
+    }
+}
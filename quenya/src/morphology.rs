@@ -0,0 +1,83 @@
+// The contract between generation and analysis. Every language implements
+// both traits through `Language`, and `round_trip` checks in its tests that
+// they agree.
+
+use crate::features::Features;
+use crate::language::{Analysis, Language};
+use crate::lexicon::Lexeme;
+
+/// Generates the forms of a lexeme. Together with `Analyzer` it is the
+/// contract of a language's morphology: the forms generated for features are
+/// analysed as the lexeme with those features, and the other way around.
+pub trait Inflector {
+    /// The forms of a lexeme that realise `features`.
+    fn inflect(&self, lexeme: &Lexeme, features: &Features) -> Vec<String>;
+}
+
+/// Analyses a word form into lemmas, parts of speech and features.
+pub trait Analyzer {
+    fn analyze(&self, form: &str) -> Vec<Analysis>;
+}
+
+impl<L: Language + ?Sized> Inflector for L {
+    fn inflect(&self, lexeme: &Lexeme, features: &Features) -> Vec<String> {
+        self.inflect_lexeme(lexeme, features)
+    }
+}
+
+impl<L: Language + ?Sized> Analyzer for L {
+    fn analyze(&self, form: &str) -> Vec<Analysis> {
+        self.analyze_word(form)
+    }
+}
+
+/// A way in which generation and analysis disagree, found by `round_trip`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mismatch {
+    /// Nothing was generated for the features.
+    NoForm(Features),
+    /// A generated form has no analysis as the lexeme with the features.
+    NotAnalyzed { form: String, features: Features },
+    /// An analysis of a generated form as the lexeme does not generate it.
+    NotGenerated { form: String, analysis: Analysis },
+}
+
+/// Inflects a lexeme for every bundle of features and analyses the forms,
+/// returning where generation and analysis disagree. Language modules use it
+/// in their tests as `assert_eq!(round_trip(...), vec![])`.
+pub fn round_trip<L: Inflector + Analyzer + ?Sized>(language: &L, lexeme: &Lexeme, features: &[Features]) -> Vec<Mismatch> {
+    let mut mismatches: Vec<Mismatch> = Vec::new();
+
+    for bundle in features {
+        let forms = language.inflect(lexeme, bundle);
+
+        if forms.is_empty() {
+            mismatches.push(Mismatch::NoForm(bundle.clone()));
+        }
+
+        for form in forms {
+            let analyses: Vec<Analysis> = language.analyze(&form)
+                .into_iter()
+                .filter(|analysis| analysis.lemma == lexeme.lemma && analysis.pos == Some(lexeme.pos))
+                .collect();
+
+            if !analyses.iter().any(|analysis| analysis.features.contains(bundle)) {
+                mismatches.push(Mismatch::NotAnalyzed {
+                    form: form.clone(),
+                    features: bundle.clone(),
+                });
+            }
+
+            for analysis in analyses {
+                if !language.inflect(lexeme, &analysis.features).contains(&form) {
+                    mismatches.push(Mismatch::NotGenerated {
+                        form: form.clone(),
+                        analysis,
+                    });
+                }
+            }
+        }
+    }
+
+    mismatches
+}
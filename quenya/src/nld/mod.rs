@@ -1,28 +1,127 @@
-use crate::lexicon::{Lexicon, Lexeme};
+use crate::features::Features;
+use crate::hunspell::Dictionary;
+use crate::import::ImportError;
+use crate::import::wordnet::import_multilingual_wordnet;
+use crate::language::{Analysis, Capability, Language, analyze_with_lexicon, inflect_with_lexicon};
+use crate::stem::Algorithm;
+use crate::lexicon::Lexicon;
 use std::path::Path;
-use wn::multi::parse::{parse_multilingual_wordnet_file, Entry, Lemma};
 use std::fs::File;
+use wn::pos::Part;
 
-pub fn load_lexicon() -> Lexicon {
-    let path = Path::new("C:\\Users\\Dennis\\Dev\\Quenya\\dict\\nld\\wn-data-nld.tab");
+/// Loads the Dutch lexicon from an OMW `wn-data-nld.tab` file.
+pub fn load_lexicon(path: &Path) -> Result<Lexicon, ImportError> {
+    let mut lexicon = Lexicon::new();
 
-    let entries = parse_multilingual_wordnet_file(File::open(&path).unwrap());
+    import_multilingual_wordnet(File::open(path)?, &mut lexicon)?;
 
-    let mut lexicon = Lexicon::new();
+    Ok(lexicon)
+}
+
+/// Dutch, analysed and inflected through its lexicon. A Hunspell dictionary
+/// checks spelling and analyses the words the lexicon lacks, such as
+/// compounds.
+pub struct Dutch {
+    lexicon: Lexicon,
+    dictionary: Option<Dictionary>,
+}
+
+impl Dutch {
+    pub fn new(lexicon: Lexicon) -> Dutch {
+        Dutch { lexicon, dictionary: None }
+    }
+
+    pub fn with_dictionary(mut self, dictionary: Dictionary) -> Dutch {
+        self.dictionary = Some(dictionary);
+        self
+    }
+}
+
+impl Language for Dutch {
+    fn code(&self) -> &str {
+        "nld"
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        match self.dictionary {
+            Some(_) => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Inflect, Capability::SpellCheck, Capability::Stem],
+            None => &[Capability::Tokenize, Capability::Analyze, Capability::Lemmatize, Capability::Inflect, Capability::Stem],
+        }
+    }
+
+    fn lexicon(&self) -> Option<&Lexicon> {
+        Some(&self.lexicon)
+    }
+
+    fn dictionary(&self) -> Option<&Dictionary> {
+        self.dictionary.as_ref()
+    }
+
+    fn analyze_word(&self, word: &str) -> Vec<Analysis> {
+        let mut analyses = analyze_with_lexicon(&self.lexicon, word);
+
+        // Sentence-initial words are capitalised.
+        let lowercase = word.to_lowercase();
 
-    for entry in entries {
-        match entry {
-            Entry::Lemma(lemma) => {
-                let lexeme = Lexeme {
-                    lemma: lemma.lemma,
-                    pos: lemma.pos,
-                };
-
-                lexicon.add_lexeme(lexeme);
-            }
-            _ => {}
+        if analyses.is_empty() && lowercase != word {
+            analyses = analyze_with_lexicon(&self.lexicon, &lowercase);
         }
+
+        if let (true, Some(dictionary)) = (analyses.is_empty(), &self.dictionary) {
+            analyses = dictionary.analyze(word);
+        }
+
+        analyses
     }
 
-    lexicon
+    fn inflect(&self, lemma: &str, pos: Part, features: &Features) -> Vec<String> {
+        inflect_with_lexicon(&self.lexicon, lemma, pos, features)
+    }
+
+    fn stem(&self, word: &str) -> Option<String> {
+        Some(Algorithm::Dutch.stem(word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morphology::{Mismatch, round_trip};
+
+    #[test]
+    fn it_lemmatizes_capitalised_words() {
+        let mut lexicon = Lexicon::new();
+        lexicon.add_form("zoeken", Part::Verb, "zocht", Features::new().with("Tense", "Past"));
+
+        let dutch = Dutch::new(lexicon);
+
+        assert_eq!(dutch.lemmatize("Zocht"), vec!["zoeken"]);
+    }
+
+    #[test]
+    fn it_agrees_with_its_lexicon() {
+        let mut lexicon = Lexicon::new();
+        lexicon.add_form("zoeken", Part::Verb, "zocht", Features::parse("Number=Sing|Tense=Past").unwrap());
+        lexicon.add_form("zoeken", Part::Verb, "zochten", Features::parse("Number=Plur|Tense=Past").unwrap());
+
+        let dutch = Dutch::new(lexicon);
+        let lexeme = dutch.lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap().clone();
+
+        let features = vec![Features::parse("Tense=Past").unwrap(), Features::parse("Number=Plur").unwrap()];
+
+        assert_eq!(round_trip(&dutch, &lexeme, &features), vec![]);
+        assert_eq!(round_trip(&dutch, &lexeme, &[Features::parse("Tense=Pres").unwrap()]), vec![Mismatch::NoForm(Features::parse("Tense=Pres").unwrap())]);
+    }
+
+    #[test]
+    fn it_analyzes_compounds_through_a_dictionary() {
+        let aff = "SET UTF-8\nCOMPOUNDFLAG Z\nSFX E Y 1\nSFX E 0 en . is:Number=Plur\n";
+        let dic = "2\nboek/EZ po:noun\nkast/EZ po:noun\n";
+
+        let dutch = Dutch::new(Lexicon::new()).with_dictionary(Dictionary::from_strings(aff, dic).unwrap());
+
+        assert_eq!(dutch.supports(Capability::SpellCheck), true);
+        assert_eq!(dutch.lemmatize("boekkasten"), vec!["boekkast"]);
+        assert_eq!(dutch.check("boekkastten"), Some(false));
+    }
 }
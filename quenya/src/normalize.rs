@@ -0,0 +1,310 @@
+// Orthographic preprocessing. Text is normalised one grapheme cluster at a
+// time, so that every stretch of normalised text can be traced back to the
+// characters the user wrote.
+
+use std::borrow::Cow;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::omni::Omniglot;
+use crate::pipeline::Stage;
+use crate::text::AnnotatedText;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A Unicode normalisation form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalForm {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+/// The steps of a normalisation, applied in the order of the fields. All
+/// steps are off in `Normalization::new`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Normalization {
+    pub form: Option<NormalForm>,
+    /// Folds full-width ASCII and the ideographic space to ASCII, and
+    /// half-width katakana to full-width.
+    pub fold_width: bool,
+    pub fold_case: bool,
+    /// Replaces typographic quotes with `'` and `"`.
+    pub quotes: bool,
+    /// Replaces hyphens, dashes and the minus sign with `-`.
+    pub dashes: bool,
+    /// Removes the Arabic tatweel (kashida) used to stretch words.
+    pub arabic_kashida: bool,
+    /// Removes the Arabic short vowels, tanween, shadda and sukun.
+    pub arabic_vowels: bool,
+}
+
+impl Normalization {
+    pub fn new() -> Normalization {
+        Normalization::default()
+    }
+
+    /// The normalisation the Buckwalter analyzer expects: NFC without tatweel
+    /// and vowel marks.
+    pub fn arabic() -> Normalization {
+        Normalization {
+            form: Some(NormalForm::Nfc),
+            arabic_kashida: true,
+            arabic_vowels: true,
+            ..Normalization::new()
+        }
+    }
+
+    /// Parses a comma-separated list of steps, such as `nfkc, case, quotes`.
+    /// The steps are `nfc`, `nfd`, `nfkc`, `nfkd`, `width`, `case`, `quotes`,
+    /// `dashes`, `kashida` and `vowels`.
+    pub fn parse(steps: &str) -> Result<Normalization, String> {
+        let mut normalization = Normalization::new();
+
+        for step in steps.split(',').map(str::trim).filter(|step| !step.is_empty()) {
+            match step {
+                "nfc" => normalization.form = Some(NormalForm::Nfc),
+                "nfd" => normalization.form = Some(NormalForm::Nfd),
+                "nfkc" => normalization.form = Some(NormalForm::Nfkc),
+                "nfkd" => normalization.form = Some(NormalForm::Nfkd),
+                "width" => normalization.fold_width = true,
+                "case" => normalization.fold_case = true,
+                "quotes" => normalization.quotes = true,
+                "dashes" => normalization.dashes = true,
+                "kashida" => normalization.arabic_kashida = true,
+                "vowels" => normalization.arabic_vowels = true,
+                _ => return Err(format!("unknown normalization step '{}'", step)),
+            }
+        }
+
+        Ok(normalization)
+    }
+
+    /// Normalises text, keeping a map of offsets back to the original.
+    pub fn apply(&self, text: &str) -> NormalizedText {
+        let mut normalized = String::with_capacity(text.len());
+        let mut map = OffsetMap::new();
+
+        for (start, grapheme) in text.grapheme_indices(true) {
+            normalized.push_str(&self.apply_grapheme(grapheme));
+
+            map.push(start + grapheme.len(), normalized.len());
+        }
+
+        NormalizedText {
+            text: normalized,
+            map,
+        }
+    }
+
+    fn apply_grapheme<'a>(&self, grapheme: &'a str) -> Cow<'a, str> {
+        let mut result = Cow::Borrowed(grapheme);
+
+        if let Some(form) = self.form {
+            let normalized: String = match form {
+                NormalForm::Nfc => result.nfc().collect(),
+                NormalForm::Nfd => result.nfd().collect(),
+                NormalForm::Nfkc => result.nfkc().collect(),
+                NormalForm::Nfkd => result.nfkd().collect(),
+            };
+
+            if normalized != result {
+                result = Cow::Owned(normalized);
+            }
+        }
+
+        if self.fold_width && result.chars().any(is_wide_or_narrow) {
+            result = Cow::Owned(fold_width(&result));
+        }
+
+        if self.fold_case && result.chars().any(|c| c.is_uppercase() || c == 'ς') {
+            result = Cow::Owned(result.chars().flat_map(char::to_lowercase).map(|c| if c == 'ς' { 'σ' } else { c }).collect());
+        }
+
+        if self.quotes && result.chars().any(|c| quote_for(c).is_some()) {
+            result = Cow::Owned(result.chars().map(|c| quote_for(c).unwrap_or(c)).collect());
+        }
+
+        if self.dashes && result.chars().any(is_dash) {
+            result = Cow::Owned(result.chars().map(|c| if is_dash(c) { '-' } else { c }).collect());
+        }
+
+        if self.arabic_kashida && result.contains('\u{0640}') {
+            result = Cow::Owned(result.replace('\u{0640}', ""));
+        }
+
+        if self.arabic_vowels && result.chars().any(is_arabic_vowel) {
+            result = Cow::Owned(result.chars().filter(|c| !is_arabic_vowel(*c)).collect());
+        }
+
+        result
+    }
+}
+
+/// Full-width ASCII, the ideographic space and half-width katakana.
+fn is_wide_or_narrow(c: char) -> bool {
+    matches!(c, '\u{3000}' | '\u{FF01}'..='\u{FF5E}' | '\u{FF61}'..='\u{FF9F}')
+}
+
+fn fold_width(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\u{3000}' => folded.push(' '),
+            '\u{FF01}'..='\u{FF5E}' => folded.push(char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)),
+            // Half-width katakana and their sound marks, which compose with
+            // the kana before them.
+            '\u{FF61}'..='\u{FF9F}' => folded.extend(c.to_string().nfkc()),
+            _ => folded.push(c),
+        }
+    }
+
+    folded.nfc().collect()
+}
+
+fn quote_for(c: char) -> Option<char> {
+    match c {
+        '‘' | '’' | '‚' | '‛' | '′' => Some('\''),
+        '“' | '”' | '„' | '‟' | '″' => Some('"'),
+        _ => None,
+    }
+}
+
+fn is_dash(c: char) -> bool {
+    matches!(c, '\u{2010}'..='\u{2015}' | '\u{2212}' | '\u{FE58}' | '\u{FE63}' | '\u{FF0D}')
+}
+
+fn is_arabic_vowel(c: char) -> bool {
+    matches!(c, '\u{064B}'..='\u{0652}')
+}
+
+/// Pairs of offsets in the original and the normalised text that mark the
+/// same boundary. Between two boundaries lies one grapheme cluster of the
+/// original and what it was normalised to, which may be nothing.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OffsetMap {
+    original: Vec<usize>,
+    normalized: Vec<usize>,
+}
+
+impl OffsetMap {
+    fn new() -> OffsetMap {
+        OffsetMap {
+            original: vec![0],
+            normalized: vec![0],
+        }
+    }
+
+    fn push(&mut self, original: usize, normalized: usize) {
+        self.original.push(original);
+        self.normalized.push(normalized);
+    }
+
+    /// The stretch of the original text that `start..end` of the normalised
+    /// text came from. Offsets within a grapheme cluster widen to the whole
+    /// cluster, and removed characters around the stretch are left out.
+    pub fn to_original(&self, start: usize, end: usize) -> (usize, usize) {
+        map_span(&self.normalized, &self.original, start, end)
+    }
+
+    /// The stretch of the normalised text that `start..end` of the original
+    /// text became.
+    pub fn to_normalized(&self, start: usize, end: usize) -> (usize, usize) {
+        map_span(&self.original, &self.normalized, start, end)
+    }
+}
+
+impl Default for OffsetMap {
+    fn default() -> OffsetMap {
+        OffsetMap::new()
+    }
+}
+
+fn map_span(from: &[usize], to: &[usize], start: usize, end: usize) -> (usize, usize) {
+    let first = from.partition_point(|&offset| offset <= start).saturating_sub(1);
+    let last = from.partition_point(|&offset| offset < end).min(from.len() - 1);
+
+    (to[first], to[last.max(first)])
+}
+
+/// Normalised text with the map back to the text it came from.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NormalizedText {
+    pub text: String,
+    pub map: OffsetMap,
+}
+
+impl NormalizedText {
+    /// The normalised text of `start..end` of the original text.
+    pub fn slice(&self, start: usize, end: usize) -> &str {
+        let (start, end) = self.map.to_normalized(start, end);
+
+        &self.text[start..end]
+    }
+}
+
+impl Stage for Normalization {
+    fn name(&self) -> &str {
+        "normalizer"
+    }
+
+    /// Stores the normalised text, which later stages tokenize and analyse
+    /// while their offsets keep pointing at the original.
+    fn process(&self, _omni: &Omniglot, text: &mut AnnotatedText) {
+        text.normalized = Some(self.apply(&text.text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_normalizes_unicode_forms() {
+        let decomposed = "Cafe\u{0301} ﬁn";
+
+        assert_eq!(Normalization::parse("nfc").unwrap().apply(decomposed).text, "Café ﬁn");
+        assert_eq!(Normalization::parse("nfkc").unwrap().apply(decomposed).text, "Café fin");
+        assert_eq!(Normalization::parse("nfd").unwrap().apply("é").text, "e\u{0301}");
+        assert_eq!(Normalization::parse("nfc, shout").is_err(), true);
+    }
+
+    #[test]
+    fn it_folds_width_and_case() {
+        let normalization = Normalization::parse("width, case").unwrap();
+
+        assert_eq!(normalization.apply("ＡＢＣ　ｶﾞｲﾄﾞ").text, "abc ガイド");
+        assert_eq!(normalization.apply("ΟΔΟΣ").text, "οδοσ");
+    }
+
+    #[test]
+    fn it_normalizes_quotes_and_dashes() {
+        let normalization = Normalization::parse("quotes, dashes").unwrap();
+
+        assert_eq!(normalization.apply("„Ja“ – zei ‘hij’ −3").text, "\"Ja\" - zei 'hij' -3");
+    }
+
+    #[test]
+    fn it_removes_arabic_marks() {
+        assert_eq!(Normalization::arabic().apply("كَتَبَ الولـــد").text, "كتب الولد");
+    }
+
+    #[test]
+    fn it_maps_offsets_to_the_original() {
+        let original = "ＡＢ كَتَـبَ";
+        let normalized = Normalization::parse("width, kashida, vowels").unwrap().apply(original);
+
+        assert_eq!(normalized.text, "AB كتب");
+
+        let (start, end) = normalized.map.to_original(3, 9);
+
+        assert_eq!(&original[start..end], "كَتَـبَ");
+        assert_eq!(normalized.map.to_original(1, 2), (3, 6));
+        assert_eq!(normalized.slice(0, 6), "AB");
+        assert_eq!(normalized.map.to_original(9, 9), (original.len(), original.len()));
+    }
+}
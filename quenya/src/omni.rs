@@ -1,34 +1,700 @@
+use crate::ara::Arabic;
+use crate::eng::English;
+use crate::fst::{FstError, Morphology, RuleSet};
+use crate::fst::lexc;
+use crate::hunspell::{Dictionary, HunspellError};
+use crate::identify::{Identification, Identifier, Profile};
+use crate::import::ImportError;
+use crate::import::wordnet::import_multilingual_wordnet;
+use crate::jpn::Japanese;
+use crate::language::{Generic, Language};
 use crate::lexicon::Lexicon;
+use crate::nld::Dutch;
+use crate::segmentation::{Segment, script_runs, segment_runs};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::snapshot::MappedSnapshot;
+use crate::snapshot::SnapshotError;
 use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use crate::uxy::Xiinthlea;
 
-struct Omniglot {
-    lexicon: HashMap<String, Lexicon>
+/// Environment variable naming a registry configuration file.
+pub const CONFIG_VARIABLE: &str = "QUENYA_CONFIG";
+
+/// Environment variable naming a data directory with one subdirectory per
+/// language, such as `nld/` and `jpn/`.
+pub const DATA_VARIABLE: &str = "QUENYA_DATA";
+
+/// File name of a lexicon snapshot inside a language's data directory.
+pub const SNAPSHOT_FILE: &str = "lexicon.qlx";
+
+/// File name of a character n-gram profile inside a language's data directory.
+pub const PROFILE_FILE: &str = "profile.tsv";
+
+/// File name of a lexc lexicon inside a language's data directory.
+pub const MORPHOLOGY_FILE: &str = "morphology.lexc";
+
+/// File name of the rules that go with `MORPHOLOGY_FILE`.
+pub const RULES_FILE: &str = "morphology.rules";
+
+/// File name of a Hunspell affix file inside a language's data directory.
+pub const AFFIX_FILE: &str = "hunspell.aff";
+
+/// File name of the Hunspell word list that goes with `AFFIX_FILE`.
+pub const DICTIONARY_FILE: &str = "hunspell.dic";
+
+#[derive(Debug)]
+pub enum OmniError {
+    /// Neither `QUENYA_CONFIG` nor `QUENYA_DATA` is set.
+    NotConfigured,
+    UnknownLanguage(String),
+    MissingResource { language: String, path: PathBuf },
+    Config { line: usize, message: String },
+    Io { path: PathBuf, error: io::Error },
+    Snapshot { path: PathBuf, error: SnapshotError },
+    Morphology { path: PathBuf, error: FstError },
+    Dictionary { path: PathBuf, error: HunspellError },
+    Import { path: PathBuf, error: ImportError },
+    /// The language failed to load before, with the given error.
+    Unavailable { language: String, reason: String },
+}
+
+impl fmt::Display for OmniError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OmniError::NotConfigured => {
+                write!(f, "no languages configured, set {} or {}", CONFIG_VARIABLE, DATA_VARIABLE)
+            }
+            OmniError::UnknownLanguage(language) => write!(f, "language '{}' is not registered", language),
+            OmniError::MissingResource { language, path } => {
+                write!(f, "no lexicon for '{}' found at {}", language, path.display())
+            }
+            OmniError::Config { line, message } => write!(f, "configuration line {}: {}", line, message),
+            OmniError::Io { path, error } => write!(f, "could not read {}: {}", path.display(), error),
+            OmniError::Snapshot { path, error } => write!(f, "could not load {}: {}", path.display(), error),
+            OmniError::Morphology { path, error } => write!(f, "could not compile {}: {}", path.display(), error),
+            OmniError::Dictionary { path, error } => write!(f, "could not load {}: {}", path.display(), error),
+            OmniError::Import { path, error } => write!(f, "could not import {}: {}", path.display(), error),
+            OmniError::Unavailable { language, reason } => write!(f, "language '{}' is unavailable: {}", language, reason),
+        }
+    }
+}
+
+impl std::error::Error for OmniError {}
+
+struct Registration {
+    data_dir: Option<PathBuf>,
+    /// The loaded language, or the error that loading it failed with.
+    language: OnceLock<Result<Box<dyn Language>, String>>,
+}
+
+/// A registry of languages keyed by ISO 639-3 code. Languages registered with
+/// a data directory are loaded on first use, with the module for their code
+/// or, for codes without a module, as a `Generic` language.
+#[derive(Default)]
+pub struct Omniglot {
+    languages: HashMap<String, Registration>,
+    identifier: OnceLock<Identifier>,
+    default_language: Option<String>,
 }
 
 impl Omniglot {
-    fn new() -> Omniglot {
-        let mut omni = Omniglot {
-            lexicon: HashMap::new(),
-        };
-        
-        omni.lexicon.insert(String::from("nld"), crate::nld::load_lexicon());
-        
+    pub fn new() -> Omniglot {
+        Omniglot {
+            languages: HashMap::new(),
+            identifier: OnceLock::new(),
+            default_language: None,
+        }
+    }
+
+    /// A registry of the languages that need no data: English with rule-based
+    /// verb morphology, Arabic transliteration, Japanese and Xiinthlea.
+    /// English is the default language.
+    pub fn builtin() -> Omniglot {
+        let mut omni = Omniglot::new();
+
+        omni.add_language(Box::new(Arabic::new(None, None)));
+        omni.add_language(Box::new(English::new(None)));
+        omni.add_language(Box::new(Japanese::new(None)));
+        omni.add_language(Box::new(Xiinthlea));
+        omni.set_default_language("eng");
+
         omni
     }
+
+    /// Configures the registry from `QUENYA_CONFIG` if it is set, or else from
+    /// `QUENYA_DATA`.
+    pub fn from_env() -> Result<Omniglot, OmniError> {
+        if let Some(config) = env::var_os(CONFIG_VARIABLE) {
+            return Omniglot::from_config_file(Path::new(&config));
+        }
+
+        if let Some(data) = env::var_os(DATA_VARIABLE) {
+            return Omniglot::from_data_dir(Path::new(&data));
+        }
+
+        Err(OmniError::NotConfigured)
+    }
+
+    /// Reads a configuration file with one `code = path` line per language.
+    /// Relative paths are resolved against the directory of the file.
+    pub fn from_config_file(path: &Path) -> Result<Omniglot, OmniError> {
+        let text = fs::read_to_string(path).map_err(|error| OmniError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+        Omniglot::from_config(&text, base)
+    }
+
+    pub fn from_config(config: &str, base: &Path) -> Result<Omniglot, OmniError> {
+        let mut omni = Omniglot::new();
+
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+
+            let (code, path) = match (parts.next(), parts.next()) {
+                (Some(code), Some(path)) => (code.trim(), path.trim()),
+                _ => return Err(OmniError::Config {
+                    line: number + 1,
+                    message: String::from("expected 'code = path'"),
+                }),
+            };
+
+            if !is_language_code(code) {
+                return Err(OmniError::Config {
+                    line: number + 1,
+                    message: format!("'{}' is not an ISO 639-3 code", code),
+                });
+            }
+
+            omni.register(code, base.join(path));
+        }
+
+        Ok(omni)
+    }
+
+    /// Registers every subdirectory of `root` that is named after a language.
+    pub fn from_data_dir(root: &Path) -> Result<Omniglot, OmniError> {
+        let mut omni = Omniglot::new();
+
+        let entries = fs::read_dir(root).map_err(|error| OmniError::Io {
+            path: root.to_path_buf(),
+            error,
+        })?;
+
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+
+            if let Some(code) = name.to_str() {
+                if is_language_code(code) && entry.path().is_dir() {
+                    omni.register(code, entry.path());
+                }
+            }
+        }
+
+        Ok(omni)
+    }
+
+    /// Registers a language with its data directory, replacing any earlier
+    /// registration and discarding its loaded resources.
+    pub fn register<P: Into<PathBuf>>(&mut self, code: &str, data_dir: P) {
+        self.languages.insert(String::from(code), Registration {
+            data_dir: Some(data_dir.into()),
+            language: OnceLock::new(),
+        });
+    }
+
+    /// Registers a language that is already loaded, such as a custom
+    /// implementation, under its own code.
+    pub fn add_language(&mut self, language: Box<dyn Language>) {
+        self.languages.insert(String::from(language.code()), Registration {
+            data_dir: None,
+            language: OnceLock::from(Ok(language)),
+        });
+    }
+
+    /// Sets the language of text that could not be identified.
+    pub fn set_default_language(&mut self, code: &str) {
+        self.default_language = Some(String::from(code));
+    }
+
+    pub fn has_language(&self, code: &str) -> bool {
+        self.languages.contains_key(code)
+    }
+
+    /// The registered language codes, sorted.
+    pub fn languages(&self) -> Vec<&str> {
+        let mut codes: Vec<&str> = self.languages.keys().map(|code| code.as_str()).collect();
+        codes.sort_unstable();
+
+        codes
+    }
+
+    /// The data directory of a language, unless it was added already loaded.
+    pub fn data_dir(&self, code: &str) -> Option<&Path> {
+        self.languages.get(code)?.data_dir.as_deref()
+    }
+
+    /// Whether a language has been loaded yet.
+    pub fn is_loaded(&self, code: &str) -> bool {
+        match self.languages.get(code) {
+            Some(registration) => registration.language.get().is_some_and(Result::is_ok),
+            None => false,
+        }
+    }
+
+    /// Returns a language, loading it on first use. A language that fails to
+    /// load is not tried again: the first call returns the error, later calls
+    /// return `OmniError::Unavailable` and `load_errors` lists it.
+    pub fn get(&self, code: &str) -> Result<&dyn Language, OmniError> {
+        let registration = self.registration(code)?;
+
+        if let Some(loaded) = registration.language.get() {
+            return match loaded {
+                Ok(language) => Ok(language.as_ref()),
+                Err(reason) => Err(OmniError::Unavailable {
+                    language: String::from(code),
+                    reason: reason.clone(),
+                }),
+            };
+        }
+
+        let data_dir = registration.data_dir.as_deref().unwrap_or_else(|| Path::new(""));
+
+        match load_language(code, data_dir) {
+            Ok(language) => match registration.language.get_or_init(|| Ok(language)) {
+                Ok(language) => Ok(language.as_ref()),
+                Err(_) => self.get(code),
+            },
+            Err(error) => {
+                let _ = registration.language.set(Err(error.to_string()));
+
+                Err(error)
+            }
+        }
+    }
+
+    /// The languages that failed to load so far, with their errors, sorted by
+    /// code. Pipeline stages skip the words of such languages, so callers can
+    /// report these after annotating.
+    pub fn load_errors(&self) -> Vec<(&str, &str)> {
+        let mut errors: Vec<(&str, &str)> = self.languages.iter()
+            .filter_map(|(code, registration)| match registration.language.get() {
+                Some(Err(reason)) => Some((code.as_str(), reason.as_str())),
+                _ => None,
+            })
+            .collect();
+
+        errors.sort_unstable();
+
+        errors
+    }
+
+    /// Returns the lexicon of a language, loading the language on first use.
+    /// A snapshot (`lexicon.qlx`) in the data directory is preferred over the
+    /// OMW wordnet (`wn-data-<code>.tab`).
+    pub fn lexicon(&self, code: &str) -> Result<&Lexicon, OmniError> {
+        match self.get(code)?.lexicon() {
+            Some(lexicon) => Ok(lexicon),
+            None => Err(missing_lexicon(code, self.data_dir(code).unwrap_or_else(|| Path::new("")))),
+        }
+    }
+
+    /// Ranks the languages a text may be written in, using the n-gram profiles
+    /// (`profile.tsv`) of the registered languages. The profiles are loaded
+    /// on first use.
+    pub fn identify(&self, text: &str) -> Result<Vec<Identification>, OmniError> {
+        if let Some(identifier) = self.identifier.get() {
+            return Ok(identifier.identify(text));
+        }
+
+        let mut identifier = Identifier::new();
+
+        for (code, registration) in &self.languages {
+            let path = match &registration.data_dir {
+                Some(data_dir) => data_dir.join(PROFILE_FILE),
+                None => continue,
+            };
+
+            if path.is_file() {
+                let profile = File::open(&path)
+                    .and_then(|file| Profile::read(code, file))
+                    .map_err(|error| OmniError::Io { path, error })?;
+
+                identifier.add_profile(profile);
+            }
+        }
+
+        Ok(self.identifier.get_or_init(|| identifier).identify(text))
+    }
+
+    /// Segments text like `segmentation::segment`, but gives runs in scripts
+    /// shared by several languages, such as Latin, the registered language
+    /// that identification ranks highest, or else the default language.
+    pub fn segment<'a>(&self, text: &'a str) -> Result<Vec<Segment<'a>>, OmniError> {
+        let mut runs = script_runs(text);
+
+        for run in &mut runs {
+            if run.language.as_deref().is_some_and(|language| self.has_language(language)) {
+                continue;
+            }
+
+            let identified = self.identify(run.text)?
+                .into_iter()
+                .find(|identification| self.has_language(&identification.language));
+
+            if let Some(identification) = identified {
+                run.language = Some(identification.language);
+            } else if run.language.is_none() {
+                run.language = self.default_language.clone();
+            }
+        }
+
+        Ok(segment_runs(&runs))
+    }
+
+    fn registration(&self, code: &str) -> Result<&Registration, OmniError> {
+        match self.languages.get(code) {
+            Some(registration) => Ok(registration),
+            None => Err(OmniError::UnknownLanguage(String::from(code))),
+        }
+    }
+}
+
+fn is_language_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_lowercase())
+}
+
+fn load_language(code: &str, data_dir: &Path) -> Result<Box<dyn Language>, OmniError> {
+    let lexicon = load_lexicon(code, data_dir)?;
+
+    let language: Box<dyn Language> = match code {
+        "ara" => {
+            let arabic = Arabic::load(data_dir, lexicon).map_err(|error| OmniError::Io {
+                path: data_dir.join("aramorph"),
+                error,
+            })?;
+
+            match load_dictionary(data_dir)? {
+                Some(dictionary) => Box::new(arabic.with_dictionary(dictionary)),
+                None => Box::new(arabic),
+            }
+        },
+        "eng" => match load_dictionary(data_dir)? {
+            Some(dictionary) => Box::new(English::new(lexicon).with_dictionary(dictionary)),
+            None => Box::new(English::new(lexicon)),
+        },
+        "jpn" => Box::new(Japanese::new(lexicon)),
+        "nld" => match (lexicon, load_dictionary(data_dir)?) {
+            (Some(lexicon), Some(dictionary)) => Box::new(Dutch::new(lexicon).with_dictionary(dictionary)),
+            (Some(lexicon), None) => Box::new(Dutch::new(lexicon)),
+            (None, _) => return Err(missing_lexicon(code, data_dir)),
+        },
+        "uxy" => Box::new(Xiinthlea),
+        _ => {
+            let mut generic = Generic::new(code, lexicon);
+
+            if let Some(morphology) = load_morphology(data_dir)? {
+                generic = generic.with_morphology(morphology);
+            }
+
+            if let Some(dictionary) = load_dictionary(data_dir)? {
+                generic = generic.with_dictionary(dictionary);
+            }
+
+            Box::new(generic)
+        }
+    };
+
+    Ok(language)
+}
+
+/// Compiles the finite-state morphology in a language's data directory, if
+/// it has one.
+fn load_morphology(data_dir: &Path) -> Result<Option<Morphology>, OmniError> {
+    let lexc = data_dir.join(MORPHOLOGY_FILE);
+
+    if !lexc.is_file() {
+        return Ok(None);
+    }
+
+    let read = |path: &Path| fs::read_to_string(path).map_err(|error| OmniError::Io {
+        path: path.to_path_buf(),
+        error,
+    });
+
+    let lexicon = lexc::compile(&read(&lexc)?).map_err(|error| OmniError::Morphology { path: lexc, error })?;
+    let rules = data_dir.join(RULES_FILE);
+
+    if !rules.is_file() {
+        return Ok(Some(Morphology::with_rules(&lexicon, None)));
+    }
+
+    match RuleSet::parse(&read(&rules)?) {
+        Ok(rules) => Ok(Some(Morphology::with_rules(&lexicon, Some(&rules)))),
+        Err(error) => Err(OmniError::Morphology { path: rules, error }),
+    }
+}
+
+/// Reads the Hunspell dictionary in a language's data directory, if it has
+/// one.
+fn load_dictionary(data_dir: &Path) -> Result<Option<Dictionary>, OmniError> {
+    let aff = data_dir.join(AFFIX_FILE);
+    let dic = data_dir.join(DICTIONARY_FILE);
+
+    if !aff.is_file() || !dic.is_file() {
+        return Ok(None);
+    }
+
+    let read = |path: &Path| fs::read(path).map_err(|error| OmniError::Io {
+        path: path.to_path_buf(),
+        error,
+    });
+
+    match Dictionary::from_bytes(&read(&aff)?, &read(&dic)?) {
+        Ok(dictionary) => Ok(Some(dictionary)),
+        // The encoding is named in the affix file, so only a malformed word
+        // list is the fault of the word list.
+        Err(error @ HunspellError::Malformed { file: "dic", .. }) => Err(OmniError::Dictionary { path: dic, error }),
+        Err(error) => Err(OmniError::Dictionary { path: aff, error }),
+    }
+}
+
+fn missing_lexicon(code: &str, data_dir: &Path) -> OmniError {
+    OmniError::MissingResource {
+        language: String::from(code),
+        path: data_dir.join(format!("wn-data-{}.tab", code)),
+    }
+}
+
+/// Loads the lexicon in a language's data directory, if it has one.
+fn load_lexicon(code: &str, data_dir: &Path) -> Result<Option<Lexicon>, OmniError> {
+    let snapshot = data_dir.join(SNAPSHOT_FILE);
+
+    if snapshot.is_file() {
+        return match open_snapshot(&snapshot) {
+            Ok(lexicon) => Ok(Some(lexicon)),
+            Err(error) => Err(OmniError::Snapshot { path: snapshot, error }),
+        };
+    }
+
+    let wordnet = data_dir.join(format!("wn-data-{}.tab", code));
+
+    if !wordnet.is_file() {
+        return Ok(None);
+    }
+
+    let file = File::open(&wordnet).map_err(|error| OmniError::Io {
+        path: wordnet.clone(),
+        error,
+    })?;
+
+    let mut lexicon = Lexicon::new();
+
+    if let Err(error) = import_multilingual_wordnet(file, &mut lexicon) {
+        return Err(OmniError::Import { path: wordnet, error });
+    }
+
+    Ok(Some(lexicon))
+}
+
+/// Opens a snapshot as a lexicon that reads from the mapped file, so that
+/// nothing is rebuilt when the language loads.
+#[cfg(not(target_arch = "wasm32"))]
+fn open_snapshot(path: &Path) -> Result<Lexicon, SnapshotError> {
+    Ok(Lexicon::from(MappedSnapshot::open(path)?))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn open_snapshot(path: &Path) -> Result<Lexicon, SnapshotError> {
+    Lexicon::from_snapshot(fs::read(path)?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use wn::pos::Part;
+    use crate::features::Features;
+    use crate::language::Capability;
+
+    fn data_dir(name: &str) -> PathBuf {
+        let root = env::temp_dir().join(format!("quenya-omni-{}-{}", name, std::process::id()));
+        let nld = root.join("nld");
+
+        fs::create_dir_all(&nld).unwrap();
+        fs::create_dir_all(root.join("jpn")).unwrap();
+        fs::write(nld.join("wn-data-nld.tab"), "# Dutch\n01315613-v\tnld:lemma\tzoeken\n").unwrap();
+
+        root
+    }
 
     #[test]
     fn it_should_create() {
-        let omni = Omniglot::new();
-        
-        assert_eq!(omni.lexicon.get("nld").is_some(), true);
-        
-        assert_eq!(omni.lexicon.get("nld").unwrap().find_lexeme_by_lemma("zoeken", Part::Verb).is_ok(), true);
-        assert_eq!(omni.lexicon.get("nld").unwrap().find_lexeme_by_lemma("3513dsags", Part::Verb).is_ok(), false);
+        let root = data_dir("create");
+        let omni = Omniglot::from_data_dir(&root).unwrap();
+
+        assert_eq!(omni.languages(), vec!["jpn", "nld"]);
+        assert!(!omni.is_loaded("nld"));
+
+        assert!(omni.lexicon("nld").unwrap().find_lexeme_by_lemma("zoeken", Part::Verb).is_some());
+        assert!(omni.lexicon("nld").unwrap().find_lexeme_by_lemma("3513dsags", Part::Verb).is_none());
+        assert!(omni.is_loaded("nld"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn it_reports_missing_resources() {
+        let root = data_dir("missing");
+        let omni = Omniglot::from_data_dir(&root).unwrap();
+
+        assert!(matches!(omni.lexicon("jpn"), Err(OmniError::MissingResource { .. })));
+        assert!(matches!(omni.lexicon("ara"), Err(OmniError::UnknownLanguage(_))));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn it_loads_language_modules() {
+        let root = data_dir("modules");
+        let omni = Omniglot::from_data_dir(&root).unwrap();
+
+        assert_eq!(omni.get("nld").unwrap().lemmatize("zoeken"), vec!["zoeken"]);
+        assert!(!omni.get("jpn").unwrap().supports(Capability::Analyze));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn it_loads_finite_state_morphology() {
+        let root = data_dir("morphology");
+        let tlh = root.join("tlh");
+
+        fs::create_dir_all(&tlh).unwrap();
+        fs::write(tlh.join(MORPHOLOGY_FILE), "Multichar_Symbols +Noun +Number=Plur\nLEXICON Root\nmIl Number ;\nLEXICON Number\n+Noun:0 # ;\n+Noun+Number=Plur:mey # ;\n").unwrap();
+        fs::write(tlh.join(RULES_FILE), "l -> 0 || _ m e y ;").unwrap();
+
+        let omni = Omniglot::from_data_dir(&root).unwrap();
+        let klingon = omni.get("tlh").unwrap();
+
+        assert_eq!(klingon.lemmatize("mImey"), vec!["mIl"]);
+        assert_eq!(klingon.inflect("mIl", Part::Noun, &Features::new().with("Number", "Plur")), vec!["mImey"]);
+        assert!(klingon.supports(Capability::Inflect));
+
+        fs::write(tlh.join(RULES_FILE), "l -> ;").unwrap();
+
+        let omni = Omniglot::from_data_dir(&root).unwrap();
+
+        assert!(matches!(omni.get("tlh"), Err(OmniError::Morphology { .. })));
+        assert!(matches!(omni.get("tlh"), Err(OmniError::Unavailable { .. })));
+        assert!(!omni.is_loaded("tlh"));
+        assert_eq!(omni.load_errors().len(), 1);
+        assert_eq!(omni.load_errors()[0].0, "tlh");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn it_loads_hunspell_dictionaries() {
+        let root = data_dir("hunspell");
+        let eng = root.join("eng");
+
+        fs::create_dir_all(&eng).unwrap();
+        fs::write(eng.join(AFFIX_FILE), "SET UTF-8\nTRY esiarnto\nSFX S Y 1\nSFX S 0 s . is:Number=Plur\n").unwrap();
+        fs::write(eng.join(DICTIONARY_FILE), "1\nbook/S po:noun\n").unwrap();
+
+        let omni = Omniglot::from_data_dir(&root).unwrap();
+        let english = omni.get("eng").unwrap();
+
+        assert_eq!(english.check("books"), Some(true));
+        assert_eq!(english.suggest("boks"), vec!["books"]);
+        assert!(english.analyze_word("books").iter().any(|analysis| analysis.pos == Some(Part::Noun)));
+        assert_eq!(omni.get("nld").unwrap().check("boeken"), None);
+
+        fs::write(eng.join(DICTIONARY_FILE), "book/S\n").unwrap();
+
+        let ara = root.join("ara");
+
+        fs::create_dir_all(&ara).unwrap();
+        fs::write(ara.join(AFFIX_FILE), "SET UTF-8\nPFX A Y 1\nPFX A 0 ال . is:Definite=Def\n").unwrap();
+        fs::write(ara.join(DICTIONARY_FILE), "1\nكتاب/A po:noun\n").unwrap();
+
+        let omni = Omniglot::from_data_dir(&root).unwrap();
+
+        assert!(matches!(omni.get("eng"), Err(OmniError::Dictionary { path, .. }) if path == eng.join(DICTIONARY_FILE)));
+
+        let arabic = omni.get("ara").unwrap();
+
+        assert_eq!(arabic.check("الكتاب"), Some(true));
+        assert!(arabic.analyze_word("الكتاب").iter().any(|analysis| analysis.features.get("Definite") == Some("Def")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn it_adds_custom_languages() {
+        let mut omni = Omniglot::new();
+
+        omni.add_language(Box::new(Generic::new("tlh", None)));
+
+        assert!(omni.is_loaded("tlh"));
+        assert_eq!(omni.get("tlh").unwrap().tokenize("Qapla'"), vec!["Qapla", "'"]);
+        assert_eq!(omni.data_dir("tlh"), None);
+    }
+
+    #[test]
+    fn it_identifies_languages_with_profiles() {
+        let root = data_dir("identify");
+        fs::write(root.join("nld").join(PROFILE_FILE), " de\t3\nde \t3\nd\t3\ne\t3\n").unwrap();
+
+        let omni = Omniglot::from_data_dir(&root).unwrap();
+        let identifications = omni.identify("de").unwrap();
+
+        assert_eq!(identifications[0].language, "nld");
+        assert_eq!(omni.identify("ひらがな").unwrap()[0].language, "jpn");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn it_passes_language_hints_to_segments() {
+        let root = data_dir("segment");
+        fs::write(root.join("nld").join(PROFILE_FILE), " de\t3\nde \t3\nd\t3\ne\t3\n").unwrap();
+
+        let omni = Omniglot::from_data_dir(&root).unwrap();
+        let segments = omni.segment("東京でde").unwrap();
+
+        assert_eq!(segments[0].language.as_deref(), Some("jpn"));
+        assert_eq!(segments[2].language.as_deref(), Some("nld"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn it_reads_a_configuration() {
+        let omni = Omniglot::from_config("# languages\nnld = dict/nld\njpn = /data/jpn\n", Path::new("/srv/quenya")).unwrap();
+
+        assert_eq!(omni.data_dir("nld").unwrap(), Path::new("/srv/quenya/dict/nld"));
+        assert_eq!(omni.data_dir("jpn").unwrap(), Path::new("/data/jpn"));
+    }
+
+    #[test]
+    fn it_rejects_invalid_configurations() {
+        assert!(matches!(Omniglot::from_config("dutch = nld", Path::new("")), Err(OmniError::Config { line: 1, .. })));
+        assert!(matches!(Omniglot::from_config("\nnld", Path::new("")), Err(OmniError::Config { line: 2, .. })));
     }
 }
@@ -0,0 +1,541 @@
+// Inflection paradigms: the forms of a lexeme laid out in tables whose rows
+// and columns are feature combinations, with renderers for HTML, Markdown,
+// plain text and JSON.
+//
+// Only English and Dutch verbs are generated by rule. The Japanese and Arabic
+// layouts are filled from the forms recorded in the lexicon, so their cells
+// are gaps unless an importer has added those forms.
+
+use std::fmt::Write;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+use wn::pos::Part;
+use crate::features::Features;
+use crate::language::Language;
+use crate::lexicon::Lexeme;
+
+/// A table layout written as UD feature strings, for the built-in layouts.
+struct TableSpec {
+    title: &'static str,
+    features: &'static str,
+    columns: &'static [(&'static str, &'static str)],
+    rows: &'static [(&'static str, &'static str)],
+}
+
+const ONE_COLUMN: &[(&str, &str)] = &[("", "")];
+
+const NUMBERS: &[(&str, &str)] = &[("Singular", "Number=Sing"), ("Plural", "Number=Plur")];
+
+const ENGLISH_VERB: &[TableSpec] = &[
+    TableSpec {
+        title: "Conjugation",
+        features: "",
+        columns: ONE_COLUMN,
+        rows: &[
+            ("Infinitive", "VerbForm=Inf"),
+            ("Present, 3rd person singular", "Number=Sing|Person=3|Tense=Pres"),
+            ("Past", "Tense=Past"),
+            ("Present participle", "Tense=Pres|VerbForm=Part"),
+            ("Past participle", "Tense=Past|VerbForm=Part"),
+        ],
+    },
+];
+
+const DUTCH_VERB: &[TableSpec] = &[
+    TableSpec {
+        title: "Finite forms",
+        features: "Mood=Ind|VerbForm=Fin",
+        columns: &[("Present", "Tense=Pres"), ("Past", "Tense=Past")],
+        rows: &[
+            ("ik", "Number=Sing|Person=1"),
+            ("jij", "Number=Sing|Person=2"),
+            ("hij, zij, het", "Number=Sing|Person=3"),
+            ("wij, jullie, zij", "Number=Plur"),
+        ],
+    },
+    TableSpec {
+        title: "Non-finite forms",
+        features: "",
+        columns: ONE_COLUMN,
+        rows: &[
+            ("Infinitive", "VerbForm=Inf"),
+            ("Present participle", "Tense=Pres|VerbForm=Part"),
+            ("Past participle", "Tense=Past|VerbForm=Part"),
+        ],
+    },
+];
+
+const JAPANESE_VERB: &[TableSpec] = &[
+    TableSpec {
+        title: "Conjugation",
+        features: "",
+        columns: &[
+            ("Plain", "Polarity=Pos|Polite=Infm"),
+            ("Plain negative", "Polarity=Neg|Polite=Infm"),
+            ("Polite", "Polarity=Pos|Polite=Form"),
+            ("Polite negative", "Polarity=Neg|Polite=Form"),
+        ],
+        rows: &[("Non-past", "Tense=Pres"), ("Past", "Tense=Past")],
+    },
+    TableSpec {
+        title: "Other forms",
+        features: "",
+        columns: ONE_COLUMN,
+        rows: &[
+            ("Te form", "VerbForm=Conv"),
+            ("Volitional", "Mood=Vol"),
+            ("Imperative", "Mood=Imp"),
+            ("Conditional", "Mood=Cnd"),
+        ],
+    },
+];
+
+const ARABIC_PERSONS: &[(&str, &str)] = &[
+    ("3rd person masculine", "Gender=Masc|Person=3"),
+    ("3rd person feminine", "Gender=Fem|Person=3"),
+    ("2nd person masculine", "Gender=Masc|Person=2"),
+    ("2nd person feminine", "Gender=Fem|Person=2"),
+    ("1st person", "Person=1"),
+];
+
+const ARABIC_NUMBERS: &[(&str, &str)] = &[("Singular", "Number=Sing"), ("Dual", "Number=Dual"), ("Plural", "Number=Plur")];
+
+const ARABIC_VERB: &[TableSpec] = &[
+    TableSpec {
+        title: "Perfect",
+        features: "Aspect=Perf|Voice=Act",
+        columns: ARABIC_NUMBERS,
+        rows: ARABIC_PERSONS,
+    },
+    TableSpec {
+        title: "Imperfect indicative",
+        features: "Aspect=Imp|Mood=Ind|Voice=Act",
+        columns: ARABIC_NUMBERS,
+        rows: ARABIC_PERSONS,
+    },
+];
+
+const ARABIC_NOUN: &[TableSpec] = &[
+    TableSpec {
+        title: "Declension",
+        features: "Definite=Def",
+        columns: ARABIC_NUMBERS,
+        rows: &[("Nominative", "Case=Nom"), ("Accusative", "Case=Acc"), ("Genitive", "Case=Gen")],
+    },
+];
+
+const NOUN: &[TableSpec] = &[
+    TableSpec {
+        title: "Declension",
+        features: "",
+        columns: ONE_COLUMN,
+        rows: NUMBERS,
+    },
+];
+
+/// The rows and columns of a table, each labelled and with the features its
+/// cells realise. Every cell realises the features of the table, its row and
+/// its column together.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableLayout {
+    pub title: String,
+    pub features: Features,
+    pub columns: Vec<(String, Features)>,
+    pub rows: Vec<(String, Features)>,
+}
+
+impl TableLayout {
+    fn from_spec(spec: &TableSpec) -> TableLayout {
+        let labelled = |pairs: &[(&str, &str)]| pairs.iter()
+            .map(|(label, features)| (String::from(*label), Features::parse(features).unwrap_or_default()))
+            .collect();
+
+        TableLayout {
+            title: String::from(spec.title),
+            features: Features::parse(spec.features).unwrap_or_default(),
+            columns: labelled(spec.columns),
+            rows: labelled(spec.rows),
+        }
+    }
+}
+
+/// The built-in layouts for a part of speech in a language, if there are any.
+pub fn layouts(language: &str, pos: Part) -> Vec<TableLayout> {
+    let specs: &[TableSpec] = match (language, pos) {
+        ("eng", Part::Verb) => ENGLISH_VERB,
+        ("nld", Part::Verb) => DUTCH_VERB,
+        ("jpn", Part::Verb) => JAPANESE_VERB,
+        ("ara", Part::Verb) => ARABIC_VERB,
+        ("ara", Part::Noun) => ARABIC_NOUN,
+        (_, Part::Noun) => NOUN,
+        _ => &[],
+    };
+
+    specs.iter().map(TableLayout::from_spec).collect()
+}
+
+/// The forms that realise the features of a cell. Cells without forms are
+/// gaps in the paradigm.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cell {
+    pub features: Features,
+    pub forms: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Row {
+    pub label: String,
+    pub cells: Vec<Cell>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Table {
+    pub title: String,
+    /// Column labels. A table with one unlabelled column is a list.
+    pub columns: Vec<String>,
+    pub rows: Vec<Row>,
+}
+
+/// The inflection paradigm of a lexeme.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Paradigm {
+    pub language: String,
+    pub lemma: String,
+    pub pos: Part,
+    pub tables: Vec<Table>,
+}
+
+impl Paradigm {
+    /// Builds the paradigm of a lexeme with the built-in layouts of its
+    /// language. Without a layout, every form of the lexeme becomes a row
+    /// labelled with its features.
+    pub fn build(language: &dyn Language, lexeme: &Lexeme) -> Paradigm {
+        let layouts = layouts(language.code(), lexeme.pos);
+
+        if layouts.is_empty() {
+            return Paradigm::from_forms(language.code(), lexeme);
+        }
+
+        Paradigm::build_with(language, lexeme, &layouts)
+    }
+
+    /// Builds the paradigm of a lexeme with the given layouts. Forms come from
+    /// the language's inflection, or from the forms of the lexeme if the
+    /// language has none for a cell, as for every cell of the Japanese and
+    /// Arabic verb layouts.
+    pub fn build_with(language: &dyn Language, lexeme: &Lexeme, layouts: &[TableLayout]) -> Paradigm {
+        let tables = layouts.iter()
+            .map(|layout| Table {
+                title: layout.title.clone(),
+                columns: layout.columns.iter().map(|(label, _)| label.clone()).collect(),
+                rows: layout.rows.iter()
+                    .map(|(label, row_features)| Row {
+                        label: label.clone(),
+                        cells: layout.columns.iter()
+                            .map(|(_, column_features)| {
+                                let mut features = layout.features.clone();
+
+                                for (name, value) in row_features.iter().chain(column_features.iter()) {
+                                    features.insert(name, value);
+                                }
+
+                                let forms = inflect(language, lexeme, &features);
+
+                                Cell {
+                                    features,
+                                    forms,
+                                }
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Paradigm {
+            language: String::from(language.code()),
+            lemma: lexeme.lemma.clone(),
+            pos: lexeme.pos,
+            tables,
+        }
+    }
+
+    fn from_forms(language: &str, lexeme: &Lexeme) -> Paradigm {
+        let mut rows: Vec<Row> = Vec::new();
+
+        for form in &lexeme.forms {
+            match rows.iter_mut().find(|row| row.cells[0].features == form.features) {
+                Some(row) if !row.cells[0].forms.contains(&form.form) => row.cells[0].forms.push(form.form.clone()),
+                Some(_) => {},
+                None => rows.push(Row {
+                    label: form.features.to_string(),
+                    cells: vec![Cell {
+                        features: form.features.clone(),
+                        forms: vec![form.form.clone()],
+                    }],
+                }),
+            }
+        }
+
+        Paradigm {
+            language: String::from(language),
+            lemma: lexeme.lemma.clone(),
+            pos: lexeme.pos,
+            tables: vec![Table {
+                title: String::from("Forms"),
+                columns: vec![String::new()],
+                rows,
+            }],
+        }
+    }
+
+    /// Renders the tables as HTML, with the language on every table so that
+    /// browsers pick suitable fonts and direction.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let direction = if self.language == "ara" { " dir=\"rtl\"" } else { "" };
+
+        for table in &self.tables {
+            let _ = writeln!(html, "<table class=\"paradigm\" lang=\"{}\"{}>", escape_html(&self.language), direction);
+            let _ = writeln!(html, "<caption>{}</caption>", escape_html(&table.title));
+
+            if !is_list(table) {
+                html.push_str("<thead><tr><th></th>");
+
+                for column in &table.columns {
+                    let _ = write!(html, "<th scope=\"col\">{}</th>", escape_html(column));
+                }
+
+                html.push_str("</tr></thead>\n");
+            }
+
+            html.push_str("<tbody>\n");
+
+            for row in &table.rows {
+                let _ = write!(html, "<tr><th scope=\"row\">{}</th>", escape_html(&row.label));
+
+                for cell in &row.cells {
+                    let _ = write!(html, "<td>{}</td>", escape_html(&cell_text(cell)));
+                }
+
+                html.push_str("</tr>\n");
+            }
+
+            html.push_str("</tbody>\n</table>\n");
+        }
+
+        html
+    }
+
+    /// Renders the tables as Markdown, each under a heading.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+
+        for table in &self.tables {
+            let _ = writeln!(markdown, "### {}\n", table.title);
+
+            markdown.push('|');
+
+            for column in std::iter::once("").chain(table.columns.iter().map(String::as_str)) {
+                let _ = write!(markdown, " {} |", escape_markdown(column));
+            }
+
+            markdown.push_str("\n|");
+
+            for _ in 0..=table.columns.len() {
+                markdown.push_str(" --- |");
+            }
+
+            markdown.push('\n');
+
+            for row in &table.rows {
+                let _ = write!(markdown, "| {} |", escape_markdown(&row.label));
+
+                for cell in &row.cells {
+                    let _ = write!(markdown, " {} |", escape_markdown(&cell_text(cell)));
+                }
+
+                markdown.push('\n');
+            }
+
+            markdown.push('\n');
+        }
+
+        markdown
+    }
+
+    /// Renders the tables as plain text with aligned columns, padded by the
+    /// width of their text in a terminal so that wide characters line up.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for (index, table) in self.tables.iter().enumerate() {
+            if index > 0 {
+                text.push('\n');
+            }
+
+            let _ = writeln!(text, "{}", table.title);
+
+            let mut lines: Vec<Vec<String>> = Vec::new();
+
+            if !is_list(table) {
+                lines.push(std::iter::once(String::new()).chain(table.columns.iter().cloned()).collect());
+            }
+
+            for row in &table.rows {
+                lines.push(std::iter::once(row.label.clone()).chain(row.cells.iter().map(cell_text)).collect());
+            }
+
+            let widths: Vec<usize> = (0..=table.columns.len())
+                .map(|column| lines.iter().map(|line| line[column].width()).max().unwrap_or(0))
+                .collect();
+
+            for line in lines {
+                let mut padded = String::new();
+
+                for (column, value) in line.iter().enumerate() {
+                    let _ = write!(padded, "{}{}  ", value, " ".repeat(widths[column] - value.width()));
+                }
+
+                let _ = writeln!(text, "{}", padded.trim_end());
+            }
+        }
+
+        text
+    }
+
+    /// Renders the paradigm as JSON, with the representation of
+    /// `quenya::json`.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String, crate::json::Error> {
+        crate::json::to_json(self)
+    }
+}
+
+/// The forms of a lexeme for a cell: those of the language's inflection, or
+/// else the lexeme's own forms with the features.
+fn inflect(language: &dyn Language, lexeme: &Lexeme, features: &Features) -> Vec<String> {
+    let forms = language.inflect(&lexeme.lemma, lexeme.pos, features);
+
+    if !forms.is_empty() {
+        return forms;
+    }
+
+    let mut forms: Vec<String> = Vec::new();
+
+    for form in &lexeme.forms {
+        if form.features.contains(features) && !forms.contains(&form.form) {
+            forms.push(form.form.clone());
+        }
+    }
+
+    forms
+}
+
+fn is_list(table: &Table) -> bool {
+    table.columns.len() == 1 && table.columns[0].is_empty()
+}
+
+fn cell_text(cell: &Cell) -> String {
+    if cell.forms.is_empty() {
+        return String::from("—");
+    }
+
+    cell.forms.join(", ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eng::English;
+    use crate::jpn::Japanese;
+    use crate::lexicon::Lexicon;
+    use crate::nld::Dutch;
+
+    #[test]
+    fn it_conjugates_english_verbs_by_rule() {
+        let paradigm = Paradigm::build(&English::new(None), &Lexeme::new("walk", Part::Verb));
+        let forms: Vec<String> = paradigm.tables[0].rows.iter().map(|row| cell_text(&row.cells[0])).collect();
+
+        assert_eq!(forms, vec!["walk", "walks", "walked", "walking", "walked"]);
+    }
+
+    #[test]
+    fn it_lays_out_dutch_verbs_by_person_and_tense() {
+        let mut lexicon = Lexicon::new();
+        lexicon.add_form("zoeken", Part::Verb, "zoekt", Features::parse("Mood=Ind|Number=Sing|Person=3|Tense=Pres|VerbForm=Fin").unwrap());
+        lexicon.add_form("zoeken", Part::Verb, "zocht", Features::parse("Mood=Ind|Number=Sing|Person=3|Tense=Past|VerbForm=Fin").unwrap());
+        lexicon.add_form("zoeken", Part::Verb, "gezocht", Features::parse("Tense=Past|VerbForm=Part").unwrap());
+
+        let lexeme = lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap().clone();
+        let paradigm = Paradigm::build(&Dutch::new(lexicon), &lexeme);
+
+        assert_eq!(paradigm.tables.len(), 2);
+        assert_eq!(paradigm.tables[0].columns, vec!["Present", "Past"]);
+        assert_eq!(paradigm.tables[0].rows[2].cells[1].forms, vec!["zocht"]);
+        assert!(paradigm.tables[0].rows[0].cells[0].forms.is_empty());
+        assert_eq!(paradigm.tables[1].rows[2].cells[0].forms, vec!["gezocht"]);
+    }
+
+    fn japanese() -> Paradigm {
+        let mut lexeme = Lexeme::new("食べる", Part::Verb);
+        lexeme.add_form("食べる", Features::parse("Polarity=Pos|Polite=Infm|Tense=Pres").unwrap());
+        lexeme.add_form("食べない", Features::parse("Polarity=Neg|Polite=Infm|Tense=Pres").unwrap());
+        lexeme.add_form("食べました", Features::parse("Polarity=Pos|Polite=Form|Tense=Past").unwrap());
+
+        Paradigm::build(&Japanese::new(None), &lexeme)
+    }
+
+    #[test]
+    fn it_renders_tables() {
+        let paradigm = japanese();
+
+        let html = paradigm.to_html();
+        let markdown = paradigm.to_markdown();
+        let text = paradigm.to_text();
+
+        assert!(html.starts_with("<table class=\"paradigm\" lang=\"jpn\">\n<caption>Conjugation</caption>"));
+        assert!(html.contains("<tr><th scope=\"row\">Past</th><td>—</td><td>—</td><td>食べました</td><td>—</td></tr>"));
+        assert!(markdown.contains("| Non-past | 食べる | 食べない | — | — |"));
+        assert!(markdown.contains("|  | Plain | Plain negative | Polite | Polite negative |\n| --- | --- | --- | --- | --- |"));
+        assert_eq!(text.lines().nth(2), Some("Non-past  食べる  食べない        —           —"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_renders_json() {
+        let paradigm = japanese();
+        let json: serde_json::Value = serde_json::from_str(&paradigm.to_json().unwrap()).unwrap();
+
+        assert_eq!(json["lemma"], "食べる");
+        assert_eq!(json["pos"], "verb");
+        assert_eq!(json["tables"][0]["rows"][0]["cells"][1]["forms"][0], "食べない");
+        assert_eq!(json["tables"][0]["rows"][0]["cells"][1]["features"]["Polarity"], "Neg");
+        assert_eq!(crate::json::from_json::<Paradigm>(&paradigm.to_json().unwrap()).unwrap(), paradigm);
+    }
+
+    #[test]
+    fn it_lists_forms_without_a_layout() {
+        let mut lexeme = Lexeme::new("snel", Part::Adjective);
+        lexeme.add_form("snelle", Features::parse("Degree=Pos").unwrap());
+        lexeme.add_form("sneller", Features::parse("Degree=Cmp").unwrap());
+
+        let paradigm = Paradigm::build(&Japanese::new(None), &lexeme);
+
+        assert_eq!(paradigm.tables[0].rows.len(), 2);
+        assert_eq!(paradigm.tables[0].rows[1].label, "Degree=Cmp");
+    }
+}
@@ -0,0 +1,926 @@
+// Grammatical patterns: sequences of token tests, written like
+// `aux:[Lemma=zijn|hebben] [UPOS=ADV]* verb:[VerbForm=Part]`, that are matched
+// over the tokens of an annotated text.
+//
+// An element is one of
+//
+// - `[Key=value ...]`, a token whose fields all match. Keys are `Form`,
+//   `Lemma`, `UPOS` and `XPOS`, or else feature names. Values may list
+//   alternatives as `NOUN|PROPN`, or be a regular expression as `/ge.*d/`
+//   that must match the whole value. `Key!=value` negates a test, and `[]`
+//   matches any token;
+// - a word such as `の` or `"de"`, a token with that form;
+// - `( ... )`, a group of elements.
+//
+// Elements may be followed by `?`, `*`, `+` or `{m,n}`, and preceded by
+// `name:` to capture the tokens they match.
+
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+use regex::Regex;
+use crate::omni::Omniglot;
+use crate::pipeline::{PipelineError, Stage};
+use crate::text::{AnnotatedText, SENTENCE_LAYER, Span, Token};
+
+/// The layer `PatternSet` adds its matches to.
+pub const PATTERN_LAYER: &str = "patterns";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternError {
+    /// Character offset in the pattern.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pattern position {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Field {
+    Form,
+    Lemma,
+    Upos,
+    Xpos,
+    Feature(String),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Value {
+    OneOf(Vec<String>),
+    Regex(Regex),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Constraint {
+    pub(crate) field: Field,
+    pub(crate) value: Value,
+    pub(crate) negated: bool,
+}
+
+impl Constraint {
+    fn matches(&self, text: &AnnotatedText, token: &Token) -> bool {
+        let actual = match &self.field {
+            Field::Form => Some(text.word_form(token)),
+            Field::Lemma => token.lemma.as_deref(),
+            Field::Upos => token.upos.as_deref(),
+            Field::Xpos => token.xpos.as_deref(),
+            Field::Feature(name) => token.features.get(name),
+        };
+
+        let matches = actual.is_some_and(|actual| match &self.value {
+            Value::OneOf(values) => values.iter().any(|value| value == actual),
+            Value::Regex(regex) => regex.is_match(actual),
+        });
+
+        matches != self.negated
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Element {
+    Token(Vec<Constraint>),
+    Group(Vec<Item>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Item {
+    pub(crate) element: Element,
+    pub(crate) min: usize,
+    pub(crate) max: Option<usize>,
+    pub(crate) capture: Option<String>,
+}
+
+/// Tokens captured by a named element of a pattern.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capture {
+    pub name: String,
+    /// The text the tokens cover, labelled with the name of the capture.
+    pub span: Span,
+    /// Indices into the tokens of the text.
+    pub tokens: Range<usize>,
+}
+
+/// A match of a pattern.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternMatch {
+    /// The text the match covers, labelled with the label of the pattern.
+    pub span: Span,
+    pub tokens: Range<usize>,
+    pub captures: Vec<Capture>,
+}
+
+impl PatternMatch {
+    /// The first capture with the given name.
+    pub fn capture(&self, name: &str) -> Option<&Capture> {
+        self.captures.iter().find(|capture| capture.name == name)
+    }
+}
+
+/// A compiled pattern, which can be matched against any number of texts.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    label: String,
+    source: String,
+    items: Vec<Item>,
+    program: Vec<Instruction>,
+}
+
+impl Pattern {
+    pub fn compile(label: &str, source: &str) -> Result<Pattern, PatternError> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            position: 0,
+        };
+
+        let items = parser.sequence(None)?;
+
+        if items.is_empty() {
+            return Err(parser.error("expected at least one element"));
+        }
+
+        let mut program = Vec::new();
+
+        compile_items(&items, &mut program);
+        program.push(Instruction::Match);
+
+        Ok(Pattern {
+            label: String::from(label),
+            source: String::from(source),
+            items,
+            program,
+        })
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub(crate) fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// Finds the matches in a text from left to right, taking the longest
+    /// match at each token and not letting matches overlap. Matches stay
+    /// within one sentence if the text has a sentence layer.
+    pub fn find_all(&self, text: &AnnotatedText) -> Vec<PatternMatch> {
+        let mut matches: Vec<PatternMatch> = Vec::new();
+
+        for range in sentence_ranges(text) {
+            let mut start = range.start;
+
+            while start < range.end {
+                match self.match_at(text, start, range.end) {
+                    Some(found) => {
+                        start = found.tokens.end;
+                        matches.push(found);
+                    },
+                    None => start += 1,
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// Matches the pattern at a token, ending before the token at `limit`.
+    /// Of the matches that end last, it takes the one that prefers longer
+    /// repetitions from the left.
+    fn match_at(&self, text: &AnnotatedText, start: usize, limit: usize) -> Option<PatternMatch> {
+        let matcher = Matcher {
+            program: &self.program,
+            text,
+            limit,
+        };
+
+        let mut visited = vec![false; self.program.len()];
+        let mut threads = Vec::new();
+        let mut found: Option<(usize, Captures)> = None;
+
+        matcher.add(&mut threads, &mut visited, 0, start, None, None);
+
+        for position in start.. {
+            if threads.is_empty() {
+                break;
+            }
+
+            let mut next = Vec::new();
+            let mut matched = false;
+
+            visited.fill(false);
+
+            for thread in &threads {
+                match &self.program[thread.pc] {
+                    Instruction::Match if position > start && !matched => {
+                        found = Some((position, thread.captures.clone()));
+                        matched = true;
+                    },
+                    Instruction::Token(constraints) if matcher.matches(constraints, position) => {
+                        matcher.add(&mut next, &mut visited, thread.pc + 1, position + 1, thread.starts.clone(), thread.captures.clone());
+                    },
+                    _ => {},
+                }
+            }
+
+            threads = next;
+        }
+
+        let (end, mut stack) = found?;
+        let mut captures = Vec::new();
+
+        while let Some(link) = stack {
+            let (name, tokens) = &link.value;
+
+            if !tokens.is_empty() {
+                captures.push(Capture {
+                    name: String::from(*name),
+                    span: token_span(text, tokens.clone(), name),
+                    tokens: tokens.clone(),
+                });
+            }
+
+            stack = link.next.clone();
+        }
+
+        captures.reverse();
+
+        Some(PatternMatch {
+            span: token_span(text, start..end, &self.label),
+            tokens: start..end,
+            captures,
+        })
+    }
+
+    /// Adds the span of every match to a layer.
+    pub fn annotate(&self, text: &mut AnnotatedText, layer: &str) {
+        for found in self.find_all(text) {
+            text.add_span(layer, found.span);
+        }
+    }
+}
+
+/// The token ranges of the sentences, or of the whole text.
+fn sentence_ranges(text: &AnnotatedText) -> Vec<Range<usize>> {
+    let whole = 0..text.tokens.len();
+
+    let sentences = match text.layer(SENTENCE_LAYER) {
+        Some(sentences) => sentences.spans(),
+        None => return vec![whole],
+    };
+
+    sentences.iter()
+        .map(|sentence| {
+            let start = text.tokens.partition_point(|token| token.start < sentence.start);
+
+            start..start + text.tokens_of(sentence).len()
+        })
+        .collect()
+}
+
+fn token_span(text: &AnnotatedText, tokens: Range<usize>, label: &str) -> Span {
+    Span::new(text.tokens[tokens.start].start, text.tokens[tokens.end - 1].end, label)
+}
+
+/// An instruction of the program a pattern compiles to.
+#[derive(Clone, Debug)]
+enum Instruction {
+    /// Matches a token whose fields meet every constraint.
+    Token(Vec<Constraint>),
+    /// Continues at both instructions, preferring the first.
+    Split(usize, usize),
+    Jump(usize),
+    /// Starts a capture at the current token.
+    Open,
+    /// Ends the innermost open capture.
+    Close(String),
+    Match,
+}
+
+fn compile_items(items: &[Item], program: &mut Vec<Instruction>) {
+    for item in items {
+        compile_item(item, program);
+    }
+}
+
+fn compile_item(item: &Item, program: &mut Vec<Instruction>) {
+    if item.capture.is_some() {
+        program.push(Instruction::Open);
+    }
+
+    for _ in 0..item.min {
+        compile_element(&item.element, program);
+    }
+
+    match item.max {
+        Some(max) => {
+            for _ in item.min..max {
+                let split = program.len();
+
+                program.push(Instruction::Split(0, 0));
+                compile_element(&item.element, program);
+                program[split] = Instruction::Split(split + 1, program.len());
+            }
+        },
+        None => {
+            let split = program.len();
+
+            program.push(Instruction::Split(0, 0));
+            compile_element(&item.element, program);
+            program.push(Instruction::Jump(split));
+            program[split] = Instruction::Split(split + 1, program.len());
+        },
+    }
+
+    if let Some(name) = &item.capture {
+        program.push(Instruction::Close(name.clone()));
+    }
+}
+
+fn compile_element(element: &Element, program: &mut Vec<Instruction>) {
+    match element {
+        Element::Token(constraints) => program.push(Instruction::Token(constraints.clone())),
+        Element::Group(items) => compile_items(items, program),
+    }
+}
+
+/// A persistent stack, so that threads share what they have in common
+/// instead of copying it.
+type Stack<T> = Option<Rc<Link<T>>>;
+
+struct Link<T> {
+    value: T,
+    next: Stack<T>,
+}
+
+fn push<T>(stack: &Stack<T>, value: T) -> Stack<T> {
+    Some(Rc::new(Link {
+        value,
+        next: stack.clone(),
+    }))
+}
+
+/// Names and tokens of the captures a thread has closed, last first.
+type Captures<'p> = Stack<(&'p str, Range<usize>)>;
+
+/// A thread of the matcher, waiting at a `Token` or `Match` instruction.
+struct Thread<'p> {
+    pc: usize,
+    /// The starts of the open captures.
+    starts: Stack<usize>,
+    captures: Captures<'p>,
+}
+
+/// Runs a program over the tokens in lockstep, keeping at most one thread
+/// per instruction, so that matching takes time linear in the tokens times
+/// the length of the program.
+struct Matcher<'p, 'a> {
+    program: &'p [Instruction],
+    text: &'a AnnotatedText,
+    limit: usize,
+}
+
+impl<'p, 'a> Matcher<'p, 'a> {
+    fn matches(&self, constraints: &[Constraint], position: usize) -> bool {
+        if position >= self.limit {
+            return false;
+        }
+
+        let token = &self.text.tokens[position];
+
+        constraints.iter().all(|constraint| constraint.matches(self.text, token))
+    }
+
+    /// Adds a thread at an instruction, following jumps, splits and captures
+    /// in order of preference. Instructions already visited at this position
+    /// belong to a preferred thread.
+    #[allow(clippy::too_many_arguments)]
+    fn add(&self, threads: &mut Vec<Thread<'p>>, visited: &mut [bool], pc: usize, position: usize, starts: Stack<usize>, captures: Captures<'p>) {
+        if visited[pc] {
+            return;
+        }
+
+        visited[pc] = true;
+
+        match &self.program[pc] {
+            Instruction::Split(first, second) => {
+                self.add(threads, visited, *first, position, starts.clone(), captures.clone());
+                self.add(threads, visited, *second, position, starts, captures);
+            },
+            Instruction::Jump(target) => self.add(threads, visited, *target, position, starts, captures),
+            Instruction::Open => self.add(threads, visited, pc + 1, position, push(&starts, position), captures),
+            Instruction::Close(name) => {
+                let (start, rest) = match &starts {
+                    Some(link) => (link.value, link.next.clone()),
+                    None => (position, None),
+                };
+
+                self.add(threads, visited, pc + 1, position, rest, push(&captures, (name.as_str(), start..position)));
+            },
+            Instruction::Token(_) | Instruction::Match => threads.push(Thread {
+                pc,
+                starts,
+                captures,
+            }),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> PatternError {
+        PatternError {
+            position: self.position,
+            message: String::from(message),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), PatternError> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected '{}'", expected)));
+        }
+
+        self.position += 1;
+
+        Ok(())
+    }
+
+    /// Elements up to the end of the pattern or a closing bracket.
+    fn sequence(&mut self, close: Option<char>) -> Result<Vec<Item>, PatternError> {
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                None if close.is_none() => return Ok(items),
+                None => return Err(self.error("unclosed group")),
+                Some(c) if Some(c) == close => return Ok(items),
+                Some(_) => items.push(self.item()?),
+            }
+        }
+    }
+
+    fn item(&mut self) -> Result<Item, PatternError> {
+        let mut capture = None;
+
+        let element = match self.peek() {
+            Some('[') | Some('(') | Some('"') => self.element()?,
+            _ => {
+                let word = self.word();
+
+                if word.is_empty() {
+                    return Err(self.error("expected an element"));
+                }
+
+                if self.peek() == Some(':') {
+                    self.position += 1;
+                    capture = Some(word);
+
+                    self.element()?
+                } else {
+                    Element::Token(vec![form_constraint(word)])
+                }
+            },
+        };
+
+        let (min, max) = self.quantifier()?;
+
+        Ok(Item {
+            element,
+            min,
+            max,
+            capture,
+        })
+    }
+
+    fn element(&mut self) -> Result<Element, PatternError> {
+        match self.peek() {
+            Some('[') => {
+                self.position += 1;
+
+                let constraints = self.constraints()?;
+
+                self.expect(']')?;
+
+                Ok(Element::Token(constraints))
+            },
+            Some('(') => {
+                self.position += 1;
+
+                let items = self.sequence(Some(')'))?;
+
+                if items.is_empty() {
+                    return Err(self.error("empty group"));
+                }
+
+                self.expect(')')?;
+
+                Ok(Element::Group(items))
+            },
+            Some('"') => Ok(Element::Token(vec![form_constraint(self.quoted()?)])),
+            _ => Err(self.error("expected '[', '(' or a quoted word")),
+        }
+    }
+
+    /// A bare word, up to whitespace or a character with a meaning.
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || "[]()\"?*+{}:".contains(c) {
+                break;
+            }
+
+            word.push(c);
+            self.position += 1;
+        }
+
+        word
+    }
+
+    fn quoted(&mut self) -> Result<String, PatternError> {
+        self.delimited('"')
+    }
+
+    /// Text up to an unescaped `delimiter`, after an opening one.
+    fn delimited(&mut self, delimiter: char) -> Result<String, PatternError> {
+        self.expect(delimiter)?;
+
+        let mut text = String::new();
+
+        loop {
+            match self.peek() {
+                None => return Err(self.error(&format!("expected closing '{}'", delimiter))),
+                Some('\\') if self.chars.get(self.position + 1) == Some(&delimiter) => {
+                    text.push(delimiter);
+                    self.position += 2;
+                },
+                Some(c) if c == delimiter => {
+                    self.position += 1;
+
+                    return Ok(text);
+                },
+                Some(c) => {
+                    text.push(c);
+                    self.position += 1;
+                },
+            }
+        }
+    }
+
+    fn constraints(&mut self) -> Result<Vec<Constraint>, PatternError> {
+        let mut constraints = Vec::new();
+
+        loop {
+            while self.peek().is_some_and(|c| c.is_whitespace() || c == '&') {
+                self.position += 1;
+            }
+
+            if self.peek() == Some(']') || self.peek().is_none() {
+                return Ok(constraints);
+            }
+
+            let start = self.position;
+            let mut key = String::new();
+
+            while let Some(c) = self.peek() {
+                if c == '=' || c == '!' || c == ']' || c.is_whitespace() {
+                    break;
+                }
+
+                key.push(c);
+                self.position += 1;
+            }
+
+            let negated = self.peek() == Some('!');
+
+            if negated {
+                self.position += 1;
+            }
+
+            if key.is_empty() || self.peek() != Some('=') {
+                self.position = start;
+
+                return Err(self.error("expected Key=value or Key!=value"));
+            }
+
+            self.position += 1;
+
+            let value = match self.peek() {
+                Some('/') => {
+                    let position = self.position;
+                    let expression = self.delimited('/')?;
+
+                    match Regex::new(&format!("^(?:{})$", expression)) {
+                        Ok(regex) => Value::Regex(regex),
+                        Err(error) => return Err(PatternError {
+                            position,
+                            message: error.to_string(),
+                        }),
+                    }
+                },
+                Some('"') => Value::OneOf(vec![self.quoted()?]),
+                _ => {
+                    let mut value = String::new();
+
+                    while let Some(c) = self.peek() {
+                        if c == ']' || c == '&' || c.is_whitespace() {
+                            break;
+                        }
+
+                        value.push(c);
+                        self.position += 1;
+                    }
+
+                    if value.is_empty() {
+                        return Err(self.error("expected a value"));
+                    }
+
+                    Value::OneOf(value.split('|').map(String::from).collect())
+                },
+            };
+
+            constraints.push(Constraint {
+                field: field(&key),
+                value,
+                negated,
+            });
+        }
+    }
+
+    fn quantifier(&mut self) -> Result<(usize, Option<usize>), PatternError> {
+        let quantifier = match self.peek() {
+            Some('?') => (0, Some(1)),
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('{') => return self.repetition(),
+            _ => return Ok((1, Some(1))),
+        };
+
+        self.position += 1;
+
+        Ok(quantifier)
+    }
+
+    /// A repetition written as `{n}`, `{m,}` or `{m,n}`.
+    fn repetition(&mut self) -> Result<(usize, Option<usize>), PatternError> {
+        self.expect('{')?;
+
+        let mut bounds = String::new();
+
+        while let Some(c) = self.peek() {
+            if c == '}' {
+                break;
+            }
+
+            bounds.push(c);
+            self.position += 1;
+        }
+
+        let parse = |bound: &str| bound.trim().parse::<usize>().ok();
+
+        let repetition = match bounds.split_once(',') {
+            Some((min, max)) if max.trim().is_empty() => parse(min).map(|min| (min, None)),
+            Some((min, max)) => parse(min).zip(parse(max)).map(|(min, max)| (min, Some(max))),
+            None => parse(&bounds).map(|count| (count, Some(count))),
+        };
+
+        match repetition {
+            Some((min, Some(max))) if max < min => Err(self.error("repetition maximum is below its minimum")),
+            Some(repetition) => {
+                self.expect('}')?;
+
+                Ok(repetition)
+            },
+            None => Err(self.error("expected a repetition such as {1,3}")),
+        }
+    }
+}
+
+fn field(key: &str) -> Field {
+    match key.to_lowercase().as_str() {
+        "form" => Field::Form,
+        "lemma" => Field::Lemma,
+        "upos" => Field::Upos,
+        "xpos" => Field::Xpos,
+        _ => Field::Feature(String::from(key)),
+    }
+}
+
+fn form_constraint(form: String) -> Constraint {
+    Constraint {
+        field: Field::Form,
+        value: Value::OneOf(vec![form]),
+        negated: false,
+    }
+}
+
+/// Labelled patterns, read from lines written as `label = pattern`. As a
+/// pipeline stage it adds its matches to `PATTERN_LAYER`.
+#[derive(Clone, Debug, Default)]
+pub struct PatternSet {
+    patterns: Vec<Pattern>,
+}
+
+impl PatternSet {
+    pub fn new() -> PatternSet {
+        PatternSet {
+            patterns: Vec::new(),
+        }
+    }
+
+    pub fn from_config_file(path: &Path) -> Result<PatternSet, PipelineError> {
+        let config = fs::read_to_string(path).map_err(|error| PipelineError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        PatternSet::from_config(&config)
+    }
+
+    /// Reads one `label = pattern` per line, skipping empty lines and lines
+    /// starting with `#`.
+    pub fn from_config(config: &str) -> Result<PatternSet, PipelineError> {
+        let mut patterns = PatternSet::new();
+
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: String| PipelineError::Config {
+                line: number + 1,
+                message,
+            };
+
+            let (label, source) = line.split_once('=').ok_or_else(|| error(String::from("expected label = pattern")))?;
+            let pattern = Pattern::compile(label.trim(), source.trim()).map_err(|pattern_error| error(pattern_error.to_string()))?;
+
+            patterns.add(pattern);
+        }
+
+        Ok(patterns)
+    }
+
+    pub fn add(&mut self, pattern: Pattern) {
+        self.patterns.push(pattern);
+    }
+
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    /// The matches of every pattern, in the order of the patterns.
+    pub fn find_all(&self, text: &AnnotatedText) -> Vec<PatternMatch> {
+        self.patterns.iter().flat_map(|pattern| pattern.find_all(text)).collect()
+    }
+}
+
+impl Stage for PatternSet {
+    fn name(&self) -> &str {
+        "patterns"
+    }
+
+    fn process(&self, _omni: &Omniglot, text: &mut AnnotatedText) {
+        for pattern in &self.patterns {
+            pattern.annotate(text, PATTERN_LAYER);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::Features;
+    use crate::segmentation::SegmentKind;
+
+    /// A text of `form/lemma/UPOS/features` words separated by spaces.
+    fn tagged(words: &str) -> AnnotatedText {
+        let mut text = AnnotatedText::new("");
+        let mut tokens = Vec::new();
+
+        for word in words.split(' ') {
+            let fields: Vec<&str> = word.split('/').collect();
+
+            if !text.text.is_empty() {
+                text.text.push(' ');
+            }
+
+            let mut token = Token::new(text.text.len(), text.text.len() + fields[0].len(), SegmentKind::Word);
+            token.lemma = Some(String::from(fields[1]));
+            token.upos = Some(String::from(fields[2]));
+            token.features = fields.get(3).and_then(|features| Features::parse(features)).unwrap_or_default();
+
+            text.text.push_str(fields[0]);
+            tokens.push(token);
+        }
+
+        text.tokens = tokens;
+
+        text
+    }
+
+    #[test]
+    fn it_matches_japanese_noun_phrases() {
+        let text = tagged("東京/東京/PROPN の/の/ADP 大学/大学/NOUN の/の/ADP 先生/先生/NOUN");
+        let pattern = Pattern::compile("genitive", "[UPOS=NOUN|PROPN] (の [UPOS=NOUN])+").unwrap();
+
+        let matches = pattern.find_all(&text);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(text.span_text(&matches[0].span), "東京 の 大学 の 先生");
+        assert_eq!(matches[0].span.label, "genitive");
+    }
+
+    #[test]
+    fn it_captures_dutch_perfect_tenses() {
+        let text = tagged("Hij/hij/PRON heeft/hebben/AUX het/het/PRON niet/niet/ADV gezocht/zoeken/VERB/VerbForm=Part en/en/CCONJ is/zijn/AUX gegaan/gaan/VERB/VerbForm=Part");
+        let pattern = Pattern::compile("perfect", "aux:[Lemma=zijn|hebben] [UPOS!=VERB]* verb:[VerbForm=Part]").unwrap();
+
+        let matches = pattern.find_all(&text);
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(text.span_text(&matches[0].capture("aux").unwrap().span), "heeft");
+        assert_eq!(text.span_text(&matches[0].capture("verb").unwrap().span), "gezocht");
+        assert_eq!(text.span_text(&matches[1].span), "is gegaan");
+    }
+
+    #[test]
+    fn it_matches_forms_by_regex_and_optional_elements() {
+        let text = tagged("de/de/DET grote/groot/ADJ hond/hond/NOUN en/en/CCONJ de/de/DET kat/kat/NOUN");
+        let pattern = Pattern::compile("np", "\"de\" adjective:[Form=/gr.*/]? [UPOS=NOUN]").unwrap();
+
+        let matches = pattern.find_all(&text);
+
+        assert_eq!(matches.iter().map(|found| text.span_text(&found.span)).collect::<Vec<&str>>(), vec!["de grote hond", "de kat"]);
+        assert_eq!(matches[1].capture("adjective"), None);
+        assert_eq!(Pattern::compile("x", "[]{2}").unwrap().find_all(&text).len(), 3);
+    }
+
+    #[test]
+    fn it_stays_within_sentences() {
+        let mut text = tagged("kat/kat/NOUN ./././PUNCT hond/hond/NOUN");
+        text.add_span(SENTENCE_LAYER, Span::new(0, 5, ""));
+        text.add_span(SENTENCE_LAYER, Span::new(6, 10, ""));
+
+        assert_eq!(Pattern::compile("x", "[UPOS=NOUN] [] [UPOS=NOUN]").unwrap().find_all(&text).len(), 0);
+    }
+
+    #[test]
+    fn it_takes_the_longest_match() {
+        let text = tagged("de/de/DET kat/kat/NOUN");
+        let pattern = Pattern::compile("x", "det:\"de\"? np:(\"de\" [UPOS=NOUN])?").unwrap();
+
+        let matches = pattern.find_all(&text);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].tokens, 0..2);
+        assert_eq!(matches[0].capture("det"), None);
+        assert_eq!(matches[0].capture("np").unwrap().tokens, 0..2);
+    }
+
+    #[test]
+    fn it_matches_nested_repetitions_in_linear_time() {
+        let words = vec!["kat/kat/NOUN"; 200].join(" ");
+        let text = tagged(&words);
+        let pattern = Pattern::compile("x", "([UPOS=NOUN]+)+ [UPOS=VERB]").unwrap();
+
+        assert!(pattern.find_all(&text).is_empty());
+    }
+
+    #[test]
+    fn it_reports_errors_with_positions() {
+        assert_eq!(Pattern::compile("x", "[UPOS=NOUN").unwrap_err().position, 10);
+        assert_eq!(Pattern::compile("x", "[Form=/(/]").unwrap_err().position, 6);
+        assert!(Pattern::compile("x", "(de").is_err());
+        assert!(Pattern::compile("x", "de{3,1}").is_err());
+        assert!(PatternSet::from_config("np = de [UPOS=NOUN]\n\nbroken [").is_err());
+    }
+}
@@ -0,0 +1,470 @@
+// Annotation pipelines: a sequence of stages that each add to an
+// AnnotatedText, such as tokens, analyses or layers of named entities.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::language::Analysis;
+use crate::normalize::Normalization;
+use crate::omni::Omniglot;
+use crate::pattern::PatternSet;
+use crate::segmentation::{SegmentKind, segment};
+use crate::sentences::SentenceSplitter;
+use crate::stem::Stemmer;
+use crate::text::{AnnotatedText, Token};
+
+/// One step of a pipeline. Stages get the registry to reach the language
+/// modules, and may change tokens or add layers.
+pub trait Stage: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn process(&self, omni: &Omniglot, text: &mut AnnotatedText);
+}
+
+/// Splits the text into tokens, each with the language of its script run.
+/// Numbers and punctuation are tagged `NUM` and `PUNCT`. A normalised text is
+/// split instead of the original, with the offsets mapped back. Segments that
+/// map back into the same original grapheme, such as the `1`, `⁄` and `2`
+/// that NFKC makes of `½`, become a single token.
+pub struct Tokenizer;
+
+impl Stage for Tokenizer {
+    fn name(&self) -> &str {
+        "tokenizer"
+    }
+
+    fn process(&self, omni: &Omniglot, text: &mut AnnotatedText) {
+        let normalized = text.normalized.take();
+
+        let source = match &normalized {
+            Some(normalized) => &normalized.text,
+            None => &text.text,
+        };
+
+        let segments = match omni.segment(source) {
+            Ok(segments) => segments,
+            Err(_) => segment(source),
+        };
+
+        // The original span, kind and language of every token, with the
+        // stretch of the source it was split from.
+        let mut pieces: Vec<(Token, usize, usize)> = Vec::new();
+
+        for segment in segments {
+            let (start, end) = match &normalized {
+                Some(normalized) => normalized.map.to_original(segment.start, segment.end),
+                None => (segment.start, segment.end),
+            };
+
+            if let Some((token, _, source_end)) = pieces.last_mut() {
+                if start < token.end {
+                    token.end = token.end.max(end);
+                    token.kind = merged_kind(token.kind, segment.kind);
+                    *source_end = segment.end;
+                    continue;
+                }
+            }
+
+            let mut token = Token::new(start, end, segment.kind);
+            token.language = segment.language;
+
+            pieces.push((token, segment.start, segment.end));
+        }
+
+        for (mut token, source_start, source_end) in pieces {
+            if token.kind != SegmentKind::Word {
+                token.lemma = Some(String::from(&source[source_start..source_end]));
+                token.upos = Some(String::from(match token.kind {
+                    SegmentKind::Number => "NUM",
+                    _ => "PUNCT",
+                }));
+            }
+
+            text.tokens.push(token);
+        }
+
+        text.normalized = normalized;
+    }
+}
+
+/// The kind of a token made of segments of two kinds: a word if either is
+/// one, or else a number if either is one.
+fn merged_kind(a: SegmentKind, b: SegmentKind) -> SegmentKind {
+    match (a, b) {
+        (SegmentKind::Word, _) | (_, SegmentKind::Word) => SegmentKind::Word,
+        (SegmentKind::Number, _) | (_, SegmentKind::Number) => SegmentKind::Number,
+        _ => a,
+    }
+}
+
+/// Stores every analysis the language module of a word finds as one of its
+/// alternatives, leaving the choice to a disambiguator.
+pub struct Analyzer;
+
+impl Stage for Analyzer {
+    fn name(&self) -> &str {
+        "analyzer"
+    }
+
+    fn process(&self, omni: &Omniglot, text: &mut AnnotatedText) {
+        for index in 0..text.tokens.len() {
+            let token = &text.tokens[index];
+
+            if token.kind != SegmentKind::Word {
+                continue;
+            }
+
+            let language = token.language.as_deref().and_then(|code| omni.get(code).ok());
+
+            if let Some(language) = language {
+                text.tokens[index].alternatives = language.analyze_word(text.analysis_form(token));
+            }
+        }
+    }
+}
+
+/// Assigns every analysed word its most likely analysis: the one whose lexeme
+/// is most frequent in the lexicon of its language, or else the first one.
+pub struct Disambiguator;
+
+impl Stage for Disambiguator {
+    fn name(&self) -> &str {
+        "disambiguator"
+    }
+
+    fn process(&self, omni: &Omniglot, text: &mut AnnotatedText) {
+        for token in &mut text.tokens {
+            if token.alternatives.is_empty() || token.lemma.is_some() {
+                continue;
+            }
+
+            let lexicon = token.language.as_deref()
+                .and_then(|code| omni.get(code).ok())
+                .and_then(|language| language.lexicon());
+
+            let mut analyses: Vec<Analysis> = token.alternatives.drain(..).collect();
+
+            if let Some(lexicon) = lexicon {
+                let frequency = |analysis: &Analysis| {
+                    analysis.pos
+                        .and_then(|pos| lexicon.find_lexeme_by_lemma(&analysis.lemma, pos))
+                        .map_or(0, |lexeme| lexeme.frequency)
+                };
+
+                // A stable sort keeps the order of the language module for ties.
+                analyses.sort_by_key(|analysis| std::cmp::Reverse(frequency(analysis)));
+            }
+
+            token.assign(analyses);
+        }
+    }
+}
+
+/// How long a stage took to run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timing {
+    pub stage: String,
+    pub duration: Duration,
+}
+
+#[derive(Debug)]
+pub enum PipelineError {
+    Io { path: PathBuf, error: io::Error },
+    Config { line: usize, message: String },
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::Io { path, error } => write!(f, "could not read {}: {}", path.display(), error),
+            PipelineError::Config { line, message } => write!(f, "pipeline line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+/// Creates a stage from the optional argument of its configuration line.
+pub type StageFactory = Box<dyn Fn(Option<&str>) -> Result<Box<dyn Stage>, String> + Send + Sync>;
+
+/// The stages a pipeline configuration can name.
+pub struct StageRegistry {
+    factories: HashMap<String, StageFactory>,
+}
+
+impl StageRegistry {
+    /// A registry of the built-in stages: `normalizer`, `tokenizer`,
+    /// `sentences`, `analyzer`, `disambiguator`, `stemmer` and `patterns`. The normalizer
+    /// takes its steps as argument, as in `normalizer = nfkc, width, case`,
+    /// and `patterns` the path of a file of patterns.
+    pub fn new() -> StageRegistry {
+        let mut registry = StageRegistry {
+            factories: HashMap::new(),
+        };
+
+        registry.register("normalizer", Box::new(|argument| {
+            Normalization::parse(argument.unwrap_or("nfc")).map(|normalization| Box::new(normalization) as Box<dyn Stage>)
+        }));
+        registry.register("tokenizer", Box::new(|_| Ok(Box::new(Tokenizer) as Box<dyn Stage>)));
+        registry.register("sentences", Box::new(|_| Ok(Box::new(SentenceSplitter::new()) as Box<dyn Stage>)));
+        registry.register("analyzer", Box::new(|_| Ok(Box::new(Analyzer) as Box<dyn Stage>)));
+        registry.register("disambiguator", Box::new(|_| Ok(Box::new(Disambiguator) as Box<dyn Stage>)));
+        registry.register("stemmer", Box::new(|_| Ok(Box::new(Stemmer) as Box<dyn Stage>)));
+        registry.register("patterns", Box::new(|argument| match argument {
+            Some(path) => PatternSet::from_config_file(Path::new(path))
+                .map(|patterns| Box::new(patterns) as Box<dyn Stage>)
+                .map_err(|error| error.to_string()),
+            None => Err(String::from("expected a file of patterns")),
+        }));
+
+        registry
+    }
+
+    pub fn register(&mut self, name: &str, factory: StageFactory) {
+        self.factories.insert(String::from(name), factory);
+    }
+
+    fn create(&self, name: &str, argument: Option<&str>) -> Result<Box<dyn Stage>, String> {
+        match self.factories.get(name) {
+            Some(factory) => factory(argument),
+            None => Err(format!("unknown stage '{}'", name)),
+        }
+    }
+}
+
+impl Default for StageRegistry {
+    fn default() -> StageRegistry {
+        StageRegistry::new()
+    }
+}
+
+/// A sequence of stages that annotate a text in order.
+#[derive(Default)]
+pub struct Pipeline {
+    stages: Vec<Box<dyn Stage>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline {
+            stages: Vec::new(),
+        }
+    }
+
+    /// The pipeline behind `quenya::annotate`: tokenizer, sentence splitter,
+    /// analyzer and disambiguator.
+    pub fn standard() -> Pipeline {
+        Pipeline::new()
+            .with(Box::new(Tokenizer))
+            .with(Box::new(SentenceSplitter::new()))
+            .with(Box::new(Analyzer))
+            .with(Box::new(Disambiguator))
+    }
+
+    /// Reads a configuration file with one stage per line, written as `name`
+    /// or `name = argument`.
+    pub fn from_config_file(path: &Path, registry: &StageRegistry) -> Result<Pipeline, PipelineError> {
+        let config = fs::read_to_string(path).map_err(|error| PipelineError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        Pipeline::from_config(&config, registry)
+    }
+
+    pub fn from_config(config: &str, registry: &StageRegistry) -> Result<Pipeline, PipelineError> {
+        let mut pipeline = Pipeline::new();
+
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, argument) = match line.split_once('=') {
+                Some((name, argument)) => (name.trim(), Some(argument.trim())),
+                None => (line, None),
+            };
+
+            let stage = registry.create(name, argument).map_err(|message| PipelineError::Config {
+                line: number + 1,
+                message,
+            })?;
+
+            pipeline.stages.push(stage);
+        }
+
+        Ok(pipeline)
+    }
+
+    /// Appends a stage.
+    pub fn with(mut self, stage: Box<dyn Stage>) -> Pipeline {
+        self.stages.push(stage);
+
+        self
+    }
+
+    pub fn stages(&self) -> Vec<&str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    pub fn run(&self, omni: &Omniglot, text: &str) -> AnnotatedText {
+        let mut annotated = AnnotatedText::new(text);
+
+        for stage in &self.stages {
+            stage.process(omni, &mut annotated);
+        }
+
+        annotated
+    }
+
+    /// Runs the pipeline and records how long each stage took.
+    pub fn run_timed(&self, omni: &Omniglot, text: &str) -> (AnnotatedText, Vec<Timing>) {
+        let mut annotated = AnnotatedText::new(text);
+        let mut timings: Vec<Timing> = Vec::new();
+
+        for stage in &self.stages {
+            let start = Instant::now();
+
+            stage.process(omni, &mut annotated);
+
+            timings.push(Timing {
+                stage: String::from(stage.name()),
+                duration: start.elapsed(),
+            });
+        }
+
+        (annotated, timings)
+    }
+}
+
+/// Annotates text with the standard pipeline and the language modules of
+/// `omni`.
+pub fn annotate_with(omni: &Omniglot, text: &str) -> AnnotatedText {
+    Pipeline::standard().run(omni, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wn::pos::Part;
+    use crate::features::Features;
+    use crate::nld::Dutch;
+    use crate::lexicon::Lexicon;
+    use crate::text::{SENTENCE_LAYER, Span};
+
+    /// Marks known place names in a layer of their own.
+    struct Gazetteer {
+        places: Vec<String>,
+    }
+
+    impl Stage for Gazetteer {
+        fn name(&self) -> &str {
+            "gazetteer"
+        }
+
+        fn process(&self, _omni: &Omniglot, text: &mut AnnotatedText) {
+            let mut spans = Vec::new();
+
+            for token in &text.tokens {
+                if self.places.iter().any(|place| place == text.token_text(token)) {
+                    spans.push(Span::new(token.start, token.end, "PLACE"));
+                }
+            }
+
+            for span in spans {
+                text.add_span("places", span);
+            }
+        }
+    }
+
+    fn registry() -> StageRegistry {
+        let mut registry = StageRegistry::new();
+
+        registry.register("gazetteer", Box::new(|argument| match argument {
+            Some(places) => Ok(Box::new(Gazetteer {
+                places: places.split(',').map(|place| String::from(place.trim())).collect(),
+            }) as Box<dyn Stage>),
+            None => Err(String::from("expected a list of places")),
+        }));
+
+        registry
+    }
+
+    #[test]
+    fn it_annotates_with_the_standard_pipeline() {
+        let annotated = annotate_with(&Omniglot::builtin(), "She works 9 days.");
+
+        assert_eq!(annotated.tokens.len(), 5);
+        assert_eq!(annotated.token_text(&annotated.tokens[1]), "works");
+        assert_eq!(annotated.tokens[1].language.as_deref(), Some("eng"));
+        assert_eq!(annotated.tokens[1].lemma.as_deref(), Some("work"));
+        assert_eq!(annotated.tokens[1].upos.as_deref(), Some("VERB"));
+        assert_eq!(annotated.tokens[2].upos.as_deref(), Some("NUM"));
+        assert_eq!(annotated.tokens[4].upos.as_deref(), Some("PUNCT"));
+        assert_eq!(annotated.layer(SENTENCE_LAYER).unwrap().spans().len(), 1);
+    }
+
+    #[test]
+    fn it_runs_configured_stages_with_timings() {
+        let pipeline = Pipeline::from_config("# stages\ntokenizer\ngazetteer = Leiden, Delft\n", &registry()).unwrap();
+
+        let (annotated, timings) = pipeline.run_timed(&Omniglot::builtin(), "From Leiden to Delft");
+
+        assert_eq!(pipeline.stages(), vec!["tokenizer", "gazetteer"]);
+        assert_eq!(annotated.layer("places").unwrap().spans().len(), 2);
+        assert_eq!(timings.iter().map(|timing| timing.stage.as_str()).collect::<Vec<&str>>(), vec!["tokenizer", "gazetteer"]);
+    }
+
+    #[test]
+    fn it_analyses_normalized_text_at_original_offsets() {
+        let pipeline = Pipeline::from_config("normalizer = nfkc, case\ntokenizer\nanalyzer\ndisambiguator\n", &registry()).unwrap();
+
+        let annotated = pipeline.run(&Omniglot::builtin(), "ＳＨＥ ＷＯＲＫＳ.");
+        let works = &annotated.tokens[1];
+
+        assert_eq!(annotated.token_text(works), "ＷＯＲＫＳ");
+        assert_eq!(annotated.analysis_form(works), "works");
+        assert_eq!(works.lemma.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn it_keeps_expanded_graphemes_in_one_token() {
+        let pipeline = Pipeline::from_config("normalizer = nfkc\ntokenizer\n", &registry()).unwrap();
+
+        let annotated = pipeline.run(&Omniglot::builtin(), "½ cup");
+
+        assert_eq!(annotated.tokens.len(), 2);
+        assert_eq!(annotated.token_text(&annotated.tokens[0]), "½");
+        assert_eq!(annotated.tokens[0].kind, SegmentKind::Number);
+        assert_eq!(annotated.tokens[0].lemma.as_deref(), Some("1⁄2"));
+        assert!(!crate::conllu::write_conllu(&annotated).contains("1-"));
+    }
+
+    #[test]
+    fn it_rejects_unknown_stages() {
+        assert!(matches!(Pipeline::from_config("tokenizer\nparser\n", &registry()), Err(PipelineError::Config { line: 2, .. })));
+        assert!(matches!(Pipeline::from_config("gazetteer", &registry()), Err(PipelineError::Config { line: 1, .. })));
+    }
+
+    #[test]
+    fn it_prefers_frequent_lexemes() {
+        let mut lexicon = Lexicon::new();
+        lexicon.add_form("zijn", Part::Verb, "was", Features::new().with("Tense", "Past"));
+        lexicon.add_form("was", Part::Noun, "was", Features::new().with("Number", "Sing"));
+        lexicon.increment_frequency("zijn", Part::Verb);
+
+        let mut omni = Omniglot::new();
+        omni.add_language(Box::new(Dutch::new(lexicon)));
+        omni.set_default_language("nld");
+
+        let annotated = annotate_with(&omni, "was");
+
+        assert_eq!(annotated.tokens[0].lemma.as_deref(), Some("zijn"));
+        assert_eq!(annotated.tokens[0].alternatives[0].lemma, "was");
+    }
+}
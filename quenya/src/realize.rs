@@ -0,0 +1,397 @@
+// Pattern realisation: the reverse of matching. A template is a pattern whose
+// elements are slots, and realising it inflects a lemma for every slot with
+// the features of the template, of the fillers and of agreement.
+
+use std::fmt;
+use wn::pos::Part;
+use crate::features::Features;
+use crate::language::Language;
+use crate::pattern::{Element, Field, Pattern, PatternError, Value};
+use crate::text::part_for_upos;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RealizeError {
+    /// The pattern has an element that cannot be realised, such as a group or
+    /// a repetition.
+    Unsupported(String),
+    Pattern(PatternError),
+    UnknownSlot(String),
+    MissingLemma(String),
+    /// Two slots of an agreement have different values for a feature.
+    Agreement { feature: String, values: (String, String) },
+    NoForm { slot: String, lemma: String, features: Features },
+}
+
+impl fmt::Display for RealizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RealizeError::Unsupported(message) => write!(f, "cannot realize {}", message),
+            RealizeError::Pattern(error) => write!(f, "{}", error),
+            RealizeError::UnknownSlot(slot) => write!(f, "no slot '{}' in the template", slot),
+            RealizeError::MissingLemma(slot) => write!(f, "slot '{}' has no lemma", slot),
+            RealizeError::Agreement { feature, values } => {
+                write!(f, "{} cannot be both {} and {}", feature, values.0, values.1)
+            },
+            RealizeError::NoForm { slot, lemma, features } => {
+                write!(f, "no form of '{}' with {} for slot '{}'", lemma, features, slot)
+            },
+        }
+    }
+}
+
+impl std::error::Error for RealizeError {}
+
+impl From<PatternError> for RealizeError {
+    fn from(error: PatternError) -> RealizeError {
+        RealizeError::Pattern(error)
+    }
+}
+
+/// What a caller puts into a slot: a lemma, features, or both.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filler {
+    pub lemma: Option<String>,
+    pub features: Features,
+}
+
+impl Filler {
+    pub fn new() -> Filler {
+        Filler::default()
+    }
+
+    pub fn lemma(lemma: &str) -> Filler {
+        Filler {
+            lemma: Some(String::from(lemma)),
+            features: Features::new(),
+        }
+    }
+
+    /// Returns the filler with the feature `name` set to `value`.
+    pub fn with(mut self, name: &str, value: &str) -> Filler {
+        self.features.insert(name, value);
+
+        self
+    }
+
+    /// Parses a comma-separated list such as `lemma=zoeken, Person=3`.
+    pub fn parse(text: &str) -> Option<Filler> {
+        let mut filler = Filler::new();
+
+        for pair in text.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=')?;
+            let (name, value) = (name.trim(), value.trim());
+
+            if name.is_empty() || value.is_empty() {
+                return None;
+            }
+
+            if name.eq_ignore_ascii_case("lemma") {
+                filler.lemma = Some(String::from(value));
+            } else {
+                filler.features.insert(name, value);
+            }
+        }
+
+        Some(filler)
+    }
+}
+
+/// One word of a template.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slot {
+    /// The capture name of the element, through which fillers reach it.
+    pub name: Option<String>,
+    /// A fixed form, for elements written as a word.
+    pub form: Option<String>,
+    pub lemma: Option<String>,
+    pub pos: Option<Part>,
+    pub features: Features,
+    /// Optional slots are only realised when they are filled.
+    pub optional: bool,
+}
+
+impl Slot {
+    fn label(&self, index: usize) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("#{}", index + 1),
+        }
+    }
+}
+
+/// Features that the named slots share.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Agreement {
+    pub slots: Vec<String>,
+    pub features: Vec<String>,
+}
+
+/// A sequence of slots with agreements between them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    pub slots: Vec<Slot>,
+    pub agreements: Vec<Agreement>,
+}
+
+impl Template {
+    /// Compiles a template written in the pattern language, such as
+    /// `aux:[Lemma=hebben VerbForm=Fin] verb:[UPOS=VERB VerbForm=Part]`.
+    pub fn compile(source: &str) -> Result<Template, RealizeError> {
+        Template::from_pattern(&Pattern::compile("", source)?)
+    }
+
+    /// Makes a template of a pattern. Every element must be a single token,
+    /// possibly optional. The first value of `Form`, `Lemma`, `UPOS` and the
+    /// features is used, and negated tests and regular expressions, which
+    /// only restrict matching, are left out.
+    pub fn from_pattern(pattern: &Pattern) -> Result<Template, RealizeError> {
+        let mut slots = Vec::new();
+
+        for item in pattern.items() {
+            let constraints = match &item.element {
+                Element::Token(constraints) => constraints,
+                Element::Group(_) => return Err(RealizeError::Unsupported(String::from("a group"))),
+            };
+
+            let optional = match (item.min, item.max) {
+                (1, Some(1)) => false,
+                (0, Some(1)) => true,
+                _ => return Err(RealizeError::Unsupported(String::from("a repeated element"))),
+            };
+
+            let mut slot = Slot {
+                name: item.capture.clone(),
+                form: None,
+                lemma: None,
+                pos: None,
+                features: Features::new(),
+                optional,
+            };
+
+            for constraint in constraints {
+                let value = match &constraint.value {
+                    Value::OneOf(values) if !constraint.negated => &values[0],
+                    _ => continue,
+                };
+
+                match &constraint.field {
+                    Field::Form => slot.form = Some(value.clone()),
+                    Field::Lemma => slot.lemma = Some(value.clone()),
+                    Field::Upos => slot.pos = part_for_upos(value),
+                    Field::Xpos => {},
+                    Field::Feature(name) => slot.features.insert(name, value),
+                }
+            }
+
+            slots.push(slot);
+        }
+
+        Ok(Template {
+            slots,
+            agreements: Vec::new(),
+        })
+    }
+
+    /// Makes the named slots agree in the given features. A feature set on
+    /// one of them, by the template or a filler, applies to all of them.
+    pub fn agree(mut self, slots: &[&str], features: &[&str]) -> Template {
+        self.agreements.push(Agreement {
+            slots: slots.iter().map(|slot| String::from(*slot)).collect(),
+            features: features.iter().map(|feature| String::from(*feature)).collect(),
+        });
+
+        self
+    }
+
+    /// Realises the template with the inflection of a language. Fillers are
+    /// given by slot name. Words are separated by spaces, except in Japanese.
+    pub fn realize(&self, language: &dyn Language, fillers: &[(&str, Filler)]) -> Result<String, RealizeError> {
+        let separator = if language.code() == "jpn" { "" } else { " " };
+
+        Ok(self.realize_words(language, fillers)?.join(separator))
+    }
+
+    pub fn realize_words(&self, language: &dyn Language, fillers: &[(&str, Filler)]) -> Result<Vec<String>, RealizeError> {
+        for (name, _) in fillers {
+            if !self.slots.iter().any(|slot| slot.name.as_deref() == Some(*name)) {
+                return Err(RealizeError::UnknownSlot(String::from(*name)));
+            }
+        }
+
+        // Every slot with its fillers applied, unless it is optional and was
+        // not filled.
+        let mut slots: Vec<Option<Slot>> = Vec::new();
+
+        for slot in &self.slots {
+            let filler = fillers.iter().find(|(name, _)| slot.name.as_deref() == Some(*name)).map(|(_, filler)| filler);
+
+            if slot.optional && filler.is_none() {
+                slots.push(None);
+                continue;
+            }
+
+            let mut slot = slot.clone();
+
+            if let Some(filler) = filler {
+                if filler.lemma.is_some() {
+                    slot.lemma = filler.lemma.clone();
+                }
+
+                for (name, value) in filler.features.iter() {
+                    slot.features.insert(name, value);
+                }
+            }
+
+            slots.push(Some(slot));
+        }
+
+        // The features each slot gets from agreement, kept apart so that they
+        // can be dropped for words that do not mark them.
+        let mut agreed: Vec<Features> = vec![Features::new(); slots.len()];
+
+        for agreement in &self.agreements {
+            let members: Vec<usize> = slots.iter()
+                .enumerate()
+                .filter(|(_, slot)| slot.as_ref().is_some_and(|slot| slot.name.as_ref().is_some_and(|name| agreement.slots.contains(name))))
+                .map(|(index, _)| index)
+                .collect();
+
+            for feature in &agreement.features {
+                let mut shared: Option<&str> = None;
+
+                for &index in &members {
+                    let value = match slots[index].as_ref().and_then(|slot| slot.features.get(feature)) {
+                        Some(value) => value,
+                        None => continue,
+                    };
+
+                    match shared {
+                        Some(shared) if shared != value => return Err(RealizeError::Agreement {
+                            feature: feature.clone(),
+                            values: (String::from(shared), String::from(value)),
+                        }),
+                        _ => shared = Some(value),
+                    }
+                }
+
+                if let Some(shared) = shared {
+                    for &index in &members {
+                        agreed[index].insert(feature, shared);
+                    }
+                }
+            }
+        }
+
+        let mut words = Vec::new();
+
+        for (index, slot) in slots.iter().enumerate() {
+            if let Some(slot) = slot {
+                words.push(realize_slot(language, slot, &agreed[index], &slot.label(index))?);
+            }
+        }
+
+        Ok(words)
+    }
+}
+
+/// Inflects the lemma of a slot. Agreed features are asked for first and left
+/// out if no form has them, as for a participle that does not mark person.
+fn realize_slot(language: &dyn Language, slot: &Slot, agreed: &Features, label: &str) -> Result<String, RealizeError> {
+    let lemma = match (&slot.lemma, &slot.form) {
+        (Some(lemma), _) => lemma,
+        (None, Some(form)) => return Ok(form.clone()),
+        (None, None) => return Err(RealizeError::MissingLemma(String::from(label))),
+    };
+
+    let mut features = slot.features.clone();
+
+    for (name, value) in agreed.iter() {
+        features.insert(name, value);
+    }
+
+    let mut without_agreement = features.clone();
+
+    for (name, _) in agreed.iter() {
+        without_agreement.remove(name);
+    }
+
+    let parts = match slot.pos {
+        Some(pos) => vec![pos],
+        None => vec![Part::Verb, Part::Noun, Part::Adjective, Part::Adverb],
+    };
+
+    for request in [&features, &without_agreement] {
+        if request.is_empty() {
+            return Ok(lemma.clone());
+        }
+
+        for &pos in &parts {
+            if let Some(form) = language.inflect(lemma, pos, request).into_iter().next() {
+                return Ok(form);
+            }
+        }
+    }
+
+    Err(RealizeError::NoForm {
+        slot: String::from(label),
+        lemma: lemma.clone(),
+        features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexicon::Lexicon;
+    use crate::nld::Dutch;
+
+    fn dutch() -> Dutch {
+        let mut lexicon = Lexicon::new();
+        let present = Features::parse("Mood=Ind|Tense=Pres|VerbForm=Fin").unwrap();
+
+        lexicon.add_form("hebben", Part::Verb, "heb", present.clone().with("Number", "Sing").with("Person", "1"));
+        lexicon.add_form("hebben", Part::Verb, "heeft", present.clone().with("Number", "Sing").with("Person", "3"));
+        lexicon.add_form("hebben", Part::Verb, "hebben", present.with("Number", "Plur"));
+        lexicon.add_form("zoeken", Part::Verb, "gezocht", Features::new().with("VerbForm", "Part"));
+        lexicon.add_form("hond", Part::Noun, "honden", Features::new().with("Number", "Plur"));
+
+        Dutch::new(lexicon)
+    }
+
+    fn perfect() -> Template {
+        Template::compile("subject:[UPOS=PRON]? aux:[Lemma=hebben UPOS=AUX Mood=Ind Tense=Pres VerbForm=Fin] verb:[UPOS=VERB VerbForm=Part]")
+            .unwrap()
+            .agree(&["subject", "aux", "verb"], &["Person", "Number"])
+    }
+
+    #[test]
+    fn it_realizes_dutch_perfect_tenses() {
+        let dutch = dutch();
+        let verb = Filler::parse("lemma=zoeken, Person=3, Number=Sing").unwrap();
+
+        assert_eq!(perfect().realize(&dutch, &[("verb", verb)]).unwrap(), "heeft gezocht");
+        assert_eq!(perfect().realize(&dutch, &[("verb", Filler::lemma("zoeken").with("Number", "Plur"))]).unwrap(), "hebben gezocht");
+    }
+
+    #[test]
+    fn it_keeps_fixed_words_and_drops_unfilled_slots() {
+        let template = Template::compile("\"de\" [Lemma=hond UPOS=NOUN Number=Plur] adjective:[UPOS=ADJ]?").unwrap();
+
+        assert_eq!(template.realize(&dutch(), &[]).unwrap(), "de honden");
+    }
+
+    #[test]
+    fn it_reports_conflicts_and_missing_forms() {
+        let dutch = dutch();
+
+        let conflict = perfect().realize(&dutch, &[
+            ("subject", Filler::lemma("ik").with("Person", "1")),
+            ("verb", Filler::lemma("zoeken").with("Person", "3")),
+        ]);
+
+        assert_eq!(matches!(conflict, Err(RealizeError::Agreement { .. })), true);
+        assert_eq!(perfect().realize(&dutch, &[]), Err(RealizeError::MissingLemma(String::from("verb"))));
+        assert_eq!(perfect().realize(&dutch, &[("object", Filler::lemma("hond"))]), Err(RealizeError::UnknownSlot(String::from("object"))));
+        assert_eq!(matches!(Template::compile("[UPOS=ADJ]* [UPOS=NOUN]"), Err(RealizeError::Unsupported(_))), true);
+    }
+}
@@ -0,0 +1,211 @@
+// The front end of annotation: text is split into runs of one Unicode script,
+// and every run into words following the word boundaries of UAX #29.
+
+use unicode_script::{Script, UnicodeScript};
+use unicode_segmentation::UnicodeSegmentation;
+use crate::identify::language_for_scripts;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A maximal run of text in one script. Characters shared between scripts,
+/// such as spaces, digits and punctuation, belong to the run they follow.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptRun<'a> {
+    pub text: &'a str,
+    /// Byte offset of the run in the segmented text.
+    pub start: usize,
+    pub script: Script,
+    /// The ISO 639-3 code of the language the run is probably written in.
+    pub language: Option<String>,
+}
+
+impl<'a> ScriptRun<'a> {
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+}
+
+/// In JSON the kind is written in lowercase, such as `"word"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum SegmentKind {
+    Word,
+    Number,
+    /// Punctuation and symbols.
+    Punctuation,
+}
+
+/// A word, number or punctuation mark. Whitespace is not segmented.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment<'a> {
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+    pub kind: SegmentKind,
+    pub script: Script,
+    pub language: Option<String>,
+}
+
+/// Splits text into script runs. Runs of scripts that are mostly written in
+/// one language get that language as a hint, and Han next to kana is taken to
+/// be Japanese.
+pub fn script_runs(text: &str) -> Vec<ScriptRun<'_>> {
+    let mut runs: Vec<ScriptRun> = Vec::new();
+    let mut start = 0;
+    let mut script = Script::Common;
+
+    for (index, c) in text.char_indices() {
+        let char_script = c.script();
+
+        if is_shared(char_script) || char_script == script {
+            continue;
+        }
+
+        if script == Script::Common {
+            // Shared characters at the start of the text join the first run.
+            script = char_script;
+            continue;
+        }
+
+        runs.push(ScriptRun {
+            text: &text[start..index],
+            start,
+            script,
+            language: None,
+        });
+
+        start = index;
+        script = char_script;
+    }
+
+    if start < text.len() {
+        runs.push(ScriptRun {
+            text: &text[start..],
+            start,
+            script,
+            language: None,
+        });
+    }
+
+    let is_japanese = runs.iter().any(|run| run.script == Script::Hiragana || run.script == Script::Katakana);
+
+    for run in &mut runs {
+        let language = match run.script {
+            Script::Han if is_japanese => Some("jpn"),
+            script => language_for_scripts(&[script]),
+        };
+
+        run.language = language.map(String::from);
+    }
+
+    runs
+}
+
+/// Splits every script run into words. Within Han and kana runs, which do not
+/// separate words with spaces, every run is one word; elsewhere the word
+/// boundaries of UAX #29 apply.
+pub fn segment_runs<'a>(runs: &[ScriptRun<'a>]) -> Vec<Segment<'a>> {
+    let mut segments: Vec<Segment> = Vec::new();
+
+    for run in runs {
+        for (offset, word) in run.text.split_word_bound_indices() {
+            if word.chars().all(char::is_whitespace) {
+                continue;
+            }
+
+            let start = run.start + offset;
+
+            let kind = if word.chars().any(char::is_alphabetic) {
+                SegmentKind::Word
+            } else if word.chars().any(char::is_numeric) {
+                SegmentKind::Number
+            } else {
+                SegmentKind::Punctuation
+            };
+
+            let joins_previous = is_unspaced(run.script) && kind == SegmentKind::Word && segments.last().is_some_and(|previous| {
+                previous.end == start && previous.kind == SegmentKind::Word && previous.script == run.script
+            });
+
+            if joins_previous {
+                let previous = segments.last_mut().unwrap();
+
+                previous.end = start + word.len();
+                previous.text = &run.text[previous.start - run.start..previous.end - run.start];
+
+                continue;
+            }
+
+            segments.push(Segment {
+                text: word,
+                start,
+                end: start + word.len(),
+                kind,
+                script: run.script,
+                language: run.language.clone(),
+            });
+        }
+    }
+
+    segments
+}
+
+/// Splits text into script runs and those into words.
+pub fn segment(text: &str) -> Vec<Segment<'_>> {
+    segment_runs(&script_runs(text))
+}
+
+fn is_shared(script: Script) -> bool {
+    script == Script::Common || script == Script::Inherited || script == Script::Unknown
+}
+
+fn is_unspaced(script: Script) -> bool {
+    matches!(script, Script::Han | Script::Hiragana | Script::Katakana | Script::Thai)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(segments: &[Segment<'a>]) -> Vec<&'a str> {
+        segments.iter().map(|segment| segment.text).collect()
+    }
+
+    #[test]
+    fn it_splits_mixed_japanese_and_english() {
+        let runs = script_runs("東京でiPhoneを買った。");
+
+        let scripts: Vec<Script> = runs.iter().map(|run| run.script).collect();
+
+        assert_eq!(scripts, vec![Script::Han, Script::Hiragana, Script::Latin, Script::Hiragana, Script::Han, Script::Hiragana]);
+        assert_eq!(runs[0].language, Some(String::from("jpn")));
+        assert_eq!(runs[2].language, None);
+
+        assert_eq!(texts(&segment("東京でiPhoneを買った。")), vec!["東京", "で", "iPhone", "を", "買", "った", "。"]);
+    }
+
+    #[test]
+    fn it_splits_arabic_with_latin_abbreviations() {
+        let segments = segment("تعمل في IBM منذ 2010.");
+
+        assert_eq!(texts(&segments), vec!["تعمل", "في", "IBM", "منذ", "2010", "."]);
+        assert_eq!(segments[0].language, Some(String::from("ara")));
+        assert_eq!(segments[2].script, Script::Latin);
+        assert_eq!(segments[4].kind, SegmentKind::Number);
+        assert_eq!(&"تعمل في IBM منذ 2010."[segments[3].start..segments[3].end], "منذ");
+    }
+
+    #[test]
+    fn it_follows_uax_29_word_boundaries() {
+        let segments = segment("Hij zei: \"we can't stop\".");
+
+        assert_eq!(texts(&segments), vec!["Hij", "zei", ":", "\"", "we", "can't", "stop", "\"", "."]);
+    }
+
+    #[test]
+    fn it_keeps_combining_marks_in_runs() {
+        assert_eq!(texts(&segment("كَتَبَ الوَلَدُ")), vec!["كَتَبَ", "الوَلَدُ"]);
+    }
+}
@@ -0,0 +1,491 @@
+// Sentence boundary detection. Sentences end at terminal punctuation, unless a
+// full stop follows an abbreviation or is followed by a lowercase word, and
+// take along the closing quotes and brackets that follow them.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use crate::omni::Omniglot;
+use crate::pipeline::Stage;
+use crate::segmentation::{SegmentKind, segment};
+use crate::text::{AnnotatedText, SENTENCE_LAYER, Span};
+
+/// How often a word must occur before a full stop to be taken for an
+/// abbreviation when training.
+const MIN_ABBREVIATION_COUNT: u64 = 2;
+
+/// The share of its occurrences a word must have before a full stop to be
+/// taken for an abbreviation when training.
+const MIN_ABBREVIATION_RATIO: f64 = 0.9;
+
+/// Abbreviations longer than this are only learned when they contain a full
+/// stop of their own, as in `e.g.`.
+const MAX_ABBREVIATION_LENGTH: usize = 4;
+
+const ENGLISH_ABBREVIATIONS: &[&str] = &[
+    "approx", "apr", "aug", "ca", "cf", "co", "corp", "dec", "dept", "dr", "e.g", "est", "etc", "feb", "fig",
+    "i.e", "inc", "jan", "jr", "jul", "jun", "ltd", "mar", "mr", "mrs", "ms", "no", "nov", "oct", "p.m", "a.m",
+    "prof", "sep", "sept", "sr", "st", "u.s", "vol", "vs",
+];
+
+const DUTCH_ABBREVIATIONS: &[&str] = &[
+    "a.s", "afb", "blz", "bijv", "bv", "ca", "d.w.z", "dhr", "dr", "drs", "e.a", "enz", "etc", "ing", "ir",
+    "jl", "m.a.w", "mevr", "mr", "mw", "nl", "nr", "o.a", "p", "prof", "resp", "t.a.v", "vgl", "zg",
+];
+
+/// Frequent first words of sentences. A capitalised word after a single
+/// letter and a full stop is taken for the name after an initial, as in
+/// `J. Smith`, unless it is one of these.
+const ENGLISH_SENTENCE_STARTERS: &[&str] = &[
+    "a", "after", "all", "also", "an", "and", "as", "at", "but", "for", "he", "her", "his", "how", "however",
+    "i", "if", "in", "it", "its", "my", "no", "now", "of", "on", "our", "she", "so", "some", "that", "the",
+    "their", "then", "there", "these", "they", "this", "to", "we", "what", "when", "where", "which", "while",
+    "who", "why", "yes", "yet", "you",
+];
+
+const DUTCH_SENTENCE_STARTERS: &[&str] = &[
+    "als", "daarna", "dan", "dat", "de", "die", "dit", "een", "en", "er", "geen", "het", "hij", "hoe", "ik",
+    "in", "ja", "je", "jij", "maar", "met", "na", "nee", "niet", "nu", "ook", "op", "toen", "u", "waar",
+    "want", "wat", "we", "wie", "wij", "ze", "zij", "zo",
+];
+
+/// Characters that end a sentence. The Arabic semicolon separates complete
+/// clauses, so it is taken to end a sentence as well.
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…' | '。' | '！' | '？' | '｡' | '؟' | '؛' | '۔')
+}
+
+/// Closing quotes and brackets that belong to the sentence before them.
+fn is_closing(c: char) -> bool {
+    matches!(c, ')' | ']' | '}' | '"' | '\'' | '”' | '’' | '»' | '›' | '」' | '』' | '）' | '】' | '〉' | '》')
+}
+
+/// Terminators that end a sentence even when no space follows them, as in
+/// Japanese and Chinese.
+fn is_unspaced_terminator(c: char) -> bool {
+    matches!(c, '。' | '！' | '？' | '｡')
+}
+
+/// A list of abbreviations, kept in lowercase without their final full stop,
+/// such as `bijv` and `e.g`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Abbreviations {
+    words: HashSet<String>,
+}
+
+impl Abbreviations {
+    pub fn new() -> Abbreviations {
+        Abbreviations {
+            words: HashSet::new(),
+        }
+    }
+
+    pub fn from_words(words: &[&str]) -> Abbreviations {
+        let mut abbreviations = Abbreviations::new();
+
+        for word in words {
+            abbreviations.insert(word);
+        }
+
+        abbreviations
+    }
+
+    /// Learns abbreviations from plain text in the manner of Punkt: a short
+    /// word that nearly always occurs before a full stop is an abbreviation.
+    pub fn train(text: &str) -> Abbreviations {
+        let segments = segment(text);
+        let mut counts: HashMap<String, (u64, u64)> = HashMap::new();
+
+        for (index, word) in segments.iter().enumerate() {
+            if word.kind != SegmentKind::Word {
+                continue;
+            }
+
+            let has_period = segments.get(index + 1).is_some_and(|next| next.start == word.end && next.text == ".");
+            let count = counts.entry(word.text.to_lowercase()).or_insert((0, 0));
+
+            if has_period {
+                count.0 += 1;
+            } else {
+                count.1 += 1;
+            }
+        }
+
+        let mut abbreviations = Abbreviations::new();
+
+        for (word, (with_period, without_period)) in counts {
+            let ratio = with_period as f64 / (with_period + without_period) as f64;
+            let is_short = word.chars().count() <= MAX_ABBREVIATION_LENGTH || word.contains('.');
+
+            if with_period >= MIN_ABBREVIATION_COUNT && ratio >= MIN_ABBREVIATION_RATIO && is_short {
+                abbreviations.words.insert(word);
+            }
+        }
+
+        abbreviations
+    }
+
+    /// Reads one abbreviation per line. Empty lines and lines starting with
+    /// `#` are skipped.
+    pub fn read<R: Read>(reader: R) -> io::Result<Abbreviations> {
+        let mut abbreviations = Abbreviations::new();
+
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            abbreviations.insert(line);
+        }
+
+        Ok(abbreviations)
+    }
+
+    /// Writes the abbreviations in alphabetical order, one per line.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut words: Vec<&String> = self.words.iter().collect();
+        words.sort();
+
+        for word in words {
+            writeln!(writer, "{}", word)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds an abbreviation, with or without its final full stop.
+    pub fn insert(&mut self, word: &str) {
+        self.words.insert(word.trim_end_matches('.').to_lowercase());
+    }
+
+    pub fn extend(&mut self, other: Abbreviations) {
+        self.words.extend(other.words);
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// A token as the splitter sees it.
+struct Piece<'a> {
+    start: usize,
+    end: usize,
+    kind: SegmentKind,
+    language: Option<&'a str>,
+}
+
+/// Splits text into sentences, with abbreviation lists per language. As a
+/// pipeline stage it adds the sentences to `SENTENCE_LAYER`.
+#[derive(Clone, Debug, Default)]
+pub struct SentenceSplitter {
+    abbreviations: HashMap<String, Abbreviations>,
+}
+
+impl SentenceSplitter {
+    /// A splitter with the built-in abbreviations of English and Dutch.
+    pub fn new() -> SentenceSplitter {
+        let mut splitter = SentenceSplitter::empty();
+
+        splitter.add_abbreviations("eng", Abbreviations::from_words(ENGLISH_ABBREVIATIONS));
+        splitter.add_abbreviations("nld", Abbreviations::from_words(DUTCH_ABBREVIATIONS));
+
+        splitter
+    }
+
+    /// A splitter without abbreviations.
+    pub fn empty() -> SentenceSplitter {
+        SentenceSplitter {
+            abbreviations: HashMap::new(),
+        }
+    }
+
+    /// Adds abbreviations for a language, keeping the ones it already has.
+    pub fn add_abbreviations(&mut self, language: &str, abbreviations: Abbreviations) {
+        self.abbreviations.entry(String::from(language)).or_default().extend(abbreviations);
+    }
+
+    pub fn abbreviations(&self, language: &str) -> Option<&Abbreviations> {
+        self.abbreviations.get(language)
+    }
+
+    /// Splits text into sentences, using the languages of its script runs.
+    pub fn split(&self, text: &str) -> Vec<Span> {
+        let segments = segment(text);
+
+        let pieces: Vec<Piece> = segments.iter()
+            .map(|segment| Piece {
+                start: segment.start,
+                end: segment.end,
+                kind: segment.kind,
+                language: segment.language.as_deref(),
+            })
+            .collect();
+
+        self.split_pieces(text, &pieces)
+    }
+
+    /// Splits an annotated text into sentences along its tokens.
+    pub fn split_tokens(&self, text: &AnnotatedText) -> Vec<Span> {
+        let pieces: Vec<Piece> = text.tokens.iter()
+            .map(|token| Piece {
+                start: token.start,
+                end: token.end,
+                kind: token.kind,
+                language: token.language.as_deref(),
+            })
+            .collect();
+
+        self.split_pieces(&text.text, &pieces)
+    }
+
+    fn split_pieces(&self, text: &str, pieces: &[Piece]) -> Vec<Span> {
+        let mut sentences: Vec<Span> = Vec::new();
+        let mut start: Option<usize> = None;
+        let mut index = 0;
+
+        while index < pieces.len() {
+            let piece = &pieces[index];
+            let first = *start.get_or_insert(piece.start);
+
+            if !self.is_boundary(text, pieces, index) {
+                index += 1;
+                continue;
+            }
+
+            // Take along further terminators, as in `?!` and `...`, and the
+            // closing quotes and brackets after them.
+            let mut last = index;
+
+            while let Some(next) = pieces.get(last + 1) {
+                let next_text = &text[next.start..next.end];
+                let is_attached = next.start == pieces[last].end;
+
+                let belongs = next.kind == SegmentKind::Punctuation && (
+                    next_text.chars().all(is_terminator) ||
+                    (is_attached && next_text.chars().all(is_closing))
+                );
+
+                if !belongs {
+                    break;
+                }
+
+                last += 1;
+            }
+
+            sentences.push(Span::new(first, pieces[last].end, ""));
+            start = None;
+            index = last + 1;
+        }
+
+        if let Some(first) = start {
+            sentences.push(Span::new(first, pieces[pieces.len() - 1].end, ""));
+        }
+
+        sentences
+    }
+
+    /// Whether the piece at `index` ends a sentence.
+    fn is_boundary(&self, text: &str, pieces: &[Piece], index: usize) -> bool {
+        let piece = &pieces[index];
+        let piece_text = &text[piece.start..piece.end];
+
+        if piece.kind != SegmentKind::Punctuation || !piece_text.chars().all(is_terminator) {
+            return false;
+        }
+
+        if piece_text.chars().all(is_unspaced_terminator) {
+            return true;
+        }
+
+        // The first word after the terminator and its closing punctuation.
+        let next_index = pieces[index + 1..].iter()
+            .position(|next| !text[next.start..next.end].chars().all(|c| is_terminator(c) || is_closing(c)));
+
+        let next_index = match next_index {
+            Some(position) => index + 1 + position,
+            None => return true,
+        };
+
+        let next_word = &pieces[next_index];
+
+        let next_text = &text[next_word.start..next_word.end];
+
+        // Other terminators need space after them, unlike a full stop between
+        // the parts of a name such as `quenya.rs`.
+        if text[piece.end..next_word.start].chars().all(|c| !c.is_whitespace()) {
+            return false;
+        }
+
+        if piece_text != "." {
+            return true;
+        }
+
+        if next_text.chars().next().is_some_and(char::is_lowercase) {
+            return false;
+        }
+
+        let previous = match index.checked_sub(1).map(|previous| &pieces[previous]) {
+            Some(previous) if previous.end == piece.start && previous.kind == SegmentKind::Word => previous,
+            _ => return true,
+        };
+
+        let word = &text[previous.start..previous.end];
+
+        if self.is_abbreviation(word, previous.language) {
+            return false;
+        }
+
+        !(is_initial(word) && (is_initial_at(text, pieces, next_index) || is_name(next_text, next_word.language)))
+    }
+
+    /// Whether a word before a full stop is on the abbreviation list of its
+    /// language, learned or built in. Words of an unknown language are looked
+    /// up in every list.
+    fn is_abbreviation(&self, word: &str, language: Option<&str>) -> bool {
+        match language {
+            Some(language) => self.abbreviations.get(language).is_some_and(|abbreviations| abbreviations.contains(word)),
+            None => self.abbreviations.values().any(|abbreviations| abbreviations.contains(word)),
+        }
+    }
+}
+
+/// Whether a word is a single capital letter, as an initial is.
+fn is_initial(word: &str) -> bool {
+    let mut chars = word.chars();
+
+    chars.next().is_some_and(char::is_uppercase) && chars.next().is_none()
+}
+
+/// Whether the piece at `index` is an initial followed by a full stop.
+fn is_initial_at(text: &str, pieces: &[Piece], index: usize) -> bool {
+    let (piece, stop) = match (pieces.get(index), pieces.get(index + 1)) {
+        (Some(piece), Some(stop)) => (piece, stop),
+        _ => return false,
+    };
+
+    is_initial(&text[piece.start..piece.end]) && stop.start == piece.end && &text[stop.start..stop.end] == "."
+}
+
+/// Whether a capitalised word after an initial is taken for a name rather
+/// than the start of a new sentence.
+fn is_name(word: &str, language: Option<&str>) -> bool {
+    if !word.chars().next().is_some_and(char::is_uppercase) {
+        return false;
+    }
+
+    let word = word.to_lowercase();
+    let starters: &[&str] = match language {
+        Some("eng") => ENGLISH_SENTENCE_STARTERS,
+        Some("nld") => DUTCH_SENTENCE_STARTERS,
+        Some(_) => &[],
+        None => return !ENGLISH_SENTENCE_STARTERS.contains(&word.as_str()) && !DUTCH_SENTENCE_STARTERS.contains(&word.as_str()),
+    };
+
+    !starters.contains(&word.as_str())
+}
+
+impl Stage for SentenceSplitter {
+    fn name(&self) -> &str {
+        "sentences"
+    }
+
+    /// Splits along the tokens if the text has been tokenized, and along its
+    /// segments otherwise.
+    fn process(&self, _omni: &Omniglot, text: &mut AnnotatedText) {
+        let sentences = if text.tokens.is_empty() {
+            self.split(&text.text)
+        } else {
+            self.split_tokens(text)
+        };
+
+        for sentence in sentences {
+            text.add_span(SENTENCE_LAYER, sentence);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentences(splitter: &SentenceSplitter, text: &str) -> Vec<String> {
+        splitter.split(text).iter().map(|span| String::from(&text[span.start..span.end])).collect()
+    }
+
+    #[test]
+    fn it_splits_at_terminators() {
+        let splitter = SentenceSplitter::new();
+
+        assert_eq!(sentences(&splitter, "It costs 3.50 euro. Really?! Yes... Fine."), vec!["It costs 3.50 euro.", "Really?!", "Yes...", "Fine."]);
+        assert_eq!(sentences(&splitter, "No end in sight"), vec!["No end in sight"]);
+        assert!(sentences(&splitter, "").is_empty());
+    }
+
+    #[test]
+    fn it_skips_abbreviations() {
+        let splitter = SentenceSplitter::new();
+
+        assert_eq!(sentences(&splitter, "Neem bijv. Piet. Hij komt ook."), vec!["Neem bijv. Piet.", "Hij komt ook."]);
+        assert_eq!(sentences(&splitter, "Fruit, e.g. apples. Dr. J. Smith agrees."), vec!["Fruit, e.g. apples.", "Dr. J. Smith agrees."]);
+    }
+
+    #[test]
+    fn it_tells_initials_from_single_letter_words() {
+        let splitter = SentenceSplitter::new();
+
+        assert_eq!(sentences(&splitter, "So did I. Then we left."), vec!["So did I.", "Then we left."]);
+        assert_eq!(sentences(&splitter, "Plan B. That failed."), vec!["Plan B.", "That failed."]);
+        assert_eq!(sentences(&splitter, "Ask J. R. R. Tolkien. He knows."), vec!["Ask J. R. R. Tolkien.", "He knows."]);
+        assert_eq!(sentences(&splitter, "John F. Kennedy spoke."), vec!["John F. Kennedy spoke."]);
+
+        let mut splitter = SentenceSplitter::empty();
+        splitter.add_abbreviations("eng", Abbreviations::from_words(&["b"]));
+
+        assert_eq!(sentences(&splitter, "Plan B. That failed."), vec!["Plan B. That failed."]);
+    }
+
+    #[test]
+    fn it_keeps_quotes_and_brackets() {
+        let splitter = SentenceSplitter::new();
+
+        assert_eq!(sentences(&splitter, "He said \"Go home.\" Then he left. (It was late.) Bye."), vec!["He said \"Go home.\"", "Then he left.", "(It was late.)", "Bye."]);
+        assert_eq!(sentences(&splitter, "「行こう。」と言った。"), vec!["「行こう。」", "と言った。"]);
+    }
+
+    #[test]
+    fn it_splits_japanese_and_arabic() {
+        let splitter = SentenceSplitter::new();
+
+        assert_eq!(sentences(&splitter, "東京に行きました。楽しかった！本当？"), vec!["東京に行きました。", "楽しかった！", "本当？"]);
+        assert_eq!(sentences(&splitter, "هل كتب الولد؟ نعم؛ كتب الدرس."), vec!["هل كتب الولد؟", "نعم؛", "كتب الدرس."]);
+    }
+
+    #[test]
+    fn it_trains_abbreviations() {
+        let abbreviations = Abbreviations::train("Zie blz. 4 en blz. 7. Zie ook fig. 2, fig. 3 en het boek. Het boek is dik.");
+
+        assert!(abbreviations.contains("blz"));
+        assert!(abbreviations.contains("fig"));
+        assert!(!abbreviations.contains("boek"));
+
+        let mut splitter = SentenceSplitter::empty();
+        splitter.add_abbreviations("nld", abbreviations);
+
+        let mut written = Vec::new();
+        splitter.abbreviations("nld").unwrap().write(&mut written).unwrap();
+
+        assert_eq!(Abbreviations::read(written.as_slice()).unwrap().len(), 2);
+    }
+}
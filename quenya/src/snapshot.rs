@@ -0,0 +1,871 @@
+// Compact binary snapshots of a complete `Lexicon`.
+//
+// A snapshot is a header followed by a string table and flat tables of
+// little-endian `u32` records. Lexemes are sorted by id and the form index by
+// form, so lookups are binary searches directly on the bytes and loading a
+// snapshot only validates it instead of rebuilding any `HashMap`.
+//
+// Layout, after the four magic bytes:
+//
+//   version, string table length, and the record counts of the lexeme, form,
+//   text, translation, index entry and reading tables (one `u32` each)
+//   the string table, padded to a multiple of four bytes
+//   lexemes      id, lemma, pos, frequency (2 words), forms, glosses,
+//                pronunciations, etymology flag, etymology, translations,
+//                sources
+//   forms        form, features, sources
+//   texts        text (glosses, pronunciations and sources)
+//   translations language, word
+//   entries      form, readings
+//   readings     lexeme index, features
+//
+// Strings are stored as an offset and length into the string table, ranges of
+// other tables as a start and count. Features are stored in the UD notation.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+use wn::pos::{Part, as_char, parse_pos};
+use crate::features::Features;
+use crate::lexicon::{Form, FormReading, Lexeme, Lexicon, Translation, lexeme_key};
+
+const MAGIC: &[u8; 4] = b"QLXS";
+
+/// The snapshot format version written by this build.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+const HEADER_WORDS: usize = 8;
+const LEXEME_WORDS: usize = 20;
+const FORM_WORDS: usize = 6;
+const TEXT_WORDS: usize = 2;
+const TRANSLATION_WORDS: usize = 4;
+const ENTRY_WORDS: usize = 4;
+const READING_WORDS: usize = 3;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "could not read snapshot: {}", error),
+            SnapshotError::InvalidMagic => write!(f, "not a lexicon snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "snapshot version {} is not supported, expected {}", version, SNAPSHOT_VERSION)
+            }
+            SnapshotError::Corrupt(reason) => write!(f, "corrupt snapshot: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> SnapshotError {
+        SnapshotError::Io(error)
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    strings: Vec<u8>,
+    offsets: HashMap<String, [u32; 2]>,
+    lexemes: Vec<u32>,
+    forms: Vec<u32>,
+    texts: Vec<u32>,
+    translations: Vec<u32>,
+    entries: Vec<u32>,
+    readings: Vec<u32>,
+}
+
+impl Writer {
+    fn string(&mut self, text: &str) -> [u32; 2] {
+        if let Some(reference) = self.offsets.get(text) {
+            return *reference;
+        }
+
+        let reference = [self.strings.len() as u32, text.len() as u32];
+
+        self.strings.extend_from_slice(text.as_bytes());
+        self.offsets.insert(String::from(text), reference);
+
+        reference
+    }
+
+    fn texts(&mut self, texts: &[String]) -> [u32; 2] {
+        let start = (self.texts.len() / TEXT_WORDS) as u32;
+
+        for text in texts {
+            let reference = self.string(text);
+
+            self.texts.extend_from_slice(&reference);
+        }
+
+        [start, texts.len() as u32]
+    }
+
+    fn lexeme(&mut self, lexeme: &Lexeme) {
+        let id = self.string(&lexeme.id());
+        let lemma = self.string(&lexeme.lemma);
+
+        let forms_start = (self.forms.len() / FORM_WORDS) as u32;
+
+        for form in &lexeme.forms {
+            let text = self.string(&form.form);
+            let features = self.string(&form.features.to_string());
+            let sources = self.texts(&form.sources);
+
+            self.forms.extend_from_slice(&text);
+            self.forms.extend_from_slice(&features);
+            self.forms.extend_from_slice(&sources);
+        }
+
+        let glosses = self.texts(&lexeme.glosses);
+        let pronunciations = self.texts(&lexeme.pronunciations);
+        let sources = self.texts(&lexeme.sources);
+
+        let etymology = match &lexeme.etymology {
+            Some(etymology) => self.string(etymology),
+            None => [0, 0],
+        };
+
+        let translations_start = (self.translations.len() / TRANSLATION_WORDS) as u32;
+
+        for translation in &lexeme.translations {
+            let language = self.string(&translation.language);
+            let word = self.string(&translation.word);
+
+            self.translations.extend_from_slice(&language);
+            self.translations.extend_from_slice(&word);
+        }
+
+        self.lexemes.extend_from_slice(&id);
+        self.lexemes.extend_from_slice(&lemma);
+        self.lexemes.push(as_char(lexeme.pos) as u32);
+        self.lexemes.push(lexeme.frequency as u32);
+        self.lexemes.push((lexeme.frequency >> 32) as u32);
+        self.lexemes.extend_from_slice(&[forms_start, lexeme.forms.len() as u32]);
+        self.lexemes.extend_from_slice(&glosses);
+        self.lexemes.extend_from_slice(&pronunciations);
+        self.lexemes.push(lexeme.etymology.is_some() as u32);
+        self.lexemes.extend_from_slice(&etymology);
+        self.lexemes.extend_from_slice(&[translations_start, lexeme.translations.len() as u32]);
+        self.lexemes.extend_from_slice(&sources);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        while !self.strings.len().is_multiple_of(4) {
+            self.strings.push(0);
+        }
+
+        let header = [
+            SNAPSHOT_VERSION,
+            self.strings.len() as u32,
+            (self.lexemes.len() / LEXEME_WORDS) as u32,
+            (self.forms.len() / FORM_WORDS) as u32,
+            (self.texts.len() / TEXT_WORDS) as u32,
+            (self.translations.len() / TRANSLATION_WORDS) as u32,
+            (self.entries.len() / ENTRY_WORDS) as u32,
+            (self.readings.len() / READING_WORDS) as u32,
+        ];
+
+        let mut bytes: Vec<u8> = Vec::new();
+
+        bytes.extend_from_slice(MAGIC);
+        push_words(&mut bytes, &header);
+        bytes.extend_from_slice(&self.strings);
+
+        for table in [&self.lexemes, &self.forms, &self.texts, &self.translations, &self.entries, &self.readings].iter() {
+            push_words(&mut bytes, table);
+        }
+
+        bytes
+    }
+}
+
+fn push_words(bytes: &mut Vec<u8>, words: &[u32]) {
+    for word in words {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+}
+
+/// Serialises a lexicon, including its form index, into a snapshot.
+pub fn write_snapshot(lexicon: &Lexicon) -> Vec<u8> {
+    let mut writer = Writer::default();
+
+    let mut lexemes: Vec<&Lexeme> = lexicon.lexemes().collect();
+    lexemes.sort_by_key(|lexeme| lexeme.id());
+
+    let positions: HashMap<String, u32> = lexemes.iter()
+        .enumerate()
+        .map(|(position, lexeme)| (lexeme.id(), position as u32))
+        .collect();
+
+    for lexeme in &lexemes {
+        writer.lexeme(lexeme);
+    }
+
+    let mut forms: Vec<(&str, &[FormReading])> = lexicon.forms().collect();
+    forms.sort_by_key(|(form, _)| *form);
+
+    for (form, readings) in forms {
+        let text = writer.string(form);
+        let start = (writer.readings.len() / READING_WORDS) as u32;
+
+        for reading in readings {
+            let features = writer.string(&reading.features.to_string());
+
+            writer.readings.push(positions[&reading.lexeme]);
+            writer.readings.extend_from_slice(&features);
+        }
+
+        writer.entries.extend_from_slice(&text);
+        writer.entries.extend_from_slice(&[start, readings.len() as u32]);
+    }
+
+    writer.finish()
+}
+
+/// Writes a snapshot of `lexicon` to a file.
+pub fn save_snapshot<P: AsRef<Path>>(lexicon: &Lexicon, path: P) -> io::Result<()> {
+    std::fs::write(path, write_snapshot(lexicon))
+}
+
+/// Byte offsets and record counts of the tables in a validated snapshot.
+#[derive(Clone, Copy, Debug)]
+struct Layout {
+    strings: usize,
+    strings_len: usize,
+    lexemes: usize,
+    lexeme_count: usize,
+    forms: usize,
+    form_count: usize,
+    texts: usize,
+    text_count: usize,
+    translations: usize,
+    translation_count: usize,
+    entries: usize,
+    entry_count: usize,
+    readings: usize,
+    reading_count: usize,
+}
+
+fn read_word(bytes: &[u8], position: usize) -> u32 {
+    let mut word = [0; 4];
+    word.copy_from_slice(&bytes[position..position + 4]);
+
+    u32::from_le_bytes(word)
+}
+
+impl Layout {
+    fn validate(bytes: &[u8]) -> Result<Layout, SnapshotError> {
+        if bytes.len() < 4 || &bytes[..4] != MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+
+        if bytes.len() < 4 + HEADER_WORDS * 4 {
+            return Err(SnapshotError::Corrupt("header is truncated"));
+        }
+
+        let header: Vec<usize> = (0..HEADER_WORDS).map(|index| read_word(bytes, 4 + index * 4) as usize).collect();
+
+        if header[0] != SNAPSHOT_VERSION as usize {
+            return Err(SnapshotError::UnsupportedVersion(header[0] as u32));
+        }
+
+        let strings = 4 + HEADER_WORDS * 4;
+        let strings_len = header[1];
+
+        if !strings_len.is_multiple_of(4) {
+            return Err(SnapshotError::Corrupt("string table is not padded"));
+        }
+
+        let mut position = strings.saturating_add(strings_len);
+        let mut table = |count: usize, words: usize| {
+            let start = position;
+            position = position.saturating_add(count.saturating_mul(words * 4));
+
+            start
+        };
+
+        let layout = Layout {
+            strings,
+            strings_len,
+            lexemes: table(header[2], LEXEME_WORDS),
+            lexeme_count: header[2],
+            forms: table(header[3], FORM_WORDS),
+            form_count: header[3],
+            texts: table(header[4], TEXT_WORDS),
+            text_count: header[4],
+            translations: table(header[5], TRANSLATION_WORDS),
+            translation_count: header[5],
+            entries: table(header[6], ENTRY_WORDS),
+            entry_count: header[6],
+            readings: table(header[7], READING_WORDS),
+            reading_count: header[7],
+        };
+
+        if position != bytes.len() {
+            return Err(SnapshotError::Corrupt("tables do not match the file size"));
+        }
+
+        layout.validate_tables(bytes)?;
+
+        Ok(layout)
+    }
+
+    fn validate_tables(&self, bytes: &[u8]) -> Result<(), SnapshotError> {
+        let strings = &bytes[self.strings..self.strings + self.strings_len];
+
+        if std::str::from_utf8(strings).is_err() {
+            return Err(SnapshotError::Corrupt("string table is not UTF-8"));
+        }
+
+        let string = |offset: u32, len: u32| {
+            let range = offset as usize..offset as usize + len as usize;
+
+            if range.end > strings.len() || std::str::from_utf8(&strings[range]).is_err() {
+                return Err(SnapshotError::Corrupt("string reference is out of bounds"));
+            }
+
+            Ok(())
+        };
+
+        let span = |start: u32, count: u32, total: usize| {
+            if start as usize + count as usize > total {
+                return Err(SnapshotError::Corrupt("table range is out of bounds"));
+            }
+
+            Ok(())
+        };
+
+        let word = |table: usize, words: usize, index: usize, field: usize| {
+            read_word(bytes, table + (index * words + field) * 4)
+        };
+
+        for index in 0..self.lexeme_count {
+            let field = |field: usize| word(self.lexemes, LEXEME_WORDS, index, field);
+
+            string(field(0), field(1))?;
+            string(field(2), field(3))?;
+
+            if !['a', 'n', 'r', 'v'].iter().any(|pos| *pos as u32 == field(4)) {
+                return Err(SnapshotError::Corrupt("unknown part of speech"));
+            }
+
+            span(field(7), field(8), self.form_count)?;
+            span(field(9), field(10), self.text_count)?;
+            span(field(11), field(12), self.text_count)?;
+            string(field(14), field(15))?;
+            span(field(16), field(17), self.translation_count)?;
+            span(field(18), field(19), self.text_count)?;
+        }
+
+        for index in 0..self.form_count {
+            string(word(self.forms, FORM_WORDS, index, 0), word(self.forms, FORM_WORDS, index, 1))?;
+            string(word(self.forms, FORM_WORDS, index, 2), word(self.forms, FORM_WORDS, index, 3))?;
+            span(word(self.forms, FORM_WORDS, index, 4), word(self.forms, FORM_WORDS, index, 5), self.text_count)?;
+        }
+
+        for index in 0..self.text_count {
+            string(word(self.texts, TEXT_WORDS, index, 0), word(self.texts, TEXT_WORDS, index, 1))?;
+        }
+
+        for index in 0..self.translation_count {
+            string(word(self.translations, TRANSLATION_WORDS, index, 0), word(self.translations, TRANSLATION_WORDS, index, 1))?;
+            string(word(self.translations, TRANSLATION_WORDS, index, 2), word(self.translations, TRANSLATION_WORDS, index, 3))?;
+        }
+
+        for index in 0..self.entry_count {
+            string(word(self.entries, ENTRY_WORDS, index, 0), word(self.entries, ENTRY_WORDS, index, 1))?;
+            span(word(self.entries, ENTRY_WORDS, index, 2), word(self.entries, ENTRY_WORDS, index, 3), self.reading_count)?;
+        }
+
+        for index in 0..self.reading_count {
+            if word(self.readings, READING_WORDS, index, 0) as usize >= self.lexeme_count {
+                return Err(SnapshotError::Corrupt("reading refers to an unknown lexeme"));
+            }
+
+            string(word(self.readings, READING_WORDS, index, 1), word(self.readings, READING_WORDS, index, 2))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A read-only lexicon backed directly by the bytes of a snapshot.
+#[derive(Clone, Copy)]
+pub struct LexiconSnapshot<'a> {
+    bytes: &'a [u8],
+    layout: Layout,
+}
+
+impl<'a> LexiconSnapshot<'a> {
+    /// Validates the snapshot in `bytes`. Nothing is copied or rebuilt.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<LexiconSnapshot<'a>, SnapshotError> {
+        let layout = Layout::validate(bytes)?;
+
+        Ok(LexiconSnapshot { bytes, layout })
+    }
+
+    pub fn len(&self) -> usize {
+        self.layout.lexeme_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layout.lexeme_count == 0
+    }
+
+    pub fn lexemes(&self) -> impl Iterator<Item = SnapshotLexeme<'a>> {
+        let snapshot = *self;
+
+        (0..self.len()).map(move |index| SnapshotLexeme { snapshot, index })
+    }
+
+    pub fn find_lexeme_by_id(&self, id: &str) -> Option<SnapshotLexeme<'a>> {
+        let index = self.lexeme_index(id)?;
+
+        Some(SnapshotLexeme { snapshot: *self, index })
+    }
+
+    pub fn find_lexeme_by_lemma(&self, lemma: &str, pos: Part) -> Option<SnapshotLexeme<'a>> {
+        self.find_lexeme_by_id(&lexeme_key(lemma, pos))
+    }
+
+    /// Returns every reading of a surface form, as `Lexicon::find_readings_by_form` does.
+    pub fn find_readings_by_form(&self, form: &str) -> Vec<FormReading> {
+        match self.entry_index(form) {
+            Some(entry) => self.entry_readings(entry),
+            None => Vec::new(),
+        }
+    }
+
+    fn lexeme_index(&self, id: &str) -> Option<usize> {
+        self.search(self.layout.lexeme_count, |index| self.field_str(self.layout.lexemes, LEXEME_WORDS, index, 0), id)
+    }
+
+    fn entry_index(&self, form: &str) -> Option<usize> {
+        self.search(self.layout.entry_count, |index| self.entry_form(index), form)
+    }
+
+    fn entry_form(&self, entry: usize) -> &'a str {
+        self.field_str(self.layout.entries, ENTRY_WORDS, entry, 0)
+    }
+
+    fn entry_readings(&self, entry: usize) -> Vec<FormReading> {
+        self.range(self.layout.entries, ENTRY_WORDS, entry, 2)
+            .map(|reading| {
+                let lexeme = self.field(self.layout.readings, READING_WORDS, reading, 0) as usize;
+
+                FormReading {
+                    lexeme: String::from(self.field_str(self.layout.lexemes, LEXEME_WORDS, lexeme, 0)),
+                    features: self.features(self.field_str(self.layout.readings, READING_WORDS, reading, 1)),
+                }
+            })
+            .collect()
+    }
+
+    /// Rebuilds a mutable `Lexicon` with the same lexemes and form index.
+    pub fn to_lexicon(&self) -> Lexicon {
+        let mut lexicon = Lexicon::new();
+
+        for lexeme in self.lexemes() {
+            lexicon.add_lexeme(lexeme.to_lexeme());
+        }
+
+        for entry in 0..self.layout.entry_count {
+            let form = self.field_str(self.layout.entries, ENTRY_WORDS, entry, 0);
+
+            for reading in self.range(self.layout.entries, ENTRY_WORDS, entry, 2) {
+                let lexeme = self.field(self.layout.readings, READING_WORDS, reading, 0) as usize;
+                let id = self.field_str(self.layout.lexemes, LEXEME_WORDS, lexeme, 0);
+                let features = self.features(self.field_str(self.layout.readings, READING_WORDS, reading, 1));
+
+                lexicon.index_form(form, id, features);
+            }
+        }
+
+        lexicon
+    }
+
+    fn search<F>(&self, count: usize, key: F, target: &str) -> Option<usize> where F: Fn(usize) -> &'a str {
+        let mut low = 0;
+        let mut high = count;
+
+        while low < high {
+            let middle = (low + high) / 2;
+
+            match key(middle).cmp(target) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(middle),
+            }
+        }
+
+        None
+    }
+
+    fn field(&self, table: usize, words: usize, index: usize, field: usize) -> u32 {
+        read_word(self.bytes, table + (index * words + field) * 4)
+    }
+
+    fn field_str(&self, table: usize, words: usize, index: usize, field: usize) -> &'a str {
+        self.string(self.field(table, words, index, field), self.field(table, words, index, field + 1))
+    }
+
+    fn range(&self, table: usize, words: usize, index: usize, field: usize) -> Range<usize> {
+        let start = self.field(table, words, index, field) as usize;
+
+        start..start + self.field(table, words, index, field + 1) as usize
+    }
+
+    fn string(&self, offset: u32, len: u32) -> &'a str {
+        let start = self.layout.strings + offset as usize;
+
+        // Every reference was checked when the snapshot was validated.
+        std::str::from_utf8(&self.bytes[start..start + len as usize]).unwrap_or("")
+    }
+
+    fn features(&self, text: &str) -> Features {
+        Features::parse(text).unwrap_or_default()
+    }
+}
+
+/// A lexeme inside a `LexiconSnapshot`. Its data is read from the snapshot on
+/// demand.
+#[derive(Clone, Copy)]
+pub struct SnapshotLexeme<'a> {
+    snapshot: LexiconSnapshot<'a>,
+    index: usize,
+}
+
+impl<'a> SnapshotLexeme<'a> {
+    pub fn id(&self) -> &'a str {
+        self.string(0)
+    }
+
+    pub fn lemma(&self) -> &'a str {
+        self.string(2)
+    }
+
+    pub fn pos(&self) -> Part {
+        let pos = std::char::from_u32(self.field(4)).unwrap_or('n');
+
+        parse_pos(&pos.to_string())
+    }
+
+    pub fn frequency(&self) -> u64 {
+        self.field(5) as u64 | (self.field(6) as u64) << 32
+    }
+
+    pub fn forms(&self) -> Vec<Form> {
+        let layout = &self.snapshot.layout;
+
+        self.range(7)
+            .map(|form| Form {
+                form: String::from(self.snapshot.field_str(layout.forms, FORM_WORDS, form, 0)),
+                features: self.snapshot.features(self.snapshot.field_str(layout.forms, FORM_WORDS, form, 2)),
+                sources: self.snapshot.range(layout.forms, FORM_WORDS, form, 4)
+                    .map(|text| String::from(self.snapshot.field_str(layout.texts, TEXT_WORDS, text, 0)))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    pub fn glosses(&self) -> Vec<&'a str> {
+        self.texts(9)
+    }
+
+    pub fn pronunciations(&self) -> Vec<&'a str> {
+        self.texts(11)
+    }
+
+    pub fn etymology(&self) -> Option<&'a str> {
+        if self.field(13) == 1 {
+            Some(self.string(14))
+        } else {
+            None
+        }
+    }
+
+    pub fn translations(&self) -> Vec<Translation> {
+        let layout = &self.snapshot.layout;
+
+        self.range(16)
+            .map(|translation| Translation {
+                language: String::from(self.snapshot.field_str(layout.translations, TRANSLATION_WORDS, translation, 0)),
+                word: String::from(self.snapshot.field_str(layout.translations, TRANSLATION_WORDS, translation, 2)),
+            })
+            .collect()
+    }
+
+    pub fn sources(&self) -> Vec<&'a str> {
+        self.texts(18)
+    }
+
+    /// Copies the lexeme out of the snapshot.
+    pub fn to_lexeme(&self) -> Lexeme {
+        let mut lexeme = Lexeme::new(self.lemma(), self.pos());
+
+        lexeme.forms = self.forms();
+        lexeme.frequency = self.frequency();
+        lexeme.glosses = self.glosses().into_iter().map(String::from).collect();
+        lexeme.pronunciations = self.pronunciations().into_iter().map(String::from).collect();
+        lexeme.etymology = self.etymology().map(String::from);
+        lexeme.translations = self.translations();
+        lexeme.sources = self.sources().into_iter().map(String::from).collect();
+
+        lexeme
+    }
+
+    fn field(&self, field: usize) -> u32 {
+        self.snapshot.field(self.snapshot.layout.lexemes, LEXEME_WORDS, self.index, field)
+    }
+
+    fn string(&self, field: usize) -> &'a str {
+        self.snapshot.field_str(self.snapshot.layout.lexemes, LEXEME_WORDS, self.index, field)
+    }
+
+    fn range(&self, field: usize) -> Range<usize> {
+        self.snapshot.range(self.snapshot.layout.lexemes, LEXEME_WORDS, self.index, field)
+    }
+
+    fn texts(&self, field: usize) -> Vec<&'a str> {
+        self.range(field)
+            .map(|text| self.snapshot.field_str(self.snapshot.layout.texts, TEXT_WORDS, text, 0))
+            .collect()
+    }
+}
+
+/// A snapshot file mapped into memory. Pages are only read when a lookup
+/// touches them.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct MappedSnapshot {
+    mmap: memmap2::Mmap,
+    layout: Layout,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl MappedSnapshot {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MappedSnapshot, SnapshotError> {
+        let file = std::fs::File::open(path)?;
+
+        // The mapping is only ever read. As with any memory map, the file must
+        // not be truncated or modified while it is mapped.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        let layout = Layout::validate(&mmap)?;
+
+        Ok(MappedSnapshot { mmap, layout })
+    }
+
+    pub fn snapshot(&self) -> LexiconSnapshot<'_> {
+        LexiconSnapshot {
+            bytes: &self.mmap,
+            layout: self.layout,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<MappedSnapshot> for Lexicon {
+    /// A lexicon backed by the mapping, as `Lexicon::from_snapshot` is by bytes.
+    fn from(mapped: MappedSnapshot) -> Lexicon {
+        Lexicon::from_backing(SnapshotBacking::new(Box::new(mapped.mmap), mapped.layout))
+    }
+}
+
+/// The snapshot behind a `Lexicon` that has not been changed since it was
+/// loaded, with the lexemes and readings read from it so far. They are read
+/// once and kept, so that the lexicon can lend them out like its own.
+pub(crate) struct SnapshotBacking {
+    bytes: Box<dyn AsRef<[u8]> + Send + Sync>,
+    layout: Layout,
+    lexemes: Vec<OnceLock<Box<Lexeme>>>,
+    readings: Vec<OnceLock<Vec<FormReading>>>,
+}
+
+impl SnapshotBacking {
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Result<SnapshotBacking, SnapshotError> {
+        let layout = Layout::validate(&bytes)?;
+
+        Ok(SnapshotBacking::new(Box::new(bytes), layout))
+    }
+
+    fn new(bytes: Box<dyn AsRef<[u8]> + Send + Sync>, layout: Layout) -> SnapshotBacking {
+        SnapshotBacking {
+            bytes,
+            layout,
+            lexemes: (0..layout.lexeme_count).map(|_| OnceLock::new()).collect(),
+            readings: (0..layout.entry_count).map(|_| OnceLock::new()).collect(),
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> LexiconSnapshot<'_> {
+        LexiconSnapshot {
+            bytes: self.bytes.as_ref().as_ref(),
+            layout: self.layout,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.layout.lexeme_count
+    }
+
+    pub(crate) fn find_lexeme_by_id(&self, id: &str) -> Option<&Lexeme> {
+        let index = self.snapshot().lexeme_index(id)?;
+
+        Some(self.lexeme(index))
+    }
+
+    pub(crate) fn find_readings_by_form(&self, form: &str) -> &[FormReading] {
+        match self.snapshot().entry_index(form) {
+            Some(entry) => self.readings(entry),
+            None => &[],
+        }
+    }
+
+    pub(crate) fn lexemes(&self) -> impl Iterator<Item = &Lexeme> {
+        (0..self.layout.lexeme_count).map(move |index| self.lexeme(index))
+    }
+
+    pub(crate) fn forms(&self) -> impl Iterator<Item = (&str, &[FormReading])> {
+        (0..self.layout.entry_count).map(move |entry| (self.snapshot().entry_form(entry), self.readings(entry)))
+    }
+
+    fn lexeme(&self, index: usize) -> &Lexeme {
+        self.lexemes[index].get_or_init(|| Box::new(SnapshotLexeme { snapshot: self.snapshot(), index }.to_lexeme()))
+    }
+
+    fn readings(&self, entry: usize) -> &[FormReading] {
+        self.readings[entry].get_or_init(|| self.snapshot().entry_readings(entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon() -> Lexicon {
+        let mut lexicon = Lexicon::new();
+
+        let mut lexeme = Lexeme::new("zoeken", Part::Verb);
+        lexeme.add_form("zocht", Features::new().with("Number", "Sing").with("Tense", "Past"));
+        lexeme.add_form("gezocht", Features::new().with("VerbForm", "Part"));
+        lexeme.frequency = 5_000_000_000;
+        lexeme.glosses.push(String::from("to search"));
+        lexeme.pronunciations.push(String::from("/ˈzu.kə(n)/"));
+        lexeme.etymology = Some(String::from("From Middle Dutch soeken."));
+        lexeme.translations.push(Translation { language: String::from("en"), word: String::from("search") });
+        lexeme.add_source("wiktionary");
+
+        lexicon.add_lexeme(lexeme);
+        lexicon.add_lexeme(Lexeme::new("boek", Part::Noun));
+        lexicon.index_forms(&|lexeme: &Lexeme| vec![Form::new(&format!("{}en", lexeme.lemma), Features::new().with("Number", "Plur"))]);
+
+        lexicon
+    }
+
+    #[test]
+    fn it_finds_lexemes_in_a_snapshot() {
+        let bytes = write_snapshot(&lexicon());
+        let snapshot = LexiconSnapshot::from_bytes(&bytes).unwrap();
+
+        let lexeme = snapshot.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap();
+
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(lexeme.lemma(), "zoeken");
+        assert_eq!(lexeme.pos(), Part::Verb);
+        assert_eq!(lexeme.frequency(), 5_000_000_000);
+        assert_eq!(lexeme.forms().len(), 2);
+        assert_eq!(lexeme.glosses(), vec!["to search"]);
+        assert_eq!(lexeme.pronunciations(), vec!["/ˈzu.kə(n)/"]);
+        assert_eq!(lexeme.etymology(), Some("From Middle Dutch soeken."));
+        assert_eq!(lexeme.translations()[0].word, "search");
+        assert_eq!(lexeme.sources(), vec!["wiktionary"]);
+        assert_eq!(lexeme.forms()[0].sources, vec!["wiktionary"]);
+        assert!(snapshot.find_lexeme_by_lemma("boek", Part::Verb).is_none());
+    }
+
+    #[test]
+    fn it_finds_forms_in_a_snapshot() {
+        let bytes = write_snapshot(&lexicon());
+        let snapshot = LexiconSnapshot::from_bytes(&bytes).unwrap();
+
+        assert_eq!(snapshot.find_readings_by_form("zocht")[0].features.get("Tense"), Some("Past"));
+        assert_eq!(snapshot.find_readings_by_form("boeken")[0].lexeme, "boek:n");
+        assert!(snapshot.find_readings_by_form("boeks").is_empty());
+    }
+
+    #[test]
+    fn it_rebuilds_the_lexicon() {
+        let bytes = write_snapshot(&lexicon());
+        let lexicon = LexiconSnapshot::from_bytes(&bytes).unwrap().to_lexicon();
+
+        assert_eq!(lexicon.len(), 2);
+        assert_eq!(lexicon.find_readings_by_form("boeken").len(), 1);
+        assert_eq!(lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap().translations.len(), 1);
+    }
+
+    #[test]
+    fn it_rejects_other_files() {
+        assert!(matches!(LexiconSnapshot::from_bytes(b"not a snapshot"), Err(SnapshotError::InvalidMagic)));
+    }
+
+    #[test]
+    fn it_rejects_other_versions() {
+        let mut bytes = write_snapshot(&lexicon());
+        bytes[4] = 99;
+
+        assert!(matches!(LexiconSnapshot::from_bytes(&bytes), Err(SnapshotError::UnsupportedVersion(99))));
+    }
+
+    #[test]
+    fn it_rejects_truncated_snapshots() {
+        let bytes = write_snapshot(&lexicon());
+
+        assert!(matches!(LexiconSnapshot::from_bytes(&bytes[..bytes.len() - 4]), Err(SnapshotError::Corrupt(_))));
+    }
+
+    #[test]
+    fn it_maps_snapshot_files() {
+        let path = std::env::temp_dir().join(format!("quenya-snapshot-{}.qlx", std::process::id()));
+
+        save_snapshot(&lexicon(), &path).unwrap();
+
+        let mapped = MappedSnapshot::open(&path).unwrap();
+
+        assert!(mapped.snapshot().find_lexeme_by_lemma("boek", Part::Noun).is_some());
+
+        let lexicon = Lexicon::from(mapped);
+
+        assert_eq!(lexicon.len(), 2);
+        assert_eq!(lexicon.find_readings_by_form("zocht")[0].lexeme, "zoeken:v");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn it_backs_a_lexicon_with_a_snapshot() {
+        let mut lexicon = Lexicon::from_snapshot(write_snapshot(&lexicon())).unwrap();
+
+        assert_eq!(lexicon.len(), 2);
+        assert_eq!(lexicon.find_lexeme_by_lemma("zoeken", Part::Verb).unwrap().frequency, 5_000_000_000);
+        assert_eq!(lexicon.find_readings_by_form("boeken").len(), 1);
+        assert_eq!(lexicon.lexemes().count(), 2);
+        assert_eq!(lexicon.forms().count(), 6);
+
+        lexicon.add_form("boek", Part::Noun, "boekje", Features::new().with("Degree", "Dim"));
+
+        assert_eq!(lexicon.len(), 2);
+        assert_eq!(lexicon.find_readings_by_form("zocht").len(), 1);
+        assert_eq!(lexicon.find_readings_by_form("boekje").len(), 1);
+    }
+}
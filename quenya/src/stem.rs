@@ -0,0 +1,184 @@
+// Rule-based stemming for search indexing. Unlike lemmatisation, stemming
+// needs no lexicon: words are cut down to a stem that is shared by their
+// inflected and derived forms but need not be a word itself, as `generous`
+// for `generously` and `generosity`. English, Dutch and Arabic use the
+// Snowball algorithms; Japanese has a light stemmer for katakana loanwords.
+
+use rust_stemmers::{Algorithm as Snowball, Stemmer as SnowballStemmer};
+use crate::omni::Omniglot;
+use crate::pipeline::Stage;
+use crate::segmentation::SegmentKind;
+use crate::text::{AnnotatedText, STEM_LAYER, Span};
+
+/// The prolonged sound mark that ends many katakana loanwords, as in `コンピューター`.
+const PROLONGED_SOUND_MARK: char = 'ー';
+
+/// The shortest katakana word the katakana stemmer shortens.
+const MIN_KATAKANA_LENGTH: usize = 4;
+
+/// A stemming algorithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// The Snowball English (Porter2) stemmer.
+    English,
+    /// The Snowball Dutch stemmer.
+    Dutch,
+    /// The Snowball Arabic stemmer.
+    Arabic,
+    /// Drops the final prolonged sound mark of katakana words, so that
+    /// `コンピューター` and `コンピュータ` share a stem.
+    Katakana,
+}
+
+impl Algorithm {
+    /// The algorithm for an ISO 639-3 language code.
+    pub fn for_language(code: &str) -> Option<Algorithm> {
+        match code {
+            "eng" => Some(Algorithm::English),
+            "nld" => Some(Algorithm::Dutch),
+            "ara" => Some(Algorithm::Arabic),
+            "jpn" => Some(Algorithm::Katakana),
+            _ => None,
+        }
+    }
+
+    /// The stem of a word. Words are lowercased first, as the Snowball
+    /// algorithms expect.
+    pub fn stem(&self, word: &str) -> String {
+        let snowball = match self {
+            Algorithm::English => Snowball::English,
+            Algorithm::Dutch => Snowball::Dutch,
+            Algorithm::Arabic => Snowball::Arabic,
+            Algorithm::Katakana => return stem_katakana(word),
+        };
+
+        SnowballStemmer::create(snowball).stem(&word.to_lowercase()).into_owned()
+    }
+}
+
+/// Drops the prolonged sound mark at the end of a katakana word of at least
+/// four characters, so that the spellings with and without it meet. Other
+/// words are left as they are.
+pub fn stem_katakana(word: &str) -> String {
+    let katakana = !word.is_empty() && word.chars().all(is_katakana);
+
+    match word.strip_suffix(PROLONGED_SOUND_MARK) {
+        Some(stem) if katakana && word.chars().count() >= MIN_KATAKANA_LENGTH => String::from(stem),
+        _ => String::from(word),
+    }
+}
+
+fn is_katakana(c: char) -> bool {
+    ('\u{30A0}'..='\u{30FF}').contains(&c)
+}
+
+/// Adds a span with the stem of every word to the stem layer, using the
+/// stemmer of the word's language module.
+pub struct Stemmer;
+
+impl Stage for Stemmer {
+    fn name(&self) -> &str {
+        "stemmer"
+    }
+
+    fn process(&self, omni: &Omniglot, text: &mut AnnotatedText) {
+        let mut spans: Vec<Span> = Vec::new();
+
+        for token in &text.tokens {
+            if token.kind != SegmentKind::Word || token.start == token.end {
+                continue;
+            }
+
+            let language = token.language.as_deref().and_then(|code| omni.get(code).ok());
+
+            if let Some(stem) = language.and_then(|language| language.stem(text.analysis_form(token))) {
+                spans.push(Span::new(token.start, token.end, &stem));
+            }
+        }
+
+        for span in spans {
+            text.add_span(STEM_LAYER, span);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+    use crate::pipeline::{Pipeline, StageRegistry};
+
+    #[test]
+    fn it_stems_english() {
+        let words = [
+            ("consign", "consign"), ("consigned", "consign"), ("consigning", "consign"), ("consignment", "consign"),
+            ("generously", "generous"), ("knightly", "knight"), ("civilized", "civil"), ("reassuringly", "reassur"),
+            ("swarthy", "swarthi"), ("twitchings", "twitch"), ("Washings", "wash"),
+        ];
+
+        for (word, stem) in &words {
+            assert_eq!(Algorithm::English.stem(word), *stem);
+        }
+    }
+
+    #[test]
+    fn it_stems_dutch() {
+        let words = [
+            ("lichaamsziek", "lichaamsziek"), ("lichamelijk", "licham"), ("lichamelijke", "licham"),
+            ("lichamelijkheden", "licham"), ("lichamen", "licham"),
+        ];
+
+        for (word, stem) in &words {
+            assert_eq!(Algorithm::Dutch.stem(word), *stem);
+        }
+    }
+
+    #[test]
+    fn it_stems_arabic() {
+        let words = [("فتفاعلية", "تفاعل"), ("لتوفيرهن", "لتوفير"), ("وأسندتك", "اسند"), ("وسأقلبها", "اقلب")];
+
+        for (word, stem) in &words {
+            assert_eq!(Algorithm::Arabic.stem(word), *stem);
+        }
+    }
+
+    #[test]
+    fn it_stems_katakana_loanwords() {
+        assert_eq!(stem_katakana("コンピューター"), "コンピュータ");
+        assert_eq!(stem_katakana("コーヒー"), "コーヒ");
+        assert_eq!(stem_katakana("カー"), "カー");
+        assert_eq!(stem_katakana("ありがとー"), "ありがとー");
+        assert_eq!(Algorithm::for_language("jpn").unwrap().stem("サーバー"), "サーバ");
+    }
+
+    #[test]
+    fn it_adds_a_stem_layer() {
+        let pipeline = Pipeline::from_config("tokenizer\nstemmer\n", &StageRegistry::new()).unwrap();
+
+        let annotated = pipeline.run(&Omniglot::builtin(), "She was washing cars.");
+        let stems: Vec<&str> = annotated.layer(STEM_LAYER).unwrap().spans().iter().map(|span| span.label.as_str()).collect();
+
+        assert_eq!(stems, vec!["she", "was", "wash", "car"]);
+    }
+
+    /// Checks the stemmers against samples of the Snowball vocabularies in
+    /// `tests/data/stem`, described in its README.
+    #[test]
+    fn it_matches_the_snowball_vocabularies() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/stem");
+
+        for (directory, algorithm) in &[("english", Algorithm::English), ("dutch", Algorithm::Dutch), ("arabic", Algorithm::Arabic)] {
+            let directory = root.join(directory);
+
+            let words = fs::read_to_string(directory.join("voc.txt")).unwrap();
+            let stems = fs::read_to_string(directory.join("output.txt")).unwrap();
+
+            assert_eq!(words.lines().count(), stems.lines().count());
+
+            for (word, stem) in words.lines().zip(stems.lines()) {
+                assert_eq!(algorithm.stem(word), stem, "stem of '{}'", word);
+            }
+        }
+    }
+}
@@ -1,8 +1,370 @@
-pub struct Token<'a> {
-    raw: &'a str,
-    lemma: &'static str,
+use wn::pos::Part;
+use crate::features::Features;
+use crate::language::Analysis;
+use crate::normalize::NormalizedText;
+use crate::segmentation::SegmentKind;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The layer of sentence spans. Sentences read from CoNLL-U are labelled
+/// with their comments, one per line.
+pub const SENTENCE_LAYER: &str = "sentences";
+
+/// The layer of multiword tokens, such as an Arabic word with its clitics.
+/// The syntactic words of a multiword token are tokens that share its span.
+pub const MULTIWORD_LAYER: &str = "multiword tokens";
+
+/// The layer of word stems, labelled with the stem of the word they span.
+pub const STEM_LAYER: &str = "stems";
+
+/// A word, number or punctuation mark of an annotated text. Tokens without
+/// width are empty nodes, words that are understood but not written.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Token {
+    /// Byte offset of the token in the text.
+    pub start: usize,
+    pub end: usize,
+    pub kind: SegmentKind,
+    /// The form of the word if it differs from the text it covers, as for the
+    /// words of a multiword token and for empty nodes.
+    pub form: Option<String>,
+    pub language: Option<String>,
+    pub lemma: Option<String>,
+    /// The Universal Dependencies part-of-speech tag.
+    pub upos: Option<String>,
+    /// A language-specific part-of-speech tag.
+    pub xpos: Option<String>,
+    pub features: Features,
+    /// The CoNLL-U ID of the head within the sentence, with 0 for the root.
+    pub head: Option<usize>,
+    pub deprel: Option<String>,
+    /// Enhanced dependencies in CoNLL-U notation.
+    pub deps: Option<String>,
+    /// Other CoNLL-U MISC entries.
+    pub misc: Option<String>,
+    /// The other analyses the language module found for the token.
+    pub alternatives: Vec<Analysis>,
+}
+
+impl Token {
+    pub fn new(start: usize, end: usize, kind: SegmentKind) -> Token {
+        Token {
+            start,
+            end,
+            kind,
+            form: None,
+            language: None,
+            lemma: None,
+            upos: None,
+            xpos: None,
+            features: Features::new(),
+            head: None,
+            deprel: None,
+            deps: None,
+            misc: None,
+            alternatives: Vec::new(),
+        }
+    }
+
+    /// Takes the lemma, part of speech and features from the first analysis
+    /// and keeps the others as alternatives.
+    pub fn assign(&mut self, mut analyses: Vec<Analysis>) {
+        if analyses.is_empty() {
+            return;
+        }
+
+        let analysis = analyses.remove(0);
+
+        self.lemma = Some(analysis.lemma);
+        self.upos = analysis.pos.map(|pos| String::from(upos_for_part(pos)));
+        self.xpos = analysis.tag;
+        self.features = analysis.features;
+        self.alternatives = analyses;
+    }
+}
+
+/// A labelled stretch of the text, such as a named entity, a multi-word
+/// expression or a sense. Spans refer to the text by byte offset.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub label: String,
+    pub features: Features,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, label: &str) -> Span {
+        Span {
+            start,
+            end,
+            label: String::from(label),
+            features: Features::new(),
+        }
+    }
+
+    /// Whether the span includes all of `start..end`.
+    pub fn covers(&self, start: usize, end: usize) -> bool {
+        self.start <= start && end <= self.end
+    }
+
+    /// Whether the span shares at least one byte with `start..end`.
+    pub fn overlaps(&self, start: usize, end: usize) -> bool {
+        self.start < end && start < self.end
+    }
+}
+
+/// A named set of spans, kept in text order. Spans of one layer may overlap.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Layer {
+    name: String,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_spans"))]
+    spans: Vec<Span>,
+}
+
+/// Reads the spans of a layer and puts them back in text order, as `Layer::add` keeps them.
+#[cfg(feature = "serde")]
+fn deserialize_spans<'de, D>(deserializer: D) -> Result<Vec<Span>, D::Error> where D: serde::Deserializer<'de> {
+    let mut spans = Vec::<Span>::deserialize(deserializer)?;
+    spans.sort_by_key(|span| (span.start, span.end));
+
+    Ok(spans)
+}
+
+impl Layer {
+    pub fn new(name: &str) -> Layer {
+        Layer {
+            name: String::from(name),
+            spans: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The spans ordered by start and then by end offset.
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    pub fn add(&mut self, span: Span) {
+        let index = self.spans.partition_point(|existing| (existing.start, existing.end) <= (span.start, span.end));
+
+        self.spans.insert(index, span);
+    }
+
+    /// The spans that include all of `start..end`.
+    pub fn covering(&self, start: usize, end: usize) -> Vec<&Span> {
+        self.spans.iter()
+            .take_while(|span| span.start <= start)
+            .filter(|span| span.covers(start, end))
+            .collect()
+    }
+
+    /// The spans that lie within `start..end`.
+    pub fn within(&self, start: usize, end: usize) -> Vec<&Span> {
+        let first = self.spans.partition_point(|span| span.start < start);
+
+        self.spans[first..].iter()
+            .take_while(|span| span.start < end)
+            .filter(|span| span.end <= end)
+            .collect()
+    }
+}
+
+/// A text with standoff annotation: its tokens, and named layers of spans
+/// that refer back to the text by offset.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnnotatedText {
+    pub text: String,
+    /// The tokens in text order. Only the words of a multiword token overlap.
+    pub tokens: Vec<Token>,
+    layers: Vec<Layer>,
+    /// The text after normalisation, if a normalizer ran. Offsets of tokens
+    /// and spans always refer to `text`.
+    pub normalized: Option<NormalizedText>,
+}
+
+impl AnnotatedText {
+    pub fn new(text: &str) -> AnnotatedText {
+        AnnotatedText {
+            text: String::from(text),
+            tokens: Vec::new(),
+            layers: Vec::new(),
+            normalized: None,
+        }
+    }
+
+    /// The text a token covers.
+    pub fn token_text(&self, token: &Token) -> &str {
+        &self.text[token.start..token.end]
+    }
+
+    /// The form of a token: its own form if it has one, or else its text.
+    pub fn word_form<'a>(&'a self, token: &'a Token) -> &'a str {
+        match &token.form {
+            Some(form) => form,
+            None => self.token_text(token),
+        }
+    }
+
+    /// The form a language module should analyse: the word form, normalised
+    /// if the text was.
+    pub fn analysis_form<'a>(&'a self, token: &'a Token) -> &'a str {
+        match (&token.form, &self.normalized) {
+            (Some(form), _) => form,
+            (None, Some(normalized)) => normalized.slice(token.start, token.end),
+            (None, None) => self.token_text(token),
+        }
+    }
+
+    pub fn span_text(&self, span: &Span) -> &str {
+        &self.text[span.start..span.end]
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Returns the layer with the given name, adding it if there is none.
+    pub fn layer_mut(&mut self, name: &str) -> &mut Layer {
+        let index = match self.layers.iter().position(|layer| layer.name == name) {
+            Some(index) => index,
+            None => {
+                self.layers.push(Layer::new(name));
+
+                self.layers.len() - 1
+            }
+        };
+
+        &mut self.layers[index]
+    }
+
+    /// Adds a span to the named layer.
+    pub fn add_span(&mut self, layer: &str, span: Span) {
+        self.layer_mut(layer).add(span);
+    }
+
+    /// The tokens that lie within `start..end`.
+    pub fn tokens_in(&self, start: usize, end: usize) -> &[Token] {
+        let first = self.tokens.partition_point(|token| token.start < start);
+        let last = self.tokens.partition_point(|token| token.end <= end);
+
+        if last <= first {
+            return &[];
+        }
+
+        &self.tokens[first..last]
+    }
+
+    /// The tokens within a span.
+    pub fn tokens_of(&self, span: &Span) -> &[Token] {
+        self.tokens_in(span.start, span.end)
+    }
+
+    /// The spans of every layer that cover a token, with the name of their
+    /// layer.
+    pub fn spans_covering(&self, token: &Token) -> Vec<(&str, &Span)> {
+        self.layers.iter()
+            .flat_map(|layer| layer.covering(token.start, token.end).into_iter().map(move |span| (layer.name(), span)))
+            .collect()
+    }
+}
+
+pub fn upos_for_part(pos: Part) -> &'static str {
+    match pos {
+        Part::Noun => "NOUN",
+        Part::Verb => "VERB",
+        Part::Adjective => "ADJ",
+        Part::Adverb => "ADV",
+    }
 }
 
-pub struct AnnotatedText<'a> {
-    tokens: &'a [Token<'a>],
+/// The part of speech of a Universal Dependencies tag, if it has one.
+pub fn part_for_upos(upos: &str) -> Option<Part> {
+    match upos {
+        "NOUN" | "PROPN" => Some(Part::Noun),
+        "VERB" | "AUX" => Some(Part::Verb),
+        "ADJ" => Some(Part::Adjective),
+        "ADV" => Some(Part::Adverb),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_assigns_the_first_analysis() {
+        let mut token = Token::new(0, 5, SegmentKind::Word);
+
+        token.assign(vec![
+            Analysis::new("work", Some(Part::Verb), Features::parse("Number=Sing|Person=3").unwrap()),
+            Analysis::new("work", Some(Part::Noun), Features::parse("Number=Plur").unwrap()),
+        ]);
+
+        assert_eq!(token.lemma.as_deref(), Some("work"));
+        assert_eq!(token.upos.as_deref(), Some("VERB"));
+        assert_eq!(token.features.get("Person"), Some("3"));
+        assert_eq!(token.alternatives.len(), 1);
+    }
+
+    fn annotated() -> AnnotatedText {
+        let mut annotated = AnnotatedText::new("I flew to New York City");
+
+        for (start, end) in [(0, 1), (2, 6), (7, 9), (10, 13), (14, 18), (19, 23)] {
+            annotated.tokens.push(Token::new(start, end, SegmentKind::Word));
+        }
+
+        annotated.add_span("entities", Span::new(10, 23, "GPE"));
+        annotated.add_span("entities", Span::new(10, 18, "GPE"));
+        annotated.add_span("expressions", Span::new(2, 9, "flew to"));
+
+        annotated
+    }
+
+    #[test]
+    fn it_keeps_overlapping_spans_in_order() {
+        let annotated = annotated();
+        let entities = annotated.layer("entities").unwrap();
+
+        assert_eq!(annotated.layers().len(), 2);
+        assert_eq!(annotated.span_text(&entities.spans()[0]), "New York");
+        assert_eq!(annotated.span_text(&entities.spans()[1]), "New York City");
+        assert_eq!(annotated.layer("senses"), None);
+    }
+
+    #[test]
+    fn it_finds_tokens_inside_spans() {
+        let annotated = annotated();
+        let city = &annotated.layer("entities").unwrap().spans()[1];
+
+        let tokens: Vec<&str> = annotated.tokens_of(city).iter().map(|token| annotated.token_text(token)).collect();
+
+        assert_eq!(tokens, vec!["New", "York", "City"]);
+        assert_eq!(annotated.tokens_in(3, 5).is_empty(), true);
+    }
+
+    #[test]
+    fn it_finds_spans_covering_tokens() {
+        let annotated = annotated();
+
+        let york: Vec<&str> = annotated.spans_covering(&annotated.tokens[4]).iter().map(|(_, span)| annotated.span_text(span)).collect();
+        let to: Vec<&str> = annotated.spans_covering(&annotated.tokens[2]).iter().map(|(layer, _)| *layer).collect();
+
+        assert_eq!(york, vec!["New York", "New York City"]);
+        assert_eq!(to, vec!["expressions"]);
+        assert_eq!(annotated.layer("entities").unwrap().within(10, 20).len(), 1);
+    }
 }
@@ -1 +1,21 @@
+use crate::language::{Capability, Language};
+use crate::uxy::xiinthlea::from_srx;
+
 pub mod xiinthlea;
+
+/// Xiinthlea, which can be transliterated from its SRX notation.
+pub struct Xiinthlea;
+
+impl Language for Xiinthlea {
+    fn code(&self) -> &str {
+        "uxy"
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[Capability::Tokenize, Capability::Transliterate]
+    }
+
+    fn transliterate(&self, text: &str) -> Option<String> {
+        Some(from_srx(text))
+    }
+}
@@ -2,7 +2,104 @@ use wasm_bindgen::prelude::*;
 
 extern crate quenya;
 
+/// One reading of a token, as in `quenya::language::Analysis`.
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct Analysis {
+    pub lemma: String,
+    pub upos: Option<String>,
+    /// Features in Universal Dependencies notation, such as `Number=Sing`.
+    pub features: String,
+}
+
+/// A token of `quenya::text::AnnotatedText`. Offsets are in bytes of the
+/// UTF-8 text.
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct Token {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub language: Option<String>,
+    pub lemma: Option<String>,
+    pub upos: Option<String>,
+    pub features: String,
+    pub alternatives: Vec<Analysis>,
+}
+
+/// A span of a standoff layer, as in `quenya::text::Span`.
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub label: String,
+    pub features: String,
+}
+
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct Layer {
+    pub name: String,
+    pub spans: Vec<Span>,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct AnnotatedText {
+    pub text: String,
+    pub tokens: Vec<Token>,
+    pub layers: Vec<Layer>,
+}
+
 #[wasm_bindgen]
-pub fn annotate(text: &str) -> String {
-    String::from(quenya::annotate(text))
+pub fn annotate(text: &str) -> AnnotatedText {
+    let annotated = quenya::annotate(text);
+
+    let tokens = annotated.tokens.iter()
+        .map(|token| Token {
+            start: token.start,
+            end: token.end,
+            text: String::from(annotated.token_text(token)),
+            language: token.language.clone(),
+            lemma: token.lemma.clone(),
+            upos: token.upos.clone(),
+            features: token.features.to_string(),
+            alternatives: token.alternatives.iter()
+                .map(|analysis| Analysis {
+                    lemma: analysis.lemma.clone(),
+                    upos: analysis.pos.map(|pos| String::from(quenya::text::upos_for_part(pos))),
+                    features: analysis.features.to_string(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    let layers = annotated.layers().iter()
+        .map(|layer| Layer {
+            name: String::from(layer.name()),
+            spans: layer.spans().iter()
+                .map(|span| Span {
+                    start: span.start,
+                    end: span.end,
+                    label: span.label.clone(),
+                    features: span.features.to_string(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    AnnotatedText {
+        text: annotated.text,
+        tokens,
+        layers,
+    }
+}
+
+/// Annotates text and returns the JSON of `quenya::text::AnnotatedText`, for
+/// callers that want every field rather than the summary of `annotate`.
+#[wasm_bindgen(js_name = annotateJson)]
+pub fn annotate_json(text: &str) -> Result<String, JsError> {
+    let annotated = quenya::try_annotate(text).map_err(|error| JsError::new(&error.to_string()))?;
+
+    quenya::json::to_json(&annotated).map_err(|error| JsError::new(&error.to_string()))
 }
@@ -4,7 +4,11 @@ use crate::pos::{Part, parse_pos};
 use std::fs::File;
 use std::io::{BufReader, BufRead};
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Lemma {
     pub language: String,
     offset: String,
@@ -13,6 +17,7 @@ pub struct Lemma {
 }
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Definition {
     language: String,
     offset: String,
@@ -22,6 +27,7 @@ pub struct Definition {
 }
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Example {
     language: String,
     offset: String,
@@ -29,7 +35,11 @@ pub struct Example {
     example: String,
 }
 
+/// A line of an OMW file. In JSON the kind of line is given by `"type"`:
+/// `"definition"`, `"example"` or `"lemma"`.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "lowercase"))]
 pub enum Entry {
     Definition(Definition),
     Example(Example),
@@ -37,44 +47,46 @@ pub enum Entry {
 }
 
 fn parse_multilingual_wordnet_line(line: &str) -> Entry {
+    try_parse_multilingual_wordnet_line(line).unwrap()
+}
+
+/// Parses a line of an OMW file, or returns `None` if it is not a lemma,
+/// definition or example line.
+pub fn try_parse_multilingual_wordnet_line(line: &str) -> Option<Entry> {
     lazy_static! {
         static ref MULTILINGUAL_WORDNET_LINE_REGEX: Regex = Regex::new(r"^(?P<offset>\d{8})-(?P<pos>[nvars])\s(?P<language>\w{3}):(?P<type>lemma|def|exe)\s((?P<sid>\d{1})\s)?(?P<content>.+)\s*$").unwrap();
     }
-    
-    let captures = MULTILINGUAL_WORDNET_LINE_REGEX.captures(line).unwrap();
 
-    let language = captures.name("language").unwrap().as_str().to_string();
-    let offset = captures.name("offset").unwrap().as_str().to_string();
-    let pos = parse_pos(&captures["pos"]);
-    let content = captures.name("content").unwrap().as_str().to_string();
+    let captures = MULTILINGUAL_WORDNET_LINE_REGEX.captures(line)?;
 
-    let line_type = &captures["type"];
+    let language = captures.name("language")?.as_str().to_string();
+    let offset = captures.name("offset")?.as_str().to_string();
+    let pos = parse_pos(&captures["pos"]);
+    let content = captures.name("content")?.as_str().to_string();
 
-    if line_type == "lemma" {
-        Entry::Lemma(Lemma {
-            language: language,
+    let entry = match &captures["type"] {
+        "lemma" => Entry::Lemma(Lemma {
+            language,
             offset,
             pos,
             lemma: content,
-        })
-    } else if line_type == "def" {
-        Entry::Definition(Definition {
+        }),
+        "def" => Entry::Definition(Definition {
             language,
             offset,
             pos,
-            sid: captures.name("sid").unwrap().as_str().parse::<i8>().unwrap(),
+            sid: captures.name("sid")?.as_str().parse::<i8>().ok()?,
             definition: content,
-        })
-    } else if line_type == "exe" {
-        Entry::Example(Example {
+        }),
+        _ => Entry::Example(Example {
             language,
             offset,
             pos,
             example: content,
-        })
-    } else {
-        panic!()
-    }
+        }),
+    };
+
+    Some(entry)
 }
 
 pub fn parse_multilingual_wordnet_file(file: File) -> Vec<Entry> {
@@ -170,6 +182,13 @@ mod tests {
         }))
     }
 
+    #[test]
+    fn it_rejects_malformed_lines() {
+        assert!(try_parse_multilingual_wordnet_line("00018158-v\tind:lemma\tmembubung\n").is_some());
+        assert_eq!(try_parse_multilingual_wordnet_line("not a wordnet line"), None);
+        assert_eq!(try_parse_multilingual_wordnet_line("00006024-n\tind:def\tno sense id"), None);
+    }
+
     #[test]
     fn it_reads_indonesian_wordnet_file() {
         let path = Path::new("C:\\Users\\Dennis\\Dev\\Quenya\\dict\\msa\\wn-data-ind-test.tab");
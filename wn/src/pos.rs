@@ -1,4 +1,10 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A part of speech. In JSON it is written in lowercase, such as `"noun"`.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Part {
     Adjective,
     Adverb,
@@ -8,7 +14,7 @@ pub enum Part {
 
 pub fn parse_pos(pos: &str) -> Part {
     match pos {
-        "a" => Part::Adjective,
+        "a" | "s" => Part::Adjective,
         "n" => Part::Noun,
         "r" => Part::Adverb,
         "v" => Part::Verb,